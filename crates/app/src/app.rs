@@ -2,13 +2,13 @@ use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::{channel, Receiver, Sender};
 
-pub use config::{AppConfig, DbgConfig, RomConfig};
+pub use config::{AppConfig, CheatConfig, DbgConfig, RomConfig, TraceConfig};
 pub(crate) use render::{Shelf, ShelfItem};
 use shared::egui::{Context, TextureHandle, TextureId};
 use shared::rom::Rom;
 use watcher::FileWatcher;
 
-use crate::emulator::State;
+use crate::state::State;
 
 mod config;
 mod watcher;
@@ -66,21 +66,16 @@ impl<I: ShelfItem + 'static> Storage<I> {
         let sender = self.sender.clone();
         let walk = walkdir::WalkDir::new(&root);
         std::thread::spawn(move || {
-            for path in walk.max_depth(10).follow_links(true) {
-                match path {
-                    Ok(entry) => {
-                        if !entry.file_type().is_file() { continue; }
-                        let ext = entry.path().extension().and_then(|x| x.to_str());
-                        let key = entry.path().to_str();
-                        if ext.is_none() || key.is_none() { continue; };
-                        if I::extensions().contains(&ext.unwrap()) {
-                            let key = key.unwrap().to_string();
-                            if let Ok(item) = I::load_from_path(entry.path()) {
-                                sender.send((root.clone(), key, item)).ok();
-                            }
-                        }
+            for entry in walk.max_depth(10).follow_links(true).into_iter().flatten() {
+                if !entry.file_type().is_file() { continue; }
+                let ext = entry.path().extension().and_then(|x| x.to_str());
+                let key = entry.path().to_str();
+                if ext.is_none() || key.is_none() { continue; };
+                if I::extensions().contains(&ext.unwrap()) {
+                    let key = key.unwrap().to_string();
+                    if let Ok(item) = I::load_from_path(entry.path()) {
+                        sender.send((root.clone(), key, item)).ok();
                     }
-                    _ => {}
                 }
             }
         });
@@ -93,7 +88,7 @@ impl<I: ShelfItem + 'static> Storage<I> {
                 Event::Delete(path) => I::remove(self, path),
                 Event::Reload(path) => {
                     if let Some(shelf) = self.shelves.iter_mut()
-                        .find(|x| x.has_root(&path)) {
+                        .find(|x| x.has_root(path)) {
                         shelf.clear();
                     }
                     self.search(path.clone());
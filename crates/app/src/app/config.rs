@@ -4,7 +4,9 @@ use serde::{Deserialize, Serialize};
 
 use shared::audio_settings::AudioSettings;
 use shared::breakpoints::Breakpoint;
+use shared::cheats::Cheat;
 use shared::input::Keybindings;
+use shared::rom::Rom;
 
 use crate::emulator::EmuSettings;
 use crate::settings::Mode;
@@ -24,6 +26,66 @@ pub struct DbgConfig {
     pub breaks: Vec<Breakpoint>,
     #[serde(default)]
     pub and: bool,
+    #[serde(default)]
+    pub trace: Option<TraceConfig>,
+    /// localhost port of the gdb remote protocol server, off when `None`
+    #[serde(default)]
+    pub gdb: Option<u16>,
+    /// file the logpoint messages are appended to
+    #[serde(default)]
+    pub logpoints: Option<PathBuf>,
+}
+
+/// gameboy-doctor instruction trace, written to `path` for every game started.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TraceConfig {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub start: Option<u16>,
+    #[serde(default)]
+    pub stop: Option<u16>,
+    #[serde(default)]
+    pub max: Option<usize>,
+    /// LY reads 0x90, as expected by gameboy-doctor
+    #[serde(default)]
+    pub doctor: bool,
+}
+
+impl TraceConfig {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, start: None, stop: None, max: None, doctor: false }
+    }
+
+    pub fn open(&self) -> Option<bus::Trace> {
+        let mut trace = bus::Trace::create(&self.path)
+            .map_err(|e| log::warn!("cannot open trace file {:?}: {e}", self.path))
+            .ok()?;
+        if let Some(pc) = self.start { trace = trace.start_at(pc); }
+        if let Some(pc) = self.stop { trace = trace.stop_at(pc); }
+        if let Some(max) = self.max { trace = trace.max_lines(max); }
+        Some(trace)
+    }
+}
+
+/// Cheats of a game, stored next to its save as `<title>.cheats.ron`.
+pub struct CheatConfig;
+
+impl CheatConfig {
+    fn path(rom: &Rom) -> PathBuf {
+        let title = rom.header.title.trim();
+        let name = if title.is_empty() { rom.filename.as_str() } else { title };
+        rom.location.join(format!("{name}.cheats.ron"))
+    }
+
+    pub fn load(rom: &Rom) -> Vec<Cheat> {
+        serde_any::from_file(Self::path(rom)).unwrap_or_default()
+    }
+
+    pub fn store(rom: &Rom, cheats: &[Cheat]) {
+        if let Err(e) = serde_any::ser::to_file_pretty(Self::path(rom), &cheats) {
+            log::error!("error while saving cheats {e:?}");
+        }
+    }
 }
 
 #[derive(Default, Serialize, Deserialize)]
@@ -83,6 +145,10 @@ impl AppConfig {
         path
     }
 
+    pub fn print_path() -> PathBuf {
+        Self::data_dir().join("prints")
+    }
+
     pub fn save_path<S: AsRef<str>>(rom: S) -> (String, PathBuf) {
         let path = Self::state_path().join(rom.as_ref());
         if !path.exists() {
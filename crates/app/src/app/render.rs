@@ -1,17 +1,96 @@
 use std::path::{Path, PathBuf};
 
+use bus::movie::Movie;
 use shared::{egui, Events, Handle};
 use shared::egui::{Align, Context, Layout, Margin, Rounding, Separator};
+use shared::rom::Rom;
 use shared::utils::image::ImageLoader;
 pub(crate) use shelves::{Shelf, ShelfItem};
 
-use crate::app::{AppConfig, Event, Menu, Texture};
+use crate::app::{AppConfig, Event, Menu, Texture, TraceConfig};
 use crate::emulator::Emulator;
 
 mod rom;
 mod shelves;
 pub mod state;
 
+impl Menu {
+    fn movie_menu(ui: &mut egui::Ui, emu: &mut Emulator) {
+        if emu.tape.is_some() {
+            if ui.button("Stop").clicked() {
+                if let Some(movie) = emu.stop_movie() {
+                    if let Some(path) = rfd::FileDialog::new().add_filter("movie", &["gbm"]).save_file() {
+                        movie.save(path).unwrap_or_else(|e| log::warn!("failed to save movie: {e:?}"));
+                    }
+                }
+                ui.close_menu();
+            }
+            return;
+        }
+        if ui.button("Record from power on").clicked() {
+            emu.record_movie(true);
+            ui.close_menu();
+        }
+        if ui.button("Record from here").clicked() {
+            emu.record_movie(false);
+            ui.close_menu();
+        }
+        if ui.button("Play").clicked() {
+            if let Some(path) = rfd::FileDialog::new().add_filter("movie", &["gbm"]).pick_file() {
+                match Movie::load(path) {
+                    Ok(movie) => emu.play_movie(movie),
+                    Err(e) => log::warn!("failed to load movie: {e:?}"),
+                }
+            }
+            ui.close_menu();
+        }
+    }
+
+    fn trace_menu(ui: &mut egui::Ui, emu: &mut Emulator) {
+        if let Some(mut doctor) = emu.trace.as_ref().map(|x| x.doctor) {
+            if ui.checkbox(&mut doctor, "LY reads 0x90 (gameboy-doctor)").changed() {
+                emu.set_doctor(doctor);
+            }
+            if ui.button("Stop").clicked() {
+                emu.set_trace(None);
+                ui.close_menu();
+            }
+            return;
+        }
+        for (label, doctor) in [("Start", false), ("Start for gameboy-doctor", true)] {
+            if ui.button(label).clicked() {
+                if let Some(path) = rfd::FileDialog::new().add_filter("trace", &["log"]).save_file() {
+                    emu.set_trace(Some(TraceConfig { doctor, ..TraceConfig::new(path) }));
+                }
+                ui.close_menu();
+            }
+        }
+    }
+
+    fn link_menu(ui: &mut egui::Ui, emu: &mut Emulator) {
+        if emu.peer.is_some() {
+            if ui.button("Unplug second console").clicked() {
+                emu.close_peer();
+                ui.close_menu();
+            }
+            return;
+        }
+        if ui.button("Second console (same game)").clicked() {
+            emu.open_peer(None);
+            ui.close_menu();
+        }
+        if ui.button("Second console (other game)").clicked() {
+            if let Some(path) = rfd::FileDialog::new().add_filter("rom", &["gb", "gbc"]).pick_file() {
+                match Rom::load(path) {
+                    Ok(rom) => emu.open_peer(Some(rom)),
+                    Err(e) => log::warn!("failed to load rom: {e:?}"),
+                }
+            }
+            ui.close_menu();
+        }
+    }
+}
+
 impl shared::Ui for Menu {
     type Ext = Emulator;
 
@@ -33,7 +112,7 @@ impl shared::Ui for Menu {
         for evt in self.roms.update() {
             match evt {
                 Event::Delete(path) => {
-                    emu.roms.paths.retain(|x| Path::new(x) != &path);
+                    emu.roms.paths.retain(|x| Path::new(x) != path);
                 }
                 Event::Added(root, path, mut rom) => {
                     self.add_cover(&path, &mut rom, ctx);
@@ -79,13 +158,15 @@ impl shared::Ui for Menu {
                         if ui.add(spritesheet).clicked() { emu.proxy.send_event(Events::Open(Handle::Sprites)).ok(); };
                         if ui.add(setting).clicked() { emu.proxy.send_event(Events::Open(Handle::Settings)).ok(); };
                         ui.add(Separator::default().vertical().spacing(4.));
-                        if emu.console.active() {
-                            if ui.add(save_state).clicked() { emu.save_state(); }
-                        }
+                        if emu.console.active()
+                            && ui.add(save_state).clicked() { emu.save_state(); }
                         if emu.is_running() {
                             ui.add(Separator::default().vertical().spacing(4.));
                             if ui.add(save).clicked() { emu.console.bus.save(false); }
                             if ui.add(nosave).clicked() { emu.stop(false); }
+                            ui.menu_button("Movie", |ui| Self::movie_menu(ui, emu));
+                            ui.menu_button("Link", |ui| Self::link_menu(ui, emu));
+                            ui.menu_button("Trace", |ui| Self::trace_menu(ui, emu));
                         }
                     });
                 })
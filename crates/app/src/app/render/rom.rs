@@ -38,7 +38,7 @@ impl<'a> Widget for RomView<'a> {
 
 impl<'a> RomView<'a> {
     pub(crate) fn new(rom: &'a Rom, textures: &HashMap<Texture, TextureHandle>) -> Self {
-        let handle = rom.cover.as_ref().and_then(|x| textures.get(&Texture::Cover(x.clone()))).map(|x| x.clone());
+        let handle = rom.cover.as_ref().and_then(|x| textures.get(&Texture::Cover(x.clone()))).cloned();
         Self { rom, handle }
     }
 }
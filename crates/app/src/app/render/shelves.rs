@@ -100,7 +100,7 @@ impl<Item: ShelfItem> PartialEq for Shelf<Item> {
 
 impl<Item: ShelfItem> PartialOrd for Shelf<Item> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.path.partial_cmp(&other.path)
+        Some(self.cmp(other))
     }
 }
 
@@ -113,7 +113,7 @@ impl<Item: ShelfItem> Ord for Shelf<Item> {
 impl<Item: ShelfItem> Shelf<Item> {
     pub(crate) fn view<'a>(&'a self, emu: &'a mut Emulator,
                            covers: &'a HashMap<Texture, TextureHandle>,
-                           tx: Sender<Event<Item>>) -> ShelfView<Item> {
+                           tx: Sender<Event<Item>>) -> ShelfView<'a, Item> {
         ShelfView { shelf: self, covers, emu, tx, remove: true }
     }
 
@@ -207,7 +207,7 @@ impl<'a, Item: ShelfItem> Widget for ShelfView<'a, Item> {
 
         CollapsingState::load_with_default_open(ui.ctx(), id, true)
             .show_header(ui, |ui| {
-                ui.label(RichText::new(self.shelf.name.as_ref().map(|e| e.as_str()).unwrap_or(path)).size(
+                ui.label(RichText::new(self.shelf.name.as_deref().unwrap_or(path)).size(
                     if self.shelf.root { 24. } else { 16. }
                 ));
                 if self.shelf.root && self.remove && ui.button("-").clicked() {
@@ -224,7 +224,7 @@ impl<'a, Item: ShelfItem> Widget for ShelfView<'a, Item> {
                                 ui.end_row();
                                 n = 1;
                             }
-                            if rom.render(&self.covers, ui, &self.tx).clicked() { rom.clicked(&mut self); }
+                            if rom.render(self.covers, ui, &self.tx).clicked() { rom.clicked(&mut self); }
                             n += 1;
                         }
                     }).response;
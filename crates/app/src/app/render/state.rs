@@ -1,16 +1,16 @@
 use std::collections::HashMap;
-use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::Sender;
 
 use shared::egui;
-use shared::egui::{Context, Direction, Image, Layout, Rect, Response, Sense, TextureHandle, TextureOptions, Ui, Vec2, Widget};
+use shared::egui::{Color32, Context, Direction, Image, Layout, Rect, Response, Sense, TextureHandle, TextureOptions, Ui, Vec2, Widget};
 use shared::utils::DARK_BLACK;
 
 use crate::app::render::rom::ROM_GRID;
 use crate::app::render::shelves::ShelfView;
 use crate::app::{Event, Storage, Texture};
-pub use crate::emulator::State;
+pub use crate::state::State;
+use crate::state::Issue;
 
 pub struct StateView<'a> {
     state: &'a State,
@@ -23,6 +23,18 @@ impl<'a> Widget for StateView<'a> {
         let response = ui.allocate_response(egui::Vec2::new(ROM_GRID, ROM_GRID + 16.), Sense::click());
         let img = Rect::from_min_size(response.rect.min, Vec2::splat(ROM_GRID));
         Image::new(self.handle.id(), (ROM_GRID, ROM_GRID)).paint_at(ui, img);
+        let response = match &self.state.issue {
+            Some(Issue::Warning(e)) => {
+                ui.painter().circle_filled(img.right_top() + Vec2::new(-8., 8.), 5., Color32::YELLOW);
+                response.on_hover_text(e.as_str())
+            }
+            Some(Issue::Error(e)) => {
+                ui.painter().rect_filled(img, 0., Color32::from_black_alpha(160));
+                ui.painter().circle_filled(img.right_top() + Vec2::new(-8., 8.), 5., Color32::RED);
+                response.on_hover_text(e.as_str())
+            }
+            None => response,
+        };
         let mut pos = img.min;
         pos.y += ROM_GRID;
         let mut ui = ui.child_ui(Rect::from_min_size(pos, Vec2::new(ROM_GRID, 16.)), Layout::centered_and_justified(Direction::LeftToRight));
@@ -54,15 +66,7 @@ impl super::ShelfItem for State {
     fn extensions() -> &'static [&'static str] { &["state"] }
 
     fn load_from_path(path: &Path) -> std::io::Result<Self> where Self: Sized {
-        std::fs::File::open(path)
-            .and_then(|mut x| {
-                use std::io::Read;
-                let mut v = Vec::new();
-                x.read_to_end(&mut v)?;
-                bincode::deserialize(&v).map_err(|e| {
-                    std::io::Error::new(ErrorKind::InvalidData, format!("deserialize failed {e:?}"))
-                })
-            })
+        std::fs::read(path).map(|v| State::from_bytes(&v, path))
     }
 
     fn set_cover(&mut self, cover: String) {
@@ -22,22 +22,19 @@ impl<Item: Send + 'static> FileWatcher<Item> {
         let x = path.clone();
         let tx = self.tx.clone();
         let mut w = notify::recommended_watcher(move |event| {
-            match event {
-                Ok(e @ notify::Event {
+            if let Ok(e @ notify::Event {
                     kind: EventKind::Create(_) | EventKind::Remove(_) | EventKind::Modify(_),
                     ..
-                }) => {
-                    log::debug!("{e:?}");
-                    tx.send(Event::Reload(x.clone())).ok();
-                }
-                _ => {}
+                }) = event {
+                log::debug!("{e:?}");
+                tx.send(Event::Reload(x.clone())).ok();
             };
         }).expect("failed to build watcher");
         if w.watch(Path::new(&path), RecursiveMode::Recursive)
             .map_err(|e| log::warn!("failed to start watcher for path {path:?}, reason: {e:?}"))
             .is_ok()
         {
-            self.watchers.insert(PathBuf::from(path), w);
+            self.watchers.insert(path, w);
         }
     }
 
@@ -1,33 +1,41 @@
-use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::{ErrorKind, Write};
 use std::path::{Path, PathBuf};
 use std::time::Instant;
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use winit::event::WindowEvent;
 
 use bus::Devices;
+use bus::movie::{Movie, Tape};
 use mem::{Oam, Vram};
-use serial::{Link, Port};
-use serial::com::Serial;
+use mem::colorize::Colorization;
+use serial::{Link, LinkDevice, Loopback, Plug};
+use serial::printer::Printer;
+use serial::com::{Game, Hello, Serial};
 use shared::{Events, Handle};
 use shared::audio_settings::AudioSettings;
-use shared::breakpoints::Breakpoints;
+use shared::breakpoints::{Banks, Breakpoints, Inspect};
+use shared::cheats::Cheat;
 use shared::cpu::Bus;
-use shared::emulator::{ReadAccess, Schedule};
+use shared::emulator::{ReadAccess, Schedule, WriteAccess};
 use shared::emulator::BusWrapper;
 use shared::input::{Keybindings, KeyCat, Shortcut};
 use shared::mem::{IOBus, MBCController};
 use shared::rom::Rom;
+use shared::symbols::Symbols;
+use shared::utils::camera::Sensor;
 use shared::utils::clock::Clock;
 use shared::utils::image::RawData;
 use shared::utils::palette::Palette;
 use shared::winit::window::Window;
 
 use crate::{AppConfig, Proxy};
-use crate::app::RomConfig;
+use crate::app::{CheatConfig, RomConfig, TraceConfig};
 use crate::render::{Event, Render};
+use crate::rewind::Rewind;
+use crate::state::State;
 use crate::settings::Mode;
 
 #[derive(Default, Serialize, Deserialize)]
@@ -39,42 +47,6 @@ pub struct Console {
     running: bool,
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct State {
-    pub console: Vec<u8>,
-    pub preview: RawData,
-    #[serde(skip)]
-    pub cover: Option<String>,
-    pub path: PathBuf,
-    pub ts: String,
-}
-
-impl State {
-    pub fn load(&self) -> Option<Console> {
-        bincode::deserialize(&self.console).ok()
-    }
-}
-
-impl PartialEq for State {
-    fn eq(&self, other: &Self) -> bool {
-        self.ts.eq(&other.ts)
-    }
-}
-
-impl Eq for State {}
-
-impl PartialOrd for State {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        other.ts.partial_cmp(&self.ts)
-    }
-}
-
-impl Ord for State {
-    fn cmp(&self, other: &Self) -> Ordering {
-        other.ts.cmp(&self.ts)
-    }
-}
-
 impl Console {
     pub fn load_state<P: AsRef<Path>>(path: P) -> Option<Self> {
         File::open(path).and_then(|file| {
@@ -83,14 +55,31 @@ impl Console {
     }
 
     pub fn active(&self) -> bool { self.rom.is_some() }
+
+    pub fn rom(&self) -> Option<&Rom> { self.rom.as_ref() }
 }
 
 fn autosave_default() -> u64 { 900 }
 
+fn rewind_budget_default() -> usize { 64 }
+
+fn rewind_interval_default() -> usize { 4 }
+
+fn listen_ports_default() -> (u16, u16) { (27542, 27551) }
+
+/// Reordered when reversed, port 0 picks a random port on each listener so it is excluded.
+fn listen_ports<'de, D: Deserializer<'de>>(deserializer: D) -> Result<(u16, u16), D::Error> {
+    let (first, last) = <(u16, u16)>::deserialize(deserializer)?;
+    Ok((first.min(last).max(1), first.max(last).max(1)))
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct EmuSettings {
     pub host: String,
     pub port: String,
+    /// range of ports the link cable server tries to listen on, applied on restart
+    #[serde(default = "listen_ports_default", deserialize_with = "listen_ports")]
+    pub listen_ports: (u16, u16),
     #[serde(default)]
     pub palette: Palette,
     #[serde(default = "autosave_default")]
@@ -99,6 +88,24 @@ pub struct EmuSettings {
     pub autosave: bool,
     #[serde(skip)]
     pub autosave_cycles: usize,
+    /// rewind memory budget in MiB, 0 disables rewind
+    #[serde(default = "rewind_budget_default")]
+    pub rewind_budget: usize,
+    /// frames between two rewind snapshots
+    #[serde(default = "rewind_interval_default")]
+    pub rewind_interval: usize,
+    /// tilt the cartridge accelerometer with the cursor position over the game window
+    #[serde(default)]
+    pub tilt_mouse: bool,
+    /// image or directory of images seen by the Pocket Camera
+    #[serde(default)]
+    pub camera: Option<PathBuf>,
+    /// Game Boy Printer plugged in the link port, prints are written to the data directory
+    #[serde(default)]
+    pub printer: bool,
+    /// palettes of the DMG games played in CGB mode, by title, when not picked by the boot ROM
+    #[serde(default)]
+    pub colorization: HashMap<String, Colorization>,
 }
 
 impl Default for EmuSettings {
@@ -106,10 +113,17 @@ impl Default for EmuSettings {
         Self {
             host: "127.0.0.1".to_string(),
             port: "27542".to_string(),
+            listen_ports: listen_ports_default(),
             palette: Palette::GrayScale,
             timer: 900,
             autosave: false,
             autosave_cycles: 0,
+            rewind_budget: rewind_budget_default(),
+            rewind_interval: rewind_interval_default(),
+            tilt_mouse: false,
+            camera: None,
+            printer: false,
+            colorization: HashMap::new(),
         }
     }
 }
@@ -122,6 +136,10 @@ pub struct Emulator {
     pub audio_settings: AudioSettings,
     pub bindings: Keybindings,
     pub(crate) breakpoints: Breakpoints,
+    pub(crate) trace: Option<TraceConfig>,
+    /// localhost port of the gdb server, kept in the config even when it could not listen
+    pub(crate) gdb_port: Option<u16>,
+    gdb: Option<dbg::Gdb>,
     pub console: Console,
     pub cgb: Mode,
     pub bios: bool,
@@ -131,13 +149,41 @@ pub struct Emulator {
     clock: Clock,
     pub last: Option<State>,
     throttle: Instant,
+    rewind: Rewind,
+    pub tape: Option<Tape>,
+    pub cheats: Vec<Cheat>,
+    /// labels of the `.sym` file next to the cartridge
+    symbols: Symbols,
+    camera: Option<Sensor>,
+    /// second console clocked in lockstep, linked by an in-memory cable
+    pub peer: Option<Peer>,
+    /// the peer window has focus: joypad inputs go to the second console
+    peer_focus: bool,
+}
+
+pub struct Peer {
+    pub console: Console,
+    breakpoints: Breakpoints,
+    sound: AudioSettings,
+}
+
+impl Peer {
+    fn ready(&mut self) -> bool { self.console.ready() }
+
+    fn cycle(&mut self, clock: u8) {
+        self.console.cycle(clock, bus::Settings {
+            breakpoints: &mut self.breakpoints,
+            sound: &mut self.sound,
+        });
+    }
 }
 
 impl Emulator {
     const AUTOSAVE_CHECK: usize = Console::CLOCK_PER_SECOND as usize * 10;
 
     pub fn new(proxy: Proxy, conf: AppConfig) -> Self {
-        let link = Link::new();
+        let (first, last) = conf.emu.listen_ports;
+        let link = Link::new(first..=last);
         let port = link.port;
         let mut emu = Self {
             link,
@@ -150,14 +196,28 @@ impl Emulator {
             audio_settings: conf.audio_settings,
             audio: apu::Controller::new(&conf.sound_device),
             breakpoints: Breakpoints::new(conf.debug.breaks, conf.debug.and),
+            trace: conf.debug.trace,
+            gdb_port: conf.debug.gdb,
+            gdb: conf.debug.gdb.and_then(|port| {
+                dbg::Gdb::listen(port).map_err(|e| log::error!("cannot start the gdb server on port {port}: {e}")).ok()
+            }),
             cgb: conf.mode,
             bios: conf.bios,
             timer: Instant::now(),
             clock: Clock::new(4),
             last: None,
             throttle: Instant::now(),
+            rewind: Rewind::default(),
+            tape: None,
+            cheats: vec![],
+            symbols: Symbols::default(),
+            camera: None,
+            peer: None,
+            peer_focus: false,
         };
         emu.bindings.init();
+        if let Some(path) = conf.debug.logpoints { emu.breakpoints.logs_mut().open(path); }
+        if let Some(path) = emu.settings.camera.clone() { emu.set_camera(Some(path)); }
         emu
     }
 
@@ -165,70 +225,157 @@ impl Emulator {
         if self.throttle.elapsed().as_secs_f32() < 0.5 { return; }
         let rom = self.console.rom.as_ref().unwrap();
         let (time, path) = AppConfig::save_path(&rom.header.title);
-        let v = bincode::serialize(&self.console).expect("cannot serialize Console");
-        let buf = self.console.gb.lcd.pixels.as_ref().unwrap().frame().to_owned();
+        let buf = self.console.gb.lcd.frame().to_owned();
         let preview = RawData { w: 160, h: 144, data: buf }.downsize([8, 0], [152, 144]);
-        let mut h = File::create(&path).expect(format!("cannot open path {path:?}").as_str());
-        let state = State {
-            console: v,
-            preview,
-            cover: None,
-            path,
-            ts: time,
-        };
-        let v = bincode::serialize(&state).expect("failed to save state");
-        h.write_all(&v).expect("failed to save state");
+        let mut h = File::create(&path).unwrap_or_else(|_| panic!("cannot open path {path:?}"));
+        let state = State::new(&self.console, preview, path, time);
+        h.write_all(&state.to_bytes()).expect("failed to save state");
         self.last = Some(state);
         self.throttle = Instant::now();
     }
 
     pub fn load_state(&mut self, state: Option<&State>) {
         if self.throttle.elapsed().as_secs_f32() < 0.5 { return; }
-        if let Some(mut console) = state
-            .or(self.last.as_ref())
-            .and_then(|x| x.load()) {
-            log::info!("loaded state, will save to : {:?}", console.bus.mbc().save_path());
-            self.console.bus.save(false);
-            self.serial_claim();
-            console.gb.serial = Port::new(self.link.port());
-            self.audio.reload(&mut console.gb.apu);
-            self.console = console;
-            self.proxy.send_event(Events::Reload).ok();
-            self.proxy.send_event(Events::Open(Handle::Game)).ok();
-            self.throttle = Instant::now();
+        let Some(state) = state.or(self.last.as_ref()) else { return; };
+        match state.load(self.console.rom.as_ref()) {
+            Ok(console) => self.restore(console),
+            Err(e) => log::error!("cannot load state {:?}: {e}", state.path),
         }
     }
 
+    fn restore(&mut self, mut console: Console) {
+        log::info!("loaded state, will save to : {:?}", console.bus.mbc().save_path());
+        self.close_peer();
+        self.console.bus.save(false);
+        self.serial_claim();
+        console.gb.serial.plug(Box::new(self.link.port()));
+        console.gb.cpu.set_trace(self.console.gb.cpu.take_trace());
+        self.audio.reload(&mut console.gb.apu);
+        self.console = console;
+        if let Some(rom) = self.console.rom.as_ref() {
+            self.cheats = CheatConfig::load(rom);
+            self.symbols = Symbols::load(rom);
+        }
+        self.attach();
+        self.rewind.clear();
+        self.tape = None;
+        self.proxy.send_event(Events::Reload).ok();
+        self.proxy.send_event(Events::Open(Handle::Game)).ok();
+        self.throttle = Instant::now();
+    }
+
     pub fn mode(&self) -> Mode { self.cgb }
 
     pub fn link_do<R, F: Fn(&mut Serial) -> R>(&mut self, f: F) -> R {
-        self.link.as_mut()
-            .map(|x| f(x))
-            .unwrap_or_else(|| {
-                f(self.console.gb.serial.link())
-            })
+        let cable = match self.link.as_mut() {
+            Some(cable) => cable,
+            None => self.console.gb.serial.link().expect("the cable is plugged in the console"),
+        };
+        f(cable)
     }
 
     pub fn enabled_boot(&self) -> bool {
         self.bios
     }
 
+    pub fn colorization(&self, rom: &Rom) -> Colorization {
+        self.settings.colorization.get(rom.header.title.trim()).copied().unwrap_or_default()
+    }
+
+    /// Stores the colorization of the current game and applies it right away.
+    pub fn set_colorization(&mut self, colorization: Colorization) {
+        let Some(title) = self.console.rom.as_ref().map(|x| x.header.title.trim().to_string()) else { return; };
+        match colorization {
+            Colorization::Auto => self.settings.colorization.remove(&title),
+            c => self.settings.colorization.insert(title, c),
+        };
+        self.console.bus.colorize(&mut self.console.gb, colorization);
+    }
+
     pub fn serial_port(&mut self) -> serial::com::Serial {
         self.link.port()
     }
     pub fn serial_claim(&mut self) {
         if self.link.borrowed() {
-            self.link.store(self.console.gb.serial.disconnect());
+            let cable = self.console.gb.serial.unplug().and_then(LinkDevice::into_cable);
+            self.link.store(cable.expect("the cable is plugged in the console"));
         }
     }
 
+    /// Plugs `device` in place of the tcp cable, which the link keeps meanwhile.
+    fn plug(&mut self, device: Box<dyn LinkDevice>) {
+        self.serial_claim();
+        self.console.gb.serial.plug(device);
+    }
+
+    /// Plugs the tcp cable back in place of the current device.
+    fn replug(&mut self) {
+        self.serial_claim();
+        self.console.gb.serial.plug(Box::new(self.link.port()));
+    }
+
+    /// Records the inputs from power on (restarting the game) or from the current state.
+    pub fn record_movie(&mut self, power_on: bool) {
+        let Some(rom) = self.console.rom.clone() else { return; };
+        let cgb = self.cgb.is_cgb();
+        let movie = if power_on {
+            self.insert(rom.clone(), true);
+            self.clock = Clock::new(4);
+            Movie::power_on(&rom, cgb, !self.bios, &self.console.bus)
+        } else {
+            Movie::snapshot(&rom, cgb, &self.console.bus, &self.console.gb, self.clock.value())
+        };
+        log::info!("recording movie for {}", rom.header.title);
+        self.tape = Some(Tape::record(movie, &mut self.console.bus));
+    }
+
+    /// Replays a movie recorded on the current game.
+    pub fn play_movie(&mut self, movie: Movie) {
+        let Some(rom) = self.console.rom.clone() else { return; };
+        if movie.title != rom.header.title {
+            log::warn!("movie was recorded on {}, not {}", movie.title, rom.header.title);
+            return;
+        }
+        let Some((bus, gb, clock)) = movie.console(&rom) else { return; };
+        self.insert(rom, true);
+        let host = std::mem::take(&mut self.console.gb);
+        self.console.gb = host.reload(gb);
+        self.console.bus = bus;
+        self.attach();
+        self.clock.set_value(clock);
+        log::info!("playing movie ({} frames)", movie.frames());
+        self.tape = Some(Tape::play(movie));
+    }
+
+    /// Stops the recording or playback, returns the recorded movie.
+    pub fn stop_movie(&mut self) -> Option<Movie> {
+        self.tape.take().filter(|x| !x.playing()).map(|x| x.eject(&mut self.console.bus))
+    }
+
     pub fn cycle(&mut self) {
-        if self.is_running() {
+        let rewinding = self.tape.is_none() && self.peer.is_none() && self.is_running() && self.bindings.pressed(KeyCat::Game(Shortcut::Rewind));
+        self.audio.mute(rewinding);
+        if rewinding {
+            if let Some((mut bus, gb)) = self.rewind.rewind() {
+                bus.put_rom(self.console.bus.take_rom());
+                let current = std::mem::take(&mut self.console.gb);
+                self.console.gb = current.reload(gb);
+                self.console.bus = bus;
+                self.attach();
+            }
+        } else if self.is_running() && self.both_ready() {
+            if let Some(tape) = self.tape.as_mut() {
+                if !tape.tick(&mut self.console.bus, &mut self.console.gb) {
+                    log::info!("movie ended after {} frames", tape.frame());
+                    self.tape = None;
+                }
+            }
             let clock = self.clock.tick();
             self.console.cycle(clock, bus::Settings {
                 breakpoints: &mut self.breakpoints,
                 sound: &mut self.audio_settings,
             });
+            if let Some(peer) = self.peer.as_mut() { peer.cycle(clock); }
             if self.settings.autosave {
                 self.settings.autosave_cycles += 1;
                 if self.settings.autosave_cycles > Emulator::AUTOSAVE_CHECK {
@@ -239,16 +386,118 @@ impl Emulator {
                     }
                 }
             }
+            self.rewind.tick(&mut self.console, self.settings.rewind_budget << 20, self.settings.rewind_interval);
+        }
+    }
+
+    /// Answers the gdb client, between two cycles.
+    pub fn serve_gdb(&mut self) {
+        if let Some(mut gdb) = self.gdb.take() {
+            gdb.poll(self);
+            self.gdb = Some(gdb);
         }
     }
 
+    /// Applies and stores the cheats after they were edited.
+    pub fn update_cheats(&mut self) {
+        self.console.bus.set_cheats(&self.cheats);
+        if let Some(rom) = self.console.rom.as_ref() { CheatConfig::store(rom, &self.cheats); }
+    }
+
+    /// Loads the Pocket Camera images from a file or a directory, `None` restores the default sensor.
+    pub fn set_camera(&mut self, path: Option<PathBuf>) {
+        self.camera = match path.as_ref().map(Sensor::load) {
+            Some(Ok(sensor)) => Some(sensor),
+            Some(Err(e)) => {
+                log::warn!("failed to load camera images: {e:?}");
+                return;
+            }
+            None => None,
+        };
+        self.settings.camera = path;
+        self.console.bus.set_camera(self.camera.clone().unwrap_or_default());
+    }
+
+    /// Starts a second console on `rom` (the current game if `None`), linked to the first one.
+    /// On the same game, it starts from a copy of the cartridge ram which is never saved.
+    pub fn open_peer(&mut self, rom: Option<Rom>) {
+        let Some(current) = self.console.rom.clone() else { return; };
+        self.close_peer();
+        let rom = rom.unwrap_or_else(|| current.clone());
+        let ram = (rom == current).then(|| self.console.bus.cartridge_ram());
+        let mut console = Console::peer(self, rom, ram);
+        let (a, b) = Loopback::pair();
+        self.plug(Box::new(a));
+        console.gb.serial.plug(Box::new(b));
+        self.peer = Some(Peer { console, breakpoints: Breakpoints::default(), sound: self.audio_settings.clone() });
+        self.proxy.send_event(Events::Open(Handle::Peer)).ok();
+    }
+
+    pub fn close_peer(&mut self) {
+        let Some(mut peer) = self.peer.take() else { return; };
+        peer.console.bus.save(false);
+        self.peer_focus = false;
+        self.replug();
+        self.set_printer(self.settings.printer);
+        self.proxy.send_event(Events::Close(Handle::Peer)).ok();
+    }
+
+    /// Plugs or unplugs the Game Boy Printer from the link port.
+    pub fn set_printer(&mut self, printer: bool) {
+        self.settings.printer = printer;
+        if printer && !self.console.gb.serial.plugged() {
+            self.plug(Box::new(Plug::new(Printer::new(AppConfig::print_path()))));
+        } else if !printer {
+            self.replug();
+        }
+    }
+
+    /// Traces the instructions to a new file, or stops tracing when `None`.
+    pub fn set_trace(&mut self, trace: Option<TraceConfig>) {
+        self.trace = trace;
+        self.console.gb.cpu.set_trace(self.trace.as_ref().and_then(TraceConfig::open));
+        self.attach_trace();
+    }
+
+    /// Whether LY reads 0x90 while tracing, see [`TraceConfig::doctor`].
+    pub fn set_doctor(&mut self, doctor: bool) {
+        if let Some(trace) = self.trace.as_mut() { trace.doctor = doctor; }
+        self.attach_trace();
+    }
+
+    fn attach_trace(&mut self) {
+        let doctor = self.trace.as_ref().is_some_and(|x| x.doctor);
+        self.console.bus.io_regs().stub_ly(doctor);
+    }
+
+    /// Hands the host side state (cheats, camera images, printer) to a freshly loaded console.
+    fn attach(&mut self) {
+        self.console.bus.set_cheats(&self.cheats);
+        if let Some(sensor) = &self.camera { self.console.bus.set_camera(sensor.clone()); }
+        if self.settings.printer { self.set_printer(true); }
+        self.attach_trace();
+        let cgb = self.cgb.is_cgb();
+        let hello = Hello::new(self.console.rom.as_ref().map(|x| Game::new(&x.header.title, x.header.checksum, cgb)));
+        self.link_do(|x| x.set_hello(hello));
+    }
+
     pub fn is_running(&self) -> bool { self.console.running && self.console.rom.is_some() }
 
+    /// Polls both linked consoles every time, so that each one keeps reading its cable.
+    fn both_ready(&mut self) -> bool {
+        let ready = self.console.ready();
+        let peer = self.peer.as_mut().map_or(true, Peer::ready);
+        ready && peer
+    }
+
     pub fn stop(&mut self, save: bool) {
+        self.close_peer();
         self.serial_claim();
         self.link_do(|x| { x.disconnect(); });
         if save { self.console.bus.save(false); }
         self.console = Console::default();
+        self.rewind.clear();
+        self.tape = None;
     }
 
     pub fn cycle_time(&self) -> f64 {
@@ -256,9 +505,18 @@ impl Emulator {
     }
 
     fn insert(&mut self, rom: Rom, running: bool) {
+        self.close_peer();
+        self.tape = None;
         self.serial_claim();
         self.console.bus.save(false);
+        self.cheats = CheatConfig::load(&rom);
+        self.symbols = Symbols::load(&rom);
+        // keys held while starting a game pick its colorization, as on a real CGB
+        let keys = self.console.gb.joy.state();
         self.console = Console::new(self, rom, running);
+        self.console.gb.joy.set_state(keys, self.console.bus.io_regs());
+        self.attach();
+        self.rewind.clear();
         self.proxy.send_event(Events::Reload).ok();
         self.timer = Instant::now();
     }
@@ -295,6 +553,11 @@ impl Render for Screen {
                 match event {
                     WindowEvent::CloseRequested => emu.stop(true),
                     WindowEvent::Focused(focus) => self.focus = *focus,
+                    WindowEvent::CursorMoved { position, .. } if emu.settings.tilt_mouse => {
+                        let size = window.inner_size();
+                        let axis = |pos: f64, len: u32| (2. * pos / len.max(1) as f64 - 1.) as f32;
+                        emu.console.bus.set_tilt(axis(position.x, size.width), axis(position.y, size.height));
+                    }
                     _ => {}
                 }
             }
@@ -308,10 +571,53 @@ impl Render for Screen {
                     Shortcut::SpeedUp => emu.speedup(),
                     Shortcut::SpeedDown => emu.speeddown(),
                     Shortcut::SaveState => if emu.console.active() { emu.save_state() },
-                    Shortcut::LoadState => emu.load_state(None)
+                    Shortcut::LoadState => emu.load_state(None),
+                    Shortcut::Rewind => {}
+                }
+            }
+            Event::UserEvent(Events::Press(KeyCat::Tilt(_)) | Events::Release(KeyCat::Tilt(_))) => {
+                let (x, y) = emu.bindings.tilt();
+                emu.console.bus.set_tilt(x, y);
+            }
+            e => match (emu.tape.as_mut(), emu.peer.as_mut().filter(|_| emu.peer_focus)) {
+                (_, Some(peer)) => emu.bindings.update(&mut peer.console.gb.joy, e, peer.console.bus.io_regs()),
+                (Some(tape), None) => emu.bindings.update(tape, e, emu.console.bus.io_regs()),
+                (None, None) => emu.bindings.update(&mut emu.console.gb.joy, e, emu.console.bus.io_regs()),
+            },
+        }
+    }
+}
+
+/// Window of the second console, see [`Emulator::open_peer`].
+#[derive(Default)]
+pub struct PeerScreen;
+
+impl Render for PeerScreen {
+    fn init(&mut self, window: &Window, emu: &mut Emulator) {
+        let Some(peer) = emu.peer.as_mut() else { return; };
+        peer.console.gb.lcd.init(window);
+        window.set_title(&format!("{} - player 2", peer.console.name()));
+    }
+
+    fn render(&mut self, emu: &mut Emulator) {
+        if let Some(peer) = emu.peer.as_mut() { peer.console.gb.lcd.render(); }
+    }
+
+    fn resize(&mut self, w: u32, h: u32, emu: &mut Emulator) {
+        if let Some(peer) = emu.peer.as_mut() { peer.console.gb.lcd.resize(w, h); }
+    }
+
+    fn handle(&mut self, event: &Event, window: &Window, emu: &mut Emulator) {
+        match event {
+            Event::UserEvent(Events::Reload) => Render::init(self, window, emu),
+            Event::WindowEvent { window_id, event } if window_id == &window.id() => {
+                match event {
+                    WindowEvent::CloseRequested => emu.close_peer(),
+                    WindowEvent::Focused(focus) => emu.peer_focus = *focus,
+                    _ => {}
                 }
             }
-            e => emu.bindings.update(&mut emu.console.gb.joy, e, emu.console.bus.io_regs()),
+            _ => {}
         }
     }
 }
@@ -332,6 +638,28 @@ impl ReadAccess for Emulator {
     fn mbc(&self) -> Box<&dyn MBCController> {
         self.console.bus.mbc()
     }
+
+    fn symbols(&self) -> &Symbols { &self.symbols }
+
+    fn banks(&self) -> Banks { self.console.bus.banks() }
+
+    fn rom_banks(&self) -> usize {
+        self.console.rom.as_ref().map_or(0, |rom| rom.banks())
+    }
+
+    fn read_bank(&self, bank: usize) -> Vec<u8> {
+        self.console.rom.as_ref().map_or(vec![], |rom| rom.bank(bank).to_vec())
+    }
+}
+
+impl WriteAccess for Emulator {
+    fn set_cpu_register(&mut self, reg: shared::cpu::Reg, value: shared::cpu::Value) -> bool {
+        self.console.gb.cpu.registers_mut().write(reg, value)
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        self.console.bus.debug_write(addr, value);
+    }
 }
 
 impl Schedule for Emulator {
@@ -341,6 +669,8 @@ impl Schedule for Emulator {
         self.console.running = true;
     }
 
+    fn running(&self) -> bool { self.is_running() }
+
     fn reset(&mut self) {
         let Some(rom) = self.console.rom.clone() else { return; };
         self.insert(rom, false);
@@ -368,19 +698,23 @@ impl Console {
 
     pub fn new(controller: &mut Emulator, rom: Rom, running: bool) -> Self {
         let cgb = controller.mode().is_cgb();
+        let sgb = controller.mode().is_sgb(&rom);
         let skip = !controller.enabled_boot();
-        let gb = Devices::builder()
+        let mut gb = Devices::builder()
             .skip_boot(skip)
             .set_cgb(cgb)
+            .set_sgb(sgb)
             .with_link(controller.serial_port())
             .with_sound_driver(&controller.audio)
             .build();
+        gb.cpu.set_trace(controller.trace.as_ref().and_then(TraceConfig::open));
         let bus = bus::Bus::init(&rom)
             .cgb(cgb)
             .skip_boot(skip)
             .palette(controller.settings.palette)
+            .colorization(controller.colorization(&rom))
             .build();
-        log::info!("cartridge: {} | device: {}", rom.header.title, if cgb { "CGB" } else { "DMG" });
+        log::info!("cartridge: {} | device: {}", rom.header.title, if cgb { "CGB" } else if sgb { "SGB" } else { "DMG" });
         Self {
             speed: Default::default(),
             rom: Some(rom),
@@ -390,6 +724,34 @@ impl Console {
         }
     }
 
+    /// Second console: no sound nor tcp link, the cartridge ram is never saved when given.
+    fn peer(controller: &Emulator, rom: Rom, ram: Option<Vec<u8>>) -> Self {
+        let cgb = controller.mode().is_cgb();
+        let skip = !controller.enabled_boot();
+        let gb = Devices::builder()
+            .skip_boot(skip)
+            .set_cgb(cgb)
+            .set_sgb(controller.mode().is_sgb(&rom))
+            .build();
+        let builder = bus::Bus::init(&rom)
+            .cgb(cgb)
+            .skip_boot(skip)
+            .palette(controller.settings.palette)
+            .colorization(controller.colorization(&rom));
+        let bus = match ram {
+            Some(ram) => builder.cartridge_ram(ram),
+            None => builder,
+        }.build();
+        log::info!("second console: {}", rom.header.title);
+        Self {
+            speed: Default::default(),
+            rom: Some(rom),
+            running: true,
+            gb,
+            bus,
+        }
+    }
+
     fn speed_mult(&self) -> f64 {
         match self.speed {
             0 => 1.,
@@ -403,6 +765,9 @@ impl Console {
         self.gb.apu.set_speed(self.speed_mult());
     }
 
+    /// Whether the link cable lets the console run its next cycle.
+    pub fn ready(&mut self) -> bool { self.bus.link_ready(&mut self.gb) }
+
     pub fn cycle(&mut self, clock: u8, settings: bus::Settings) {
         self.running = self.bus.tick(&mut self.gb, clock, settings);
     }
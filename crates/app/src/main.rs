@@ -20,6 +20,8 @@ mod log;
 mod render;
 mod settings;
 mod emulator;
+mod rewind;
+mod state;
 pub mod app;
 
 pub struct App {
@@ -28,6 +30,12 @@ pub struct App {
     pub windows: Windows,
 }
 
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl App {
     pub fn new() -> Self {
         let e = EventLoopBuilder::with_user_event()
@@ -89,9 +97,12 @@ impl App {
                         roms: self.emu.roms.clone(),
                         debug: DbgConfig {
                             breaks: self.emu.breakpoints.take().into_iter()
-                                .filter(|x| !x.temp())
+                                .filter(|x| !x.temp() && !x.is_remote())
                                 .collect(),
                             and: self.emu.breakpoints.and(),
+                            trace: self.emu.trace.clone(),
+                            gdb: self.emu.gdb_port,
+                            logpoints: self.emu.breakpoints.logs().path().map(|x| x.to_path_buf()),
                         },
                         emu: self.emu.settings.clone(),
                         keys: self.emu.bindings.clone(),
@@ -117,6 +128,7 @@ fn main() {
     let mut dt = Duration::from_secs(0);
     app.create(Handle::Main)
         .run(move |app| {
+            app.emu.serve_gdb();
             if app.emu.is_running() {
                 if st.paused() { st.start(); }
                 if run {
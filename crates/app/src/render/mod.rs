@@ -10,7 +10,7 @@ pub use raw_context::RawContext;
 use shared::{Events, Handle};
 
 use crate::app::Menu;
-use crate::emulator::{Emulator, Screen};
+use crate::emulator::{Emulator, PeerScreen, Screen};
 use crate::settings::Settings;
 
 mod egui_context;
@@ -56,6 +56,10 @@ impl WindowType {
                 .with_title("GBMU")
                 .with_min_inner_size(PhysicalSize::new(160, 144))
                 .with_inner_size(PhysicalSize::<u32>::from((640, 576))),
+            Handle::Peer => WindowBuilder::new()
+                .with_title("GBMU - player 2")
+                .with_min_inner_size(PhysicalSize::new(160, 144))
+                .with_inner_size(PhysicalSize::<u32>::from((640, 576))),
             Handle::Debug => WindowBuilder::new()
                 .with_title("GBMU - debugger")
                 .with_resizable(false)
@@ -71,6 +75,7 @@ impl WindowType {
         match self.0 {
             Handle::Main => EguiContext::<Emulator, Menu>::builder(emu),
             Handle::Game => RawContext::<Screen>::builder(emu),
+            Handle::Peer => RawContext::<PeerScreen>::builder(emu),
             Handle::Debug => EguiContext::<Emulator, Ninja<Emulator>>::builder(emu),
             Handle::Sprites => EguiContext::<Emulator, ppu::VramViewer<Emulator>>::builder(emu),
             Handle::Settings => EguiContext::<Emulator, Settings>::builder(emu),
@@ -50,7 +50,7 @@ impl Windows {
 
     pub fn handle_events(&mut self, event: &Event, flow: &mut ControlFlow, emu: &mut Emulator) {
         emu.bindings.update_inputs(event, &emu.proxy);
-        for (_, win) in &mut self.windows {
+        for win in self.windows.values_mut() {
             win.handle(event, emu);
         }
         match event {
@@ -65,10 +65,10 @@ impl Windows {
                 }
             }
             Event::WindowEvent { event: WindowEvent::Resized(sz), window_id } => {
-                self.windows.get_mut(&window_id).unwrap().resize(*sz, emu);
+                self.windows.get_mut(window_id).unwrap().resize(*sz, emu);
             }
             Event::RedrawRequested(id) => {
-                self.windows.get_mut(&id).unwrap().redraw(emu);
+                self.windows.get_mut(id).unwrap().redraw(emu);
             }
             _ => {}
         }
@@ -80,9 +80,9 @@ impl Windows {
         }
     }
 
-    pub fn create<'a>(&mut self, handle: Handle, emu: &mut Emulator, event_loop: &EventLoopWindowTarget<Events>) {
+    pub fn create(&mut self, handle: Handle, emu: &mut Emulator, event_loop: &EventLoopWindowTarget<Events>) {
         if let Some(handle) = self.handles.get(&handle) {
-            self.windows.get_mut(handle).map(|ctx| ctx.inner().focus_window());
+            if let Some(ctx) = self.windows.get_mut(handle) { ctx.inner().focus_window() }
              return;
         }
         let proxy = self.proxy.clone();
@@ -0,0 +1,151 @@
+use std::collections::VecDeque;
+
+use bus::Devices;
+
+use crate::emulator::Console;
+
+const FRAME_CYCLES: usize = 70224;
+
+/// XOR patch between two serialized snapshots, stored as `offset, len, bytes` runs
+/// of the non zero bytes: consecutive frames mostly differ by a few kilobytes.
+struct Delta {
+    len: usize,
+    runs: Vec<u8>,
+}
+
+impl Delta {
+    /// Patch turning `from` into `to`.
+    fn new(from: &[u8], to: &[u8]) -> Self {
+        let mut runs = Vec::new();
+        let xor = |i: usize| to[i] ^ from.get(i).copied().unwrap_or(0);
+        let mut i = 0;
+        while i < to.len() {
+            if xor(i) == 0 {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < to.len() && xor(i) != 0 { i += 1; }
+            runs.extend_from_slice(&(start as u32).to_le_bytes());
+            runs.extend_from_slice(&((i - start) as u32).to_le_bytes());
+            runs.extend((start..i).map(xor));
+        }
+        Self { len: to.len(), runs }
+    }
+
+    fn apply(&self, from: &[u8]) -> Vec<u8> {
+        let mut out = from.to_vec();
+        out.resize(self.len, 0);
+        let mut runs = self.runs.as_slice();
+        while runs.len() >= 8 {
+            let start = u32::from_le_bytes(runs[0..4].try_into().unwrap()) as usize;
+            let len = u32::from_le_bytes(runs[4..8].try_into().unwrap()) as usize;
+            out[start..start + len].iter_mut()
+                .zip(&runs[8..8 + len])
+                .for_each(|(o, x)| *o ^= x);
+            runs = &runs[8 + len..];
+        }
+        out
+    }
+
+    fn size(&self) -> usize { self.runs.len() + std::mem::size_of::<Self>() }
+}
+
+/// Ring buffer of past console states: the newest snapshot is kept whole,
+/// older ones as reverse deltas dropped oldest first once over budget.
+#[derive(Default)]
+pub struct Rewind {
+    last: Vec<u8>,
+    deltas: VecDeque<Delta>,
+    used: usize,
+    frame: usize,
+    elapsed: usize,
+    cycles: usize,
+}
+
+impl Rewind {
+    pub fn clear(&mut self) {
+        self.last.clear();
+        self.deltas.clear();
+        self.used = 0;
+        self.elapsed = 0;
+    }
+
+    /// Called after every emulated cycle, takes a snapshot every `interval` frames.
+    pub fn tick(&mut self, console: &mut Console, budget: usize, interval: usize) {
+        let frame = console.gb.lcd.frames();
+        if budget == 0 || frame == self.frame { return; }
+        self.frame = frame;
+        self.elapsed += 1;
+        if self.elapsed >= interval.max(1) {
+            self.elapsed = 0;
+            self.push(console, budget);
+        }
+    }
+
+    /// The cartridge rom never changes, it is left out of the snapshot and moved back in on restore.
+    fn push(&mut self, console: &mut Console, budget: usize) {
+        let rom = console.bus.take_rom();
+        let next = bincode::serialize(&(&console.bus, &console.gb));
+        console.bus.put_rom(rom);
+        let next = match next {
+            Ok(next) => next,
+            Err(e) => {
+                log::warn!("rewind snapshot failed: {e:?}");
+                return;
+            }
+        };
+        if !self.last.is_empty() {
+            let delta = Delta::new(&next, &self.last);
+            self.used += delta.size();
+            self.deltas.push_back(delta);
+        }
+        while self.used + next.len() > budget {
+            let Some(delta) = self.deltas.pop_front() else { break };
+            self.used -= delta.size();
+        }
+        self.last = next;
+    }
+
+    /// Called instead of emulating while rewinding, steps one snapshot back every frame.
+    /// The returned bus has no rom, see [`bus::Bus::put_rom`].
+    pub fn rewind(&mut self) -> Option<(bus::Bus, Devices)> {
+        self.cycles += 1;
+        if self.cycles < FRAME_CYCLES || self.last.is_empty() { return None; }
+        self.cycles = 0;
+        self.elapsed = 0;
+        let state = bincode::deserialize(&self.last)
+            .map_err(|e| log::warn!("rewind snapshot is invalid: {e:?}"))
+            .ok();
+        if let Some(delta) = self.deltas.pop_back() {
+            self.used -= delta.size();
+            self.last = delta.apply(&self.last);
+        }
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delta_round_trip() {
+        let from: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        let mut to = from.clone();
+        to[3] ^= 1;
+        to[500..520].fill(0xAA);
+        let delta = Delta::new(&from, &to);
+        assert_eq!(delta.apply(&from), to);
+        assert_eq!(delta.runs.len(), 8 + 1 + 8 + 20);
+        assert!(Delta::new(&from, &from).runs.is_empty());
+    }
+
+    #[test]
+    fn delta_resizes() {
+        let short = vec![1, 2, 3];
+        let long = vec![1, 2, 4, 0, 5, 6];
+        assert_eq!(Delta::new(&short, &long).apply(&short), long);
+        assert_eq!(Delta::new(&long, &short).apply(&long), short);
+    }
+}
@@ -4,6 +4,7 @@ use winit::event::Event;
 use shared::egui::{CentralPanel, Context, Ui};
 use shared::Events;
 use shared::input::KeyCat;
+use shared::rom::{Gameboy, Rom};
 use shared::widgets::tabs;
 use shared::widgets::tabs::Tab;
 
@@ -13,6 +14,7 @@ mod keybinds;
 mod audio;
 mod device;
 mod video;
+mod cheats;
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 enum Tabs {
@@ -20,6 +22,7 @@ enum Tabs {
     Device,
     Audio,
     Video,
+    Cheats,
 }
 
 impl Tab for Tabs {
@@ -33,6 +36,7 @@ pub struct Settings {
     devices: Vec<String>,
     key: Option<KeyCat>,
     autosave: String,
+    cheat: cheats::CheatInput,
 }
 
 impl Default for Settings {
@@ -42,29 +46,33 @@ impl Default for Settings {
             devices: apu::Controller::devices().collect(),
             key: None,
             autosave: "".to_string(),
+            cheat: Default::default(),
         }
     }
 }
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Serialize, Deserialize)]
+#[derive(Default)]
 pub enum Mode {
+    #[default]
     Dmg,
     Cgb,
+    Sgb,
 }
 
-impl Default for Mode {
-    fn default() -> Self {
-        Self::Dmg
-    }
-}
 
 impl Mode {
     pub fn is_cgb(&self) -> bool {
         match self {
-            Mode::Dmg => false,
+            Mode::Dmg | Mode::Sgb => false,
             Mode::Cgb => true
         }
     }
+
+    /// Super Game Boy mode, for the games supporting it: the others run as on a DMG.
+    pub fn is_sgb(&self, rom: &Rom) -> bool {
+        *self == Mode::Sgb && rom.header.funcs == Gameboy::Super
+    }
 }
 
 impl shared::Ui for Settings {
@@ -77,11 +85,12 @@ impl shared::Ui for Settings {
     fn draw(&mut self, ctx: &mut Context, emu: &mut Emulator) {
         CentralPanel::default()
             .show(ctx, |ui: &mut Ui| {
-                tabs::Tabs::new(&mut self.tab, ui, &[Tabs::Keybinds, Tabs::Device, Tabs::Audio, Tabs::Video])
+                tabs::Tabs::new(&mut self.tab, ui, &[Tabs::Keybinds, Tabs::Device, Tabs::Audio, Tabs::Video, Tabs::Cheats])
                     .with_tab(Tabs::Keybinds, keybinds::Keybinds::new(self, emu))
                     .with_tab(Tabs::Audio, audio::Audio::new(self, emu))
                     .with_tab(Tabs::Device, device::Device::new(emu, &mut self.autosave))
-                    .with_tab(Tabs::Video, video::Video::new(emu));
+                    .with_tab(Tabs::Video, video::Video::new(emu))
+                    .with_tab(Tabs::Cheats, cheats::Cheats::new(emu, &mut self.cheat));
             });
     }
 
@@ -0,0 +1,74 @@
+use shared::cheats::Cheat;
+use shared::egui::{Color32, Response, TextEdit, Ui, Widget};
+use shared::widgets::section::Section;
+
+use crate::emulator::Emulator;
+
+#[derive(Default)]
+pub struct CheatInput {
+    name: String,
+    code: String,
+    error: Option<String>,
+}
+
+pub struct Cheats<'a> {
+    emu: &'a mut Emulator,
+    input: &'a mut CheatInput,
+}
+
+impl<'a> Cheats<'a> {
+    pub fn new(emu: &'a mut Emulator, input: &'a mut CheatInput) -> Self {
+        Self { emu, input }
+    }
+}
+
+impl<'a> Widget for Cheats<'a> {
+    fn ui(self, ui: &mut Ui) -> Response {
+        ui.vertical(|ui| {
+            if !self.emu.console.active() {
+                ui.label("No game running");
+                return;
+            }
+            let mut changed = false;
+            let mut remove = None;
+            ui.section("CHEATS", |ui| {
+                let mut res = ui.label("Game Genie (ABC-DEF-GHI) or GameShark (01VVAAAA) codes");
+                for (i, cheat) in self.emu.cheats.iter_mut().enumerate() {
+                    res |= ui.horizontal(|ui| {
+                        changed |= ui.checkbox(&mut cheat.enabled, cheat.name.as_str()).changed();
+                        ui.label(cheat.code.as_str());
+                        if ui.button("Remove").clicked() { remove = Some(i); }
+                    }).response;
+                }
+                res
+            });
+            ui.section("ADD", |ui| {
+                let res = ui.horizontal(|ui| {
+                    ui.label("Name: ");
+                    ui.add(TextEdit::singleline(&mut self.input.name).desired_width(120.));
+                    ui.label(" Code: ");
+                    ui.add(TextEdit::singleline(&mut self.input.code).desired_width(160.));
+                    if ui.button("Add").clicked() {
+                        match Cheat::new(self.input.name.clone(), self.input.code.clone()) {
+                            Ok(cheat) => {
+                                self.emu.cheats.push(cheat);
+                                *self.input = CheatInput::default();
+                                changed = true;
+                            }
+                            Err(e) => self.input.error = Some(e.to_string()),
+                        }
+                    }
+                }).response;
+                match &self.input.error {
+                    Some(e) => res | ui.colored_label(Color32::RED, e.as_str()),
+                    None => res
+                }
+            });
+            if let Some(i) = remove {
+                self.emu.cheats.remove(i);
+                changed = true;
+            }
+            if changed { self.emu.update_cheats(); }
+        }).response
+    }
+}
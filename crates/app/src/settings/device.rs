@@ -1,7 +1,6 @@
-use std::net::Ipv4Addr;
 use std::time::Instant;
 
-use shared::egui::{Response, TextEdit, Ui, Widget};
+use shared::egui::{Color32, DragValue, Response, TextEdit, Ui, Widget};
 use shared::widgets::section::Section;
 
 use crate::emulator::Emulator;
@@ -28,11 +27,24 @@ impl<'a> Widget for Device<'a> {
             ui.section("MODEL", |ui| {
                 ui.radio_value(model, Mode::Dmg, format!("{:?}", Mode::Dmg)) |
                     ui.radio_value(model, Mode::Cgb, format!("{:?}", Mode::Cgb)) |
+                    ui.radio_value(model, Mode::Sgb, format!("{:?}", Mode::Sgb)) |
                     ui.checkbox(&mut self.emu.bios, "enable boot rom")
             });
             let connected = self.emu.link_do(|x| x.connected());
             ui.section(if connected { "SERIAL - (Connected)" } else { "SERIAL" }, |ui| {
-                ui.label(format!("server listening on port {}", self.emu.link_port));
+                if self.emu.link_port == 0 {
+                    let (first, last) = self.emu.settings.listen_ports;
+                    ui.colored_label(Color32::YELLOW, format!("not listening: ports {first}-{last} are all taken"));
+                } else {
+                    ui.label(format!("server listening on port {}", self.emu.link_port));
+                }
+                let status = self.emu.link_do(|x| x.status());
+                if let Some(peer) = status.peer {
+                    ui.label(format!("peer: {}", peer.describe()));
+                }
+                for warning in status.warnings() {
+                    ui.colored_label(Color32::YELLOW, warning.as_str());
+                }
                 let res = ui.horizontal(|ui| {
                     let host = TextEdit::singleline(&mut self.emu.settings.host).desired_width(120.);
                     ui.label("Host: ");
@@ -43,16 +55,23 @@ impl<'a> Widget for Device<'a> {
                         ui.label(" Port: ");
                         ui.add(port);
                     }).response;
+                ui.horizontal(|ui| {
+                    let (first, last) = &mut self.emu.settings.listen_ports;
+                    ui.label("Listen ports (on restart): ");
+                    ui.add(DragValue::new(first).clamp_range(1..=u16::MAX));
+                    ui.label("-");
+                    ui.add(DragValue::new(last).clamp_range(*first..=u16::MAX));
+                    *last = (*last).max(*first);
+                });
                 ui.horizontal(|ui| {
                     if ui.button("Connect").clicked() {
-                        match (self.emu.settings.host.parse(), self.emu.settings.port.parse()) {
-                            (Ok(addr), Ok(port)) => {
-                                let addr: Ipv4Addr = addr;
-                                let port: u16 = port;
-                                self.emu.link_do(|link| link.connect(addr, port));
+                        match self.emu.settings.port.parse::<u16>() {
+                            Ok(port) => {
+                                let host = self.emu.settings.host.clone();
+                                self.emu.link_do(|link| link.connect(&host, port));
                             }
-                            (a, p) => {
-                                log::warn!("failed to parse: {a:?}, {p:?}");
+                            Err(e) => {
+                                log::warn!("failed to parse port: {e:?}");
                             }
                         }
                     }
@@ -60,6 +79,10 @@ impl<'a> Widget for Device<'a> {
                         self.emu.link_do(|x| x.disconnect());
                     }
                 });
+                let mut printer = self.emu.settings.printer;
+                if ui.checkbox(&mut printer, "Game Boy Printer").changed() {
+                    self.emu.set_printer(printer);
+                }
                 res
             });
             ui.section("Game", |ui| {
@@ -76,6 +99,36 @@ impl<'a> Widget for Device<'a> {
                 }
                 res | auto | r
             });
+            ui.section("Rewind", |ui| {
+                ui.label("Memory (MiB, 0 to disable): ") |
+                    ui.add(DragValue::new(&mut self.emu.settings.rewind_budget).clamp_range(0..=1024)) |
+                    ui.label("Snapshot every (frames): ") |
+                    ui.add(DragValue::new(&mut self.emu.settings.rewind_interval).clamp_range(1..=60))
+            });
+            ui.section("Tilt", |ui| {
+                ui.label("Use mouse: ") | ui.checkbox(&mut self.emu.settings.tilt_mouse, "")
+            });
+            ui.section("Camera", |ui| {
+                let source = self.emu.settings.camera.as_ref()
+                    .map(|x| x.display().to_string())
+                    .unwrap_or_else(|| "none".to_string());
+                let label = ui.label(format!("Images: {source}"));
+                let image = ui.button("Image...");
+                if image.clicked() {
+                    if let Some(path) = rfd::FileDialog::new().add_filter("image", &["png", "jpg", "jpeg", "bmp"]).pick_file() {
+                        self.emu.set_camera(Some(path));
+                    }
+                }
+                let folder = ui.button("Folder...");
+                if folder.clicked() {
+                    if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                        self.emu.set_camera(Some(path));
+                    }
+                }
+                let clear = ui.button("Clear");
+                if clear.clicked() { self.emu.set_camera(None); }
+                label | image | folder | clear
+            });
         }).response
     }
 }
@@ -85,6 +85,8 @@ impl<'a> Widget for Keybinds<'a> {
             ui.add(KeybindSection::new(self.settings, self.emu, KeyCat::debug()))
         }) | ui.section("SHORTCUT", |ui| {
             ui.add(KeybindSection::new(self.settings, self.emu, KeyCat::shortcuts()))
+        }) | ui.section("TILT", |ui| {
+            ui.add(KeybindSection::new(self.settings, self.emu, KeyCat::tilt()))
         })
     }
 }
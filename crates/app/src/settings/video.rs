@@ -1,5 +1,6 @@
 use shared::egui;
 use shared::egui::{Align, Response, Ui, Widget};
+use mem::colorize::{Colorization, COMBOS};
 use shared::utils::palette::Palette;
 use crate::emulator::Emulator;
 
@@ -53,6 +54,25 @@ impl<'a> Widget for Video<'a> {
                 self.emu.console.bus.set_palette(&mut self.emu.console.gb, self.emu.settings.palette);
             }
             ui.separator();
+            ui.with_layout(egui::Layout::top_down(Align::Center), |ui| {
+                ui.label("CGB - Colorization of DMG games");
+            });
+            let current = self.emu.console.rom().map(|rom| (rom.header.title.trim().to_string(), self.emu.colorization(rom)));
+            if let Some((title, cmp)) = current {
+                let mut colorization = cmp;
+                egui::ComboBox::from_label(title)
+                    .selected_text(colorization.name())
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut colorization, Colorization::Auto, Colorization::Auto.name());
+                        for n in 0..COMBOS.len() as u8 {
+                            ui.selectable_value(&mut colorization, Colorization::Manual(n), Colorization::Manual(n).name());
+                        }
+                        ui.selectable_value(&mut colorization, Colorization::Off, Colorization::Off.name());
+                    });
+                if cmp != colorization { self.emu.set_colorization(colorization); }
+            } else {
+                ui.label("no game running");
+            }
         }).response
     }
 }
\ No newline at end of file
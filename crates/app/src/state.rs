@@ -0,0 +1,261 @@
+use std::cmp::Ordering;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use bus::Devices;
+use shared::rom::Rom;
+use shared::utils::image::RawData;
+
+use crate::emulator::Console;
+
+const MAGIC: [u8; 4] = *b"GBST";
+
+/// Version of the serialized [`Console`]: bump it and add a [`MIGRATIONS`] entry whenever
+/// a serialized struct changes, states from before the header are format 0.
+pub const FORMAT: u16 = 2;
+
+/// `MIGRATIONS[n]` turns a console saved in format `n` into format `n + 1`.
+const MIGRATIONS: [fn(Vec<u8>) -> Result<Vec<u8>, String>; FORMAT as usize] = [
+    // the header was added around the same console
+    Ok,
+    sgb,
+];
+
+/// The joypad and lcd save their Super Game Boy state since format 2.
+fn sgb(console: Vec<u8>) -> Result<Vec<u8>, String> {
+    let mut rest = console.as_slice();
+    bincode::deserialize_from::<_, (i32, Option<Rom>, bus::Bus)>(&mut rest).map_err(|e| e.to_string())?;
+    let devices = Devices::migrate_sgb(rest).map_err(|e| e.to_string())?;
+    Ok([&console[..console.len() - rest.len()], &devices].concat())
+}
+
+/// Start of a state file, written before the [`State`] itself.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Header {
+    magic: [u8; 4],
+    pub format: u16,
+    /// global checksum of the cartridge
+    pub checksum: u16,
+    /// emulator version which saved the state
+    pub version: String,
+}
+
+impl Default for Header {
+    fn default() -> Self {
+        Self { magic: [0; 4], format: 0, checksum: 0, version: "unknown".to_string() }
+    }
+}
+
+impl Header {
+    pub fn new(checksum: u16) -> Self {
+        Self { magic: MAGIC, format: FORMAT, checksum, version: env!("CARGO_PKG_VERSION").to_string() }
+    }
+}
+
+/// Why a state may not load as expected, shown on its shelf entry.
+#[derive(Clone, Debug)]
+pub enum Issue {
+    Warning(String),
+    Error(String),
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct State {
+    pub console: Vec<u8>,
+    pub preview: RawData,
+    #[serde(skip)]
+    pub cover: Option<String>,
+    pub path: PathBuf,
+    pub ts: String,
+    #[serde(skip)]
+    pub header: Header,
+    #[serde(skip)]
+    pub issue: Option<Issue>,
+}
+
+impl State {
+    pub fn new(console: &Console, preview: RawData, path: PathBuf, ts: String) -> Self {
+        let checksum = console.rom().map_or(0, |x| x.header.checksum);
+        Self {
+            console: bincode::serialize(console).expect("cannot serialize Console"),
+            preview,
+            cover: None,
+            path,
+            ts,
+            header: Header::new(checksum),
+            issue: None,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v = bincode::serialize(&self.header).expect("failed to save state");
+        v.extend(bincode::serialize(self).expect("failed to save state"));
+        v
+    }
+
+    /// Reads a state file from its header, the console itself is only migrated and decoded by
+    /// [`State::load`]: a state which cannot be loaded is still returned, with the reason in
+    /// [`State::issue`].
+    pub fn from_bytes(raw: &[u8], path: &Path) -> Self {
+        let (header, body) = match raw.get(..4) {
+            Some(magic) if magic == MAGIC => {
+                match bincode::deserialize::<Header>(raw) {
+                    Ok(header) => {
+                        let len = bincode::serialized_size(&header).unwrap_or(0) as usize;
+                        (header, &raw[len.min(raw.len())..])
+                    }
+                    Err(e) => return Self::unreadable(path, format!("corrupted header: {e}")),
+                }
+            }
+            _ => (Header::default(), raw),
+        };
+        let mut state = match bincode::deserialize::<State>(body) {
+            Ok(state) => state,
+            Err(_) if header.format > FORMAT => return Self::unreadable(path, Self::newer(&header)),
+            Err(e) => return Self::unreadable(path, format!("corrupted state: {e}")),
+        };
+        state.issue = if header.format > FORMAT { Some(Issue::Error(Self::newer(&header))) } else { state.warning(&header) };
+        state.header = header;
+        state
+    }
+
+    /// States from before the header do not know their cartridge and are trusted.
+    fn check(&self, rom: Option<&Rom>) -> Result<(), String> {
+        let header = &self.header;
+        match rom {
+            Some(rom) if header.format != 0 && rom.header.checksum != header.checksum => {
+                Err(format!("saved for another cartridge (checksum {:04X}, not {:04X})", header.checksum, rom.header.checksum))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn migrate(&self) -> Result<Vec<u8>, String> {
+        let mut console = self.console.clone();
+        for (n, migrate) in MIGRATIONS.iter().enumerate().skip(self.header.format as usize) {
+            console = migrate(console).map_err(|e| format!("cannot migrate from format {n}: {e}"))?;
+        }
+        Ok(console)
+    }
+
+    fn warning(&self, header: &Header) -> Option<Issue> {
+        if header.format < FORMAT {
+            Some(Issue::Warning(format!("migrated from format {}", header.format)))
+        } else if header.version != env!("CARGO_PKG_VERSION") {
+            Some(Issue::Warning(format!("saved by version {}", header.version)))
+        } else {
+            None
+        }
+    }
+
+    fn newer(header: &Header) -> String {
+        format!("saved by a newer version ({}, format {} > {FORMAT})", header.version, header.format)
+    }
+
+    /// Placeholder listing a state file which cannot even be decoded.
+    fn unreadable(path: &Path, reason: String) -> Self {
+        Self {
+            console: vec![],
+            preview: RawData { w: 152, h: 144, data: vec![0; 152 * 144 * 4] },
+            cover: None,
+            path: path.to_path_buf(),
+            ts: path.file_stem().and_then(|x| x.to_str()).unwrap_or_default().to_string(),
+            header: Header::default(),
+            issue: Some(Issue::Error(reason)),
+        }
+    }
+
+    /// Decodes the console, if it was saved for the cartridge `rom` currently loaded.
+    pub fn load(&self, rom: Option<&Rom>) -> Result<Console, String> {
+        if let Some(Issue::Error(e)) = &self.issue { return Err(e.clone()); }
+        self.check(rom)?;
+        bincode::deserialize(&self.migrate()?)
+            .map_err(|e| format!("incompatible with this version (format {}, saved by {}): {e}", self.header.format, self.header.version))
+    }
+}
+
+impl PartialEq for State {
+    fn eq(&self, other: &Self) -> bool {
+        self.ts.eq(&other.ts)
+    }
+}
+
+impl Eq for State {}
+
+impl PartialOrd for State {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for State {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.ts.cmp(&self.ts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(header: Header) -> State {
+        State {
+            console: vec![1, 2, 3],
+            preview: RawData { w: 0, h: 0, data: vec![] },
+            cover: None,
+            path: PathBuf::from("test.state"),
+            ts: "test".to_string(),
+            header,
+            issue: None,
+        }
+    }
+
+    fn rom(checksum: u16) -> Rom {
+        let mut raw = vec![0; 0x8000];
+        raw[0x14E..0x150].copy_from_slice(&checksum.to_be_bytes());
+        Rom::from_bytes("test".to_string(), Default::default(), raw).unwrap()
+    }
+
+    #[test]
+    fn reads_the_header() {
+        let saved = state(Header::new(0x1234));
+        let read = State::from_bytes(&saved.to_bytes(), &saved.path);
+        assert_eq!((read.header.format, read.header.checksum), (FORMAT, 0x1234));
+        assert_eq!(read.console, saved.console);
+        assert!(read.issue.is_none());
+    }
+
+    #[test]
+    fn migrates_headerless_states() {
+        let saved = state(Header::default());
+        let raw = bincode::serialize(&saved).unwrap();
+        let read = State::from_bytes(&raw, &saved.path);
+        assert_eq!(read.header.format, 0);
+        assert!(matches!(read.issue, Some(Issue::Warning(_))));
+        // not a console, the format 1 migration can't find the devices
+        assert!(read.migrate().is_err_and(|e| e.contains("format 1")));
+        // without a header there is no checksum to compare
+        assert!(read.check(Some(&rom(0x1234))).is_ok());
+    }
+
+    #[test]
+    fn refuses_newer_formats() {
+        let mut header = Header::new(0x1234);
+        header.format = FORMAT + 1;
+        let saved = state(header);
+        let read = State::from_bytes(&saved.to_bytes(), &saved.path);
+        assert!(matches!(read.issue, Some(Issue::Error(_))));
+        assert!(read.load(None).is_err());
+    }
+
+    #[test]
+    fn checks_the_loaded_cartridge() {
+        let saved = state(Header::new(0x1234));
+        let read = State::from_bytes(&saved.to_bytes(), &saved.path);
+        assert!(read.check(Some(&rom(0x1234))).is_ok());
+        assert!(read.check(Some(&rom(0x4321))).is_err());
+        assert!(read.check(None).is_ok());
+    }
+}
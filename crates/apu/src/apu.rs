@@ -6,7 +6,7 @@ use shared::io::{IO, IODevice, IORegs};
 use shared::mem::IOBus;
 use shared::utils::FEdge;
 
-use super::Input;
+use super::{Capture, Input};
 
 mod dsg;
 
@@ -94,6 +94,14 @@ impl Apu {
         apu
     }
 
+    /// Apu whose output is buffered for the caller instead of a sound device,
+    /// one second of stereo samples is buffered, newer samples are dropped until it is drained.
+    pub fn capture(sample_rate: u32, cgb: bool) -> (Self, Capture) {
+        let (input, capture) = Input::capture(2 * sample_rate as usize);
+        (Self::new(sample_rate, input, cgb), capture)
+    }
+
+    #[cfg(feature = "driver")]
     pub(crate) fn switch(&mut self, new_rate: u32, input: Input) {
         self.input = input;
         self.sample = 0.;
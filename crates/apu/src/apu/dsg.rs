@@ -34,7 +34,7 @@ impl DSG {
     }
 
     pub fn hpf(&mut self, volume: u8) -> [f32; 2] {
-        let [l, r] = [1 + (volume & 0x70) >> 4, (volume & 0x7) + 1];
+        let [l, r] = [(1 + (volume & 0x70)) >> 4, (volume & 0x7) + 1];
         let [vl, vr] = [l as f32 / 8., r as f32 / 8.];
         let [l, r] = self.output; // 0.
         let [cl, cr] = self.capacitor; // -1.
@@ -31,8 +31,6 @@ pub(crate) trait SoundChannel: IODevice {
     fn output(&self, io: &mut IORegs) -> u8;
 
     fn channel(&self) -> Channels;
-    fn enable(&mut self) {}
-    fn disable(&mut self) {}
     fn dac_enabled(&self) -> bool { false }
 
     fn clock(&mut self, io: &mut IORegs);
@@ -196,7 +194,7 @@ impl Channel {
                 self.length_timer -= 1;
                 if self.length_timer == 0 { self.enabled = false; }
             }
-            Event::Sweep => if self.inner.sweep(io) { self.disable(io); },
+            Event::Sweep if self.inner.sweep(io) => self.disable(io),
             Event::Envelope => self.inner.envelope(),
             _ => {}
         }
@@ -195,7 +195,7 @@ impl SoundChannel for Channel {
     fn trigger(&mut self, _io: &mut IORegs) -> bool {
         self.triggered = true;
         self.envelope.trigger();
-        self.freq_timer = 4 * (0x7FF - self.freq) | (self.freq_timer & 0x3);
+        self.freq_timer = (4 * (0x7FF - self.freq)) | (self.freq_timer & 0x3);
         self.sweep.trigger(self.freq) > 2047
     }
 
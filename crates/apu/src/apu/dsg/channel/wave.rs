@@ -82,7 +82,7 @@ impl SoundChannel for Channel {
 
     fn trigger(&mut self, _io: &mut IORegs) -> bool {
         self.cycle = 1;
-        self.freq_timer = 2 * (0x7FF - self.freq) | (self.freq_timer & 0x3);
+        self.freq_timer = (2 * (0x7FF - self.freq)) | (self.freq_timer & 0x3);
         false
     }
 
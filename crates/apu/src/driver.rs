@@ -7,22 +7,9 @@ use cpal::{
     traits::{DeviceTrait, HostTrait},
 };
 use rodio::{cpal, Device, OutputStream, OutputStreamHandle, Sink, Source, SupportedStreamConfig};
-use rtrb::{Consumer, Producer, RingBuffer};
+use rtrb::{Consumer, RingBuffer};
 
-pub struct Input(pub(crate) Producer<f32>);
-
-impl Default for Input {
-    fn default() -> Self {
-        Self(RingBuffer::new(0).0)
-    }
-}
-
-impl Input {
-    pub fn write_sample(&mut self, samples: [f32; 2], volume: f32) {
-        self.0.push(samples[0] * volume).ok();
-        self.0.push(samples[1] * volume).ok();
-    }
-}
+use crate::Input;
 
 pub(crate) struct Output {
     sample_rate: u32,
@@ -64,10 +51,11 @@ pub(crate) struct Audio {
     stream: Option<OutputStream>,
     handle: Option<OutputStreamHandle>,
     sink: Sink,
+    muted: bool,
 }
 
 pub(crate) fn default_device() -> String {
-    cpal::default_host().default_output_device().unwrap().name().unwrap_or(Default::default())
+    cpal::default_host().default_output_device().unwrap().name().unwrap_or_default()
 }
 
 impl Audio {
@@ -90,7 +78,7 @@ impl Audio {
             &dev, SupportedStreamConfig::new(
                 2,
                 config.sample_rate(),
-                config.buffer_size().clone(),
+                *config.buffer_size(),
                 SampleFormat::F32))?;
         let sink = Sink::try_new(&handle)?;
         self.sample_rate = config.sample_rate();
@@ -98,6 +86,7 @@ impl Audio {
         self.dev_name = dev_name;
         self.stream = Some(stream);
         self.handle = Some(handle);
+        if self.muted { sink.set_volume(0.); }
         self.sink = sink;
         Ok(self)
     }
@@ -106,6 +95,13 @@ impl Audio {
         self.connect(name.into())
     }
 
+    /// Silences the output without touching the queued samples.
+    pub(crate) fn mute(&mut self, muted: bool) {
+        if self.muted == muted { return; }
+        self.muted = muted;
+        self.sink.set_volume(if muted { 0. } else { 1. });
+    }
+
     pub(crate) fn bind(&self) -> Input {
         let (producer, consumer) = RingBuffer::new(self.sample_rate() as usize);
         self.sink.append(Output::new(self.sample_rate(), consumer));
@@ -121,6 +117,7 @@ impl Audio {
             sample_rate: SampleRate(0),
             handle: None,
             stream: None,
+            muted: false,
         };
         if let Err(e) = audio.connect(config.dev_name.clone()) {
             log::error!("failed to switch to audio device {}: {e:?}", config.dev_name);
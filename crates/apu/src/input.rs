@@ -0,0 +1,36 @@
+use rtrb::{Consumer, Producer, RingBuffer};
+
+pub struct Input(pub(crate) Producer<f32>);
+
+impl Default for Input {
+    fn default() -> Self {
+        Self(RingBuffer::new(0).0)
+    }
+}
+
+impl Input {
+    /// Input whose samples are kept for the caller instead of being sent to a sound device.
+    pub fn capture(capacity: usize) -> (Self, Capture) {
+        let (producer, consumer) = RingBuffer::new(capacity);
+        (Self(producer), Capture(consumer))
+    }
+
+    pub fn write_sample(&mut self, samples: [f32; 2], volume: f32) {
+        self.0.push(samples[0] * volume).ok();
+        self.0.push(samples[1] * volume).ok();
+    }
+}
+
+/// Receiving end of [`Input::capture`], interleaved stereo samples (L, R, L, R...).
+pub struct Capture(Consumer<f32>);
+
+impl Capture {
+    pub fn drain(&mut self, buf: &mut Vec<f32>) -> usize {
+        let len = self.0.slots();
+        buf.reserve(len);
+        for _ in 0..len {
+            if let Ok(sample) = self.0.pop() { buf.push(sample); }
+        }
+        len
+    }
+}
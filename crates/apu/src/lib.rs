@@ -1,20 +1,27 @@
 extern crate core;
 
+#[cfg(feature = "driver")]
 use cpal::traits::DeviceTrait;
+#[cfg(feature = "driver")]
 use rodio::cpal;
 use serde::{Deserialize, Serialize};
 
 pub use apu::Apu;
-use driver::{Audio, Input};
+#[cfg(feature = "driver")]
+use driver::Audio;
+pub use input::{Capture, Input};
 
 mod apu;
+#[cfg(feature = "driver")]
 mod driver;
+mod input;
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct SoundConfig {
     dev_name: String,
 }
 
+#[cfg(feature = "driver")]
 impl Default for SoundConfig {
     fn default() -> Self {
         Self {
@@ -23,10 +30,12 @@ impl Default for SoundConfig {
     }
 }
 
+#[cfg(feature = "driver")]
 pub struct Controller {
     driver: Audio,
 }
 
+#[cfg(feature = "driver")]
 impl Controller {
     pub fn devices() -> impl Iterator<Item=String> {
         Audio::devices().filter_map(|x| x.name().ok())
@@ -58,6 +67,11 @@ impl Controller {
         Apu::new(self.driver.sample_rate(), self.driver.bind(), cgb)
     }
 
+    /// Sound output stays silent while muted, e.g. while rewinding.
+    pub fn mute(&mut self, muted: bool) {
+        self.driver.mute(muted);
+    }
+
     pub fn reload(&mut self, apu: &mut Apu) {
         apu.input = self.driver.bind();
     }
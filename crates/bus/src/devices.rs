@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
-use apu::{Apu, Controller};
+use apu::Apu;
+#[cfg(feature = "sound")]
+use apu::Controller;
 use joy::Joypad;
 use shared::audio_settings::AudioSettings;
 use shared::breakpoints::Breakpoints;
@@ -9,20 +11,30 @@ use shared::mem::IOBus;
 use crate::Timer;
 
 #[derive(Default)]
-pub struct ConsoleBuilder<'a> {
-    apu: Option<&'a Controller>,
+pub struct ConsoleBuilder {
+    apu: Option<Apu>,
     serial: Option<serial::Port>,
     skip: bool,
     cgb: bool,
+    sgb: bool,
 }
 
-impl<'a> ConsoleBuilder<'a> {
+impl ConsoleBuilder {
     pub fn with_link(mut self, cable: serial::com::Serial) -> Self {
-        self.serial = Some(serial::Port::new(cable));
+        let mut port = serial::Port::new();
+        port.plug(Box::new(cable));
+        self.serial = Some(port);
         self
     }
 
-    pub fn with_sound_driver(mut self, apu: &'a Controller) -> Self {
+    /// binds the apu to the driver output right away, call after [`ConsoleBuilder::set_cgb`]
+    #[cfg(feature = "sound")]
+    pub fn with_sound_driver(mut self, apu: &Controller) -> Self {
+        self.apu = Some(apu.apu(self.cgb));
+        self
+    }
+
+    pub fn with_apu(mut self, apu: Apu) -> Self {
         self.apu = Some(apu);
         self
     }
@@ -41,19 +53,26 @@ impl<'a> ConsoleBuilder<'a> {
         self
     }
 
+    /// Super Game Boy: command packets through the joypad, palettes and border on the lcd.
+    pub fn set_sgb(mut self, sgb: bool) -> Self {
+        self.sgb = sgb;
+        self
+    }
+
     pub fn build(self) -> Devices {
         let mut cpu = cpu::Cpu::default();
 
-        let lcd = lcd::Lcd::default();
+        let mut lcd = lcd::Lcd::default();
+        lcd.set_sgb(self.sgb);
         let ppu = ppu::Controller::new();
-        let joy = Joypad::new();
+        let joy = if self.sgb { Joypad::sgb() } else { Joypad::new() };
         if self.skip { cpu.skip_boot(self.cgb); }
 
         Devices {
             cpu,
             ppu,
             lcd,
-            apu: self.apu.map(|x| x.apu(self.cgb)).unwrap_or_default(),
+            apu: self.apu.unwrap_or_default(),
             dma: ppu::Dma::default(),
             hdma: ppu::Hdma::default(),
             serial: self.serial.unwrap_or_default(),
@@ -82,10 +101,33 @@ impl Default for Devices {
 }
 
 impl Devices {
-    pub fn builder<'a>() -> ConsoleBuilder<'a> {
+    pub fn builder() -> ConsoleBuilder {
         ConsoleBuilder::default()
     }
 
+    /// Swaps in the emulated state of `load`, keeping this console's host handles
+    /// (window, sound output, serial link and cpu trace).
+    pub fn reload(self, load: Self) -> Self {
+        Self {
+            cpu: self.cpu.reload(load.cpu),
+            lcd: self.lcd.reload(load.lcd),
+            apu: self.apu.reload(load.apu),
+            serial: self.serial,
+            ..load
+        }
+    }
+
+    /// Adds the Super Game Boy state of the joypad and the lcd, both `None`, to devices
+    /// serialized before they were saved.
+    pub fn migrate_sgb(raw: &[u8]) -> bincode::Result<Vec<u8>> {
+        let mut rest = raw;
+        bincode::deserialize_from::<_, (cpu::Cpu, ppu::Controller, u8)>(&mut rest)?;
+        let joy = raw.len() - rest.len();
+        bincode::deserialize_from::<_, (bool, Vec<u8>)>(&mut rest)?;
+        let lcd = raw.len() - rest.len();
+        Ok([&raw[..joy], &[0], &raw[joy..lcd], &[0], rest].concat())
+    }
+
     pub fn io_write(&mut self, io: u16, v: u8, bus: &mut dyn IOBus) {
         if let Ok(io) = IO::try_from(io) {
             self.ppu.write(io, v, bus);
@@ -94,6 +136,7 @@ impl Devices {
             self.apu.write(io, v, bus);
             self.serial.write(io, v, bus);
             self.joy.write(io, v, bus);
+            if let Some(command) = self.joy.command() { self.lcd.command(&command); }
         }
     }
 }
@@ -123,3 +166,19 @@ pub struct Settings<'a> {
 //         }
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_the_sgb_state() {
+        let devices = Devices::default();
+        let saved = bincode::serialize(&devices).unwrap();
+        let joy = bincode::serialized_size(&(&devices.cpu, &devices.ppu, devices.joy.state())).unwrap() as usize;
+        let lcd = joy + 1 + bincode::serialized_size(&(false, vec![0u8; (4 * lcd::Lcd::WIDTH * lcd::Lcd::HEIGHT) as usize])).unwrap() as usize;
+        assert_eq!((saved[joy], saved[lcd]), (0, 0));
+        let old = [&saved[..joy], &saved[joy + 1..lcd], &saved[lcd + 1..]].concat();
+        assert_eq!(Devices::migrate_sgb(&old).unwrap(), saved);
+    }
+}
@@ -1,13 +1,22 @@
+pub use cpu::Trace;
 pub use devices::Devices;
 pub use devices::Settings;
+
+use std::path::PathBuf;
+
 use mem::{Hram, mbc, Oam, Vram, Wram};
+use mem::colorize::Colorization;
 use shared::{cpu::MemStatus, cpu::Op, mem::*};
-use shared::io::{IO, IODevice, IOReg, IORegs};
+use shared::breakpoints::{Banks, Inspect};
+use shared::cheats::{Cheat, Code};
+use shared::io::{DMG_MODE, IO, IODevice, IOReg, IORegs};
 use shared::rom::Rom;
 use shared::serde::{Deserialize, Serialize};
+use shared::utils::camera::Sensor;
 use shared::utils::palette::Palette;
 pub use timer::Timer;
 
+pub mod movie;
 mod timer;
 mod devices;
 
@@ -29,12 +38,19 @@ pub struct Bus {
     ie: IOReg,
     status: MemStatus,
     last: Option<Op>,
+    #[serde(default, skip)]
+    shark: Vec<Code>,
+    /// colorization of a DMG game, applied once the boot is done
+    #[serde(default, skip)]
+    colorization: Option<Colorization>,
 }
 
 pub struct Builder<'a> {
     palette: Option<Palette>,
     skip: bool,
     cgb: bool,
+    ram: Option<Vec<u8>>,
+    colorization: Colorization,
     rom: &'a Rom,
 }
 
@@ -54,10 +70,29 @@ impl<'a> Builder<'a> {
         self
     }
 
+    /// Palettes of a DMG game on a CGB, the boot ROM picks them itself on [`Colorization::Auto`].
+    pub fn colorization(mut self, colorization: Colorization) -> Self {
+        self.colorization = colorization;
+        self
+    }
+
+    /// Starts with this cartridge ram instead of the save file, nothing is written back to disk.
+    pub fn cartridge_ram(mut self, ram: Vec<u8>) -> Self {
+        self.ram = Some(ram);
+        self
+    }
+
     pub fn build(self) -> Bus {
+        let mbc = match self.ram {
+            Some(ram) => mbc::Controller::with_ram(self.rom, self.cgb, ram),
+            None => mbc::Controller::new(self.rom, self.cgb),
+        };
         let mut bus = Bus::new(self.cgb, self.palette.unwrap_or(Palette::GrayScale))
-            .with_mbc(mbc::Controller::new(self.rom, self.cgb));
-        if self.skip { bus.skip_boot(self.rom.raw()[0x143]); }
+            .with_mbc(mbc);
+        if self.skip { bus.skip_boot(); }
+        if self.cgb && (self.skip || self.colorization != Colorization::Auto) {
+            bus.colorization = Some(self.colorization);
+        }
         bus
     }
 }
@@ -67,8 +102,8 @@ impl Default for Bus {
 }
 
 impl Bus {
-    pub fn init(rom: &Rom) -> Builder {
-        Builder { rom, skip: false, cgb: false, palette: None }
+    pub fn init(rom: &Rom) -> Builder<'_> {
+        Builder { rom, skip: false, cgb: false, ram: None, palette: None, colorization: Colorization::Auto }
     }
 
     fn new(cgb: bool, palette: Palette) -> Self {
@@ -84,6 +119,8 @@ impl Bus {
             un_1: Empty {},
             ie: IOReg::with_access(IO::IE.access()),
             status: MemStatus::ReqRead(0x0),
+            shark: vec![],
+            colorization: None,
         }
     }
 
@@ -92,10 +129,12 @@ impl Bus {
         self
     }
 
-    fn skip_boot(&mut self, console: u8) -> &mut Self {
+    /// The console mode comes from the header of the mapped rom, the menu of a multicart.
+    fn skip_boot(&mut self) -> &mut Self {
         self.status = MemStatus::ReqRead(0x100);
-        self.io.skip_boot(console);
         self.mbc.inner_mut().post();
+        let console = self.mbc.inner().value(0x143, 0x143);
+        self.io.skip_boot(console);
         self
     }
 
@@ -153,21 +192,89 @@ impl Bus {
                 }
             }
         }
+        if let Some(colorization) = self.colorization {
+            if self.io.io(IO::POST).value() != 0 { self.colorize(devices, colorization); }
+        }
+        let frame = devices.lcd.frames();
         devices.ppu.tick(&mut self.io, &mut self.oam, &mut self.vram, &mut devices.lcd);
+        if frame != devices.lcd.frames() { self.apply_shark(); }
         devices.apu.tick(&mut self.io, ds, settings.sound);
-        let bp = settings.breakpoints.tick(&devices.cpu, self.last());
+        let bp = settings.breakpoints.tick(&devices.cpu, self.last(), &*self);
         devices.cpu.reset_finished();
         bp
     }
 
+    /// Whether the serial port lets the console run, see [`serial::Port::ready`].
+    pub fn link_ready(&mut self, devices: &mut Devices) -> bool {
+        devices.serial.ready(&mut self.io)
+    }
+
     pub fn save(&mut self, autosave: bool) {
         self.mbc.inner_mut().save(autosave);
     }
 
+    /// Enables the Game Genie (rom patches) and GameShark (ram writes on vblank) codes of `cheats`.
+    pub fn set_cheats(&mut self, cheats: &[Cheat]) {
+        self.mbc.inner_mut().set_genie(shared::cheats::active(cheats));
+        self.shark = shared::cheats::active(cheats)
+            .filter(|x| matches!(x, Code::Shark { .. }))
+            .collect();
+    }
+
+    fn apply_shark(&mut self) {
+        for code in &self.shark {
+            let Code::Shark { bank, addr, value } = *code else { continue };
+            match addr {
+                SRAM..=SRAM_END => self.mbc.write(addr - SRAM, value, addr),
+                RAM..=RAM_END if bank & 0xF0 == 0x90 => self.ram.inner_mut().write_bank(bank & 0x7, addr - RAM, value),
+                RAM..=RAM_END => self.ram.write(addr - RAM, value, addr),
+                ECHO..=ECHO_END => self.ram.write(addr - ECHO, value, addr),
+                HRAM..=HRAM_END => self.hram.write(addr - HRAM, value, addr),
+                _ => {}
+            }
+        }
+    }
+
+    pub fn cartridge_ram(&self) -> Vec<u8> { self.mbc.inner().ram_dump() }
+
+    /// Decouples the cartridge clock from the host clock, see [`shared::utils::rtc::Rtc`].
+    /// Returns the previous mode.
+    pub fn emulated_time(&mut self, emulated: bool) -> bool { self.mbc.inner_mut().emulated_time(emulated) }
+
+    /// Stops writing the battery save to disk, returns where it went.
+    pub fn detach_save(&mut self) -> Option<PathBuf> { self.mbc.inner_mut().detach_save() }
+
+    pub fn attach_save(&mut self, sav: Option<PathBuf>) { self.mbc.inner_mut().attach_save(sav); }
+
+    /// Cartridge accelerometer (MBC7), in g along each axis, clamped to [-1, 1].
+    pub fn set_tilt(&mut self, x: f32, y: f32) { self.mbc.inner_mut().set_tilt(x, y); }
+
+    /// Image source of the Pocket Camera sensor.
+    pub fn set_camera(&mut self, sensor: Sensor) { self.mbc.inner_mut().set_camera(sensor); }
+
+    /// Moves the cartridge rom out, so snapshots don't carry it, see [`Bus::put_rom`].
+    pub fn take_rom(&mut self) -> Vec<u8> { self.mbc.inner_mut().take_rom() }
+
+    pub fn put_rom(&mut self, rom: Vec<u8>) { self.mbc.inner_mut().put_rom(rom); }
+
     pub fn set_palette(&mut self, devices: &mut Devices, palette: Palette) {
         self.io.set_palette(palette);
         devices.ppu.write(IO::DMGP, 0, self);
     }
+
+    /// Debugger write, not seen by the access breakpoints.
+    pub fn debug_write(&mut self, addr: u16, value: u8) {
+        shared::cpu::Bus::write(self, addr, value);
+        self.last = None;
+    }
+
+    /// Colorizes the DMG game running in compatibility mode, with the keys currently held.
+    pub fn colorize(&mut self, devices: &mut Devices, colorization: Colorization) {
+        self.colorization = None;
+        if self.io.io(IO::CGB).value() == 0 || self.io.io(IO::KEY0).value() != DMG_MODE { return; }
+        let rom = (0..0x150).map(|addr| self.read(addr)).collect::<Vec<_>>();
+        devices.ppu.colorize(colorization.palettes(&rom, devices.joy.state()));
+    }
 }
 
 impl shared::cpu::Bus for Bus {
@@ -326,6 +433,24 @@ impl IOBus for Bus {
     }
 }
 
+impl Inspect for Bus {
+    /// Banks mapped in the cartridge, WRAM and VRAM areas.
+    fn banks(&self) -> Banks {
+        let mbc = self.mbc.inner();
+        let cgb = self.io.io(IO::CGB).value() != 0;
+        Banks {
+            rom: mbc.rom_bank() as u16,
+            ram: mbc.ram_bank() as u16,
+            wram: if cgb { (self.io.io(IO::SVBK).value() & 0x7).max(1) as u16 } else { 1 },
+            vram: if cgb { (self.io.io(IO::VBK).value() & 0x1) as u16 } else { 0 },
+        }
+    }
+
+    fn peek(&self, addr: u16) -> u8 {
+        shared::cpu::Bus::get_range(self, addr, 1).first().copied().unwrap_or(0xFF)
+    }
+}
+
 impl ppu::VramAccess for Bus {
     fn vram(&self) -> &Vram {
         self.vram.inner()
@@ -0,0 +1,162 @@
+use std::fs::File;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use shared::input::{Joypad, KeyCat};
+use shared::io::IORegs;
+use shared::mem::IOBus;
+use shared::rom::Rom;
+use shared::serde::{Deserialize, Serialize};
+
+use crate::{Bus, Devices};
+
+const FRAME_CYCLES: usize = 70224;
+
+/// Where a movie starts from.
+#[derive(Serialize, Deserialize)]
+pub enum Anchor {
+    /// Power on, with this cartridge ram (and clock) content.
+    PowerOn(Vec<u8>),
+    /// A `(Bus, Devices)` snapshot, and the clock phase it was taken at.
+    State { state: Vec<u8>, clock: u8 },
+}
+
+/// Joypad state for every frame since the anchor.
+#[derive(Serialize, Deserialize)]
+pub struct Movie {
+    pub title: String,
+    pub cgb: bool,
+    pub skip_boot: bool,
+    pub anchor: Anchor,
+    inputs: Vec<u8>,
+}
+
+impl Movie {
+    /// Anchors a movie to a console that was just powered on.
+    pub fn power_on(rom: &Rom, cgb: bool, skip_boot: bool, bus: &Bus) -> Self {
+        Self {
+            title: rom.header.title.clone(),
+            cgb,
+            skip_boot,
+            anchor: Anchor::PowerOn(bus.cartridge_ram()),
+            inputs: vec![],
+        }
+    }
+
+    /// Anchors a movie to the current state of a running console.
+    pub fn snapshot(rom: &Rom, cgb: bool, bus: &Bus, gb: &Devices, clock: u8) -> Self {
+        let state = bincode::serialize(&(bus, gb)).expect("cannot serialize console");
+        Self {
+            title: rom.header.title.clone(),
+            cgb,
+            skip_boot: true,
+            anchor: Anchor::State { state, clock },
+            inputs: vec![],
+        }
+    }
+
+    /// Rebuilds the console at the anchor, returns it with its clock phase. Its cartridge
+    /// clock only counts emulated time and nothing is written back to disk.
+    pub fn console(&self, rom: &Rom) -> Option<(Bus, Devices, u8)> {
+        let (mut bus, gb, clock) = match &self.anchor {
+            Anchor::PowerOn(ram) => {
+                let bus = Bus::init(rom)
+                    .cgb(self.cgb)
+                    .skip_boot(self.skip_boot)
+                    .cartridge_ram(ram.clone())
+                    .build();
+                let gb = Devices::builder()
+                    .skip_boot(self.skip_boot)
+                    .set_cgb(self.cgb)
+                    .build();
+                (bus, gb, 0)
+            }
+            Anchor::State { state, clock } => {
+                let (bus, gb) = bincode::deserialize::<(Bus, Devices)>(state)
+                    .map_err(|e| log::warn!("invalid movie state: {e:?}"))
+                    .ok()?;
+                (bus, gb, *clock)
+            }
+        };
+        bus.emulated_time(true);
+        bus.detach_save();
+        Some((bus, gb, clock))
+    }
+
+    pub fn frames(&self) -> usize { self.inputs.len() }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        bincode::deserialize_from(File::open(path)?)
+            .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, format!("{e:?}")))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        let raw = bincode::serialize(self)
+            .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, format!("{e:?}")))?;
+        File::create(path)?.write_all(&raw)
+    }
+}
+
+/// Records or replays a [`Movie`]: joypad changes are only applied on frame boundaries
+/// (every 70224 cycles since the anchor), so recording and playback see the same inputs.
+pub struct Tape {
+    movie: Movie,
+    playing: bool,
+    input: u8,
+    cycles: usize,
+    /// battery save and cartridge clock mode of the recording console, given back on eject
+    restore: Option<(Option<PathBuf>, bool)>,
+}
+
+impl Tape {
+    /// Records on `bus`, which only counts emulated time and keeps its battery save off the
+    /// disk until [`Tape::eject`].
+    pub fn record(movie: Movie, bus: &mut Bus) -> Self {
+        let clock = bus.emulated_time(true);
+        let restore = Some((bus.detach_save(), clock));
+        Self { movie, playing: false, input: 0, cycles: 0, restore }
+    }
+
+    pub fn play(movie: Movie) -> Self {
+        Self { movie, playing: true, input: 0, cycles: 0, restore: None }
+    }
+
+    pub fn playing(&self) -> bool { self.playing }
+    pub fn frame(&self) -> usize { self.cycles / FRAME_CYCLES }
+    pub fn movie(&self) -> &Movie { &self.movie }
+
+    /// Stops the recording (or playback) on `bus` and returns the movie.
+    pub fn eject(self, bus: &mut Bus) -> Movie {
+        if let Some((sav, clock)) = self.restore {
+            bus.attach_save(sav);
+            bus.emulated_time(clock);
+        }
+        self.movie
+    }
+
+    /// Called before every bus tick, returns false once playback reached the end of the movie.
+    pub fn tick(&mut self, bus: &mut Bus, gb: &mut Devices) -> bool {
+        if self.cycles % FRAME_CYCLES == 0 {
+            let frame = self.frame();
+            if self.playing {
+                let Some(&input) = self.movie.inputs.get(frame) else { return false };
+                self.input = input;
+            } else {
+                self.movie.inputs.push(self.input);
+            }
+            gb.joy.set_state(self.input, bus.io_regs());
+        }
+        self.cycles += 1;
+        true
+    }
+}
+
+impl Joypad for Tape {
+    /// Buffers a key change until the next frame, ignored during playback.
+    fn update(&mut self, key: KeyCat, pressed: bool, _: &mut IORegs) {
+        if let (KeyCat::Joy(key), false) = (key, self.playing) {
+            let mask = 1 << (key as u8);
+            self.input = (self.input & !mask) | if pressed { mask } else { 0 };
+        }
+    }
+}
@@ -2,7 +2,7 @@ use serde::{Deserializer, Serializer};
 use shared::{cpu::{Opcode, Reg, Value}};
 use shared::serde::{Deserialize, Serialize};
 
-use crate::Bus;
+use crate::{Bus, Trace};
 
 use super::{decode::decode, ops::*, Registers, State};
 
@@ -25,7 +25,7 @@ pub struct Cpu {
     prefixed: bool,
     finished: bool,
     ime: bool,
-    doctor: Option<std::fs::File>,
+    trace: Option<Trace>,
     stop: usize,
 }
 
@@ -38,12 +38,12 @@ impl Clone for Cpu {
             instructions: self.instructions,
             ins: self.ins,
             count: self.count,
-            regs: self.regs.clone(),
+            regs: self.regs,
             cache: self.cache.clone(),
             prefixed: self.prefixed,
             finished: self.finished,
             ime: self.ime,
-            doctor: None,
+            trace: None,
             stop: self.stop
         }
     }
@@ -85,7 +85,7 @@ impl <'de> Deserialize<'de> for Cpu {
                 prefixed: inner.prefixed,
                 finished: inner.finished,
                 ime: inner.ime,
-                doctor: None,
+                trace: None,
                 stop: inner.stop
             }
         })
@@ -101,10 +101,6 @@ impl shared::cpu::Cpu for Cpu {
 
 impl Default for Cpu {
     fn default() -> Self {
-        #[cfg(feature = "log_opcode")]
-            let doctor = std::fs::File::open("opcodes.log").ok();
-        #[cfg(not(feature = "log_opcode"))]
-            let doctor = None;
         Self {
             mode: Mode::Running,
             prev: Opcode::Nop,
@@ -117,7 +113,7 @@ impl Default for Cpu {
             finished: false,
             ime: false,
             at: 0,
-            doctor,
+            trace: None,
             stop: 0,
         }
     }
@@ -127,7 +123,7 @@ impl Cpu {
 
     pub fn reload(self, load: Self) -> Self {
         let mut t = load;
-        t.doctor = self.doctor;
+        t.trace = self.trace;
         t
     }
 
@@ -136,6 +132,12 @@ impl Cpu {
     }
 
     pub fn registers(&self) -> &Registers { &self.regs }
+    pub fn registers_mut(&mut self) -> &mut Registers { &mut self.regs }
+
+    /// Sets the instruction trace sink, `None` disables tracing.
+    pub fn set_trace(&mut self, trace: Option<Trace>) { self.trace = trace; }
+    pub fn take_trace(&mut self) -> Option<Trace> { self.trace.take() }
+    pub fn trace(&self) -> Option<&Trace> { self.trace.as_ref() }
 
     fn check_interrupts(&mut self, bus: &mut dyn Bus) {
         if self.ins < self.count || self.prev == Opcode::Ei { return; };
@@ -180,26 +182,11 @@ impl Cpu {
             let opcode = state.read();
             let Ok(opcode) = Opcode::try_from((opcode, prefixed)) else { unreachable!(); };
 
-            self.doctor.as_mut().map(|x| {
-                use std::io::Write;
-                if !prefixed {
-                    let (a, f, b, c, d, e, h, l, sp, pc) = (
-                        state.register(Reg::A).u8(), state.register(Reg::F).u8(), state.register(Reg::B).u8(), state.register(Reg::C).u8(),
-                        state.register(Reg::D).u8(), state.register(Reg::E).u8(), state.register(Reg::H).u8(), state.register(Reg::L).u8(),
-                        state.register(Reg::SP).u16(), state.register(Reg::PC).u16());
-                    let [pc0, pc1, pc2, pc3] = [
-                        state.bus.direct_read(pc),
-                        state.bus.direct_read(pc + 1),
-                        state.bus.direct_read(pc + 2),
-                        state.bus.direct_read(pc + 3)];
-                    let buff = format!("\
-                    A:{:02X} F:{:02X} B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} L:{:02X}\
-                    SP:{:04X} PC:{:04X} PCMEM:{:02X},{:02X},{:02X},{:02X} INS: {:?} NEXT: {:?}\n",
-                                       a, f, b, c, d, e, h, l, sp, pc, pc0, pc1, pc2, pc3, self.prev, opcode);
-                    log::info!("{buff}");
-                    x.write_all(buff.as_bytes()).ok();
-                }
-            });
+            if let Some(trace) = self.trace.as_mut().filter(|_| !prefixed) {
+                let pc = state.regs.pc();
+                let mem = [0, 1, 2, 3].map(|n| state.bus.direct_read(pc.wrapping_add(n)));
+                trace.log(state.regs, mem);
+            }
             self.instructions = decode(opcode);
             self.ins = 0;
             self.count = self.instructions.len();
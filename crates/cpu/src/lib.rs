@@ -1,6 +1,7 @@
 extern crate core;
 
 pub use cpu::Cpu;
+pub use trace::Trace;
 use registers::*;
 use shared::cpu::{Bus, CBOpcode, MemStatus, Opcode, Reg, regs, Value};
 
@@ -10,6 +11,7 @@ mod cpu;
 mod ops;
 mod registers;
 mod decode;
+mod trace;
 
 trait RWStatus {
     fn read(&mut self) -> u8;
@@ -163,7 +165,7 @@ impl<'a> State<'a> {
     }
 
     pub fn peek(&self) -> Option<Value> {
-        self.cache.get(0).map(|x| *x)
+        self.cache.first().copied()
     }
 
     pub fn push<V: Into<Value>>(&mut self, value: V) {
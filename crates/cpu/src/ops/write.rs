@@ -82,14 +82,14 @@ pub fn pc(state: &mut State) -> Flow {
 }
 
 pub fn mem(state: &mut State) -> Flow {
-    let v = state.try_pop().expect(format!("failed to pop mem (pc {:#06X})", state.regs.pc()).as_str());
+    let v = state.try_pop().unwrap_or_else(|| panic!("failed to pop mem (pc {:#06X})", state.regs.pc()));
     let Value::U8(v) = v else { panic!("failed to pop mem (pc: {:#06X}", state.regs.pc()); };
     state.write(v);
     CONTINUE
 }
 
 pub fn low(state: &mut State) -> Flow {
-    let v = state.try_pop().expect(format!("failed to pop mem (pc {:#06X})", state.regs.pc()).as_str());
+    let v = state.try_pop().unwrap_or_else(|| panic!("failed to pop mem (pc {:#06X})", state.regs.pc()));
     let Value::U16(v) = v else { panic!("Expected u16 on stack (pc: {:#06X}", state.regs.pc()); };
     let [low, high] = v.to_le_bytes();
     state.write_low(low);
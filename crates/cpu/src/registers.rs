@@ -35,6 +35,28 @@ impl Registers {
             e => panic!("invalid read {:?}", e)
         }
     }
+
+    /// False when the value does not have the size of the register.
+    pub fn write(&mut self, reg: Reg, value: Value) -> bool {
+        match (reg, value) {
+            (Reg::A, Value::U8(v)) => self.a = v,
+            (Reg::F, Value::U8(v)) => self.f = v & 0xF0,
+            (Reg::B, Value::U8(v)) => self.b = v,
+            (Reg::C, Value::U8(v)) => self.c = v,
+            (Reg::D, Value::U8(v)) => self.d = v,
+            (Reg::E, Value::U8(v)) => self.e = v,
+            (Reg::H, Value::U8(v)) => self.h = v,
+            (Reg::L, Value::U8(v)) => self.l = v,
+            (Reg::AF, Value::U16(v)) => self.set_af(v & 0xFFF0),
+            (Reg::BC, Value::U16(v)) => self.set_bc(v),
+            (Reg::DE, Value::U16(v)) => self.set_de(v),
+            (Reg::HL, Value::U16(v)) => self.set_hl(v),
+            (Reg::SP, Value::U16(v)) => self.sp = v,
+            (Reg::PC, Value::U16(v)) => self.pc = v,
+            _ => return false,
+        }
+        true
+    }
     pub fn a(&self) -> u8 { self.a }
     pub fn f(&self) -> u8 { self.f }
     pub fn b(&self) -> u8 { self.b }
@@ -0,0 +1,132 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::Registers;
+
+/// Instruction trace in the gameboy-doctor format, one line per executed instruction:
+/// `A:00 F:11 B:22 C:33 D:44 E:55 H:66 L:77 SP:8888 PC:9999 PCMEM:AA,BB,CC,DD`
+pub struct Trace {
+    sink: BufWriter<Box<dyn Write + Send>>,
+    start: Option<u16>,
+    stop: Option<u16>,
+    max: Option<usize>,
+    lines: usize,
+    active: bool,
+    done: bool,
+}
+
+impl Trace {
+    pub fn new<W: Write + Send + 'static>(sink: W) -> Self {
+        Self {
+            sink: BufWriter::new(Box::new(sink)),
+            start: None,
+            stop: None,
+            max: None,
+            lines: 0,
+            active: true,
+            done: false,
+        }
+    }
+
+    pub fn create<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        Ok(Self::new(File::create(path)?))
+    }
+
+    /// Only starts logging once PC reaches `pc`.
+    pub fn start_at(mut self, pc: u16) -> Self {
+        self.start = Some(pc);
+        self.active = false;
+        self
+    }
+
+    /// Stops logging when PC reaches `pc`, the instruction at `pc` is not logged.
+    pub fn stop_at(mut self, pc: u16) -> Self {
+        self.stop = Some(pc);
+        self
+    }
+
+    pub fn max_lines(mut self, max: usize) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    pub fn lines(&self) -> usize { self.lines }
+    pub fn done(&self) -> bool { self.done }
+
+    fn finish(&mut self) {
+        self.done = true;
+        self.sink.flush().ok();
+    }
+
+    pub(crate) fn log(&mut self, regs: &Registers, mem: [u8; 4]) {
+        if self.done { return; }
+        let pc = regs.pc();
+        if !self.active {
+            if self.start != Some(pc) { return; }
+            self.active = true;
+        }
+        if self.stop == Some(pc) || matches!(self.max, Some(max) if self.lines >= max) {
+            return self.finish();
+        }
+        let res = writeln!(self.sink,
+            "A:{:02X} F:{:02X} B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} L:{:02X} SP:{:04X} PC:{:04X} PCMEM:{:02X},{:02X},{:02X},{:02X}",
+            regs.a(), regs.f(), regs.b(), regs.c(), regs.d(), regs.e(), regs.h(), regs.l(), regs.sp(), pc,
+            mem[0], mem[1], mem[2], mem[3]);
+        if let Err(e) = res {
+            log::warn!("trace stopped: {e}");
+            return self.finish();
+        }
+        self.lines += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> { self.0.lock().unwrap().write(buf) }
+        fn flush(&mut self) -> std::io::Result<()> { Ok(()) }
+    }
+
+    fn registers(pc: u16) -> Registers {
+        let mut regs = Registers::default();
+        regs.set_af(0x01B0);
+        regs.set_bc(0x0013);
+        regs.set_de(0x00D8);
+        regs.set_hl(0x014D);
+        regs.set_sp(0xFFFE);
+        regs.set_pc(pc);
+        regs
+    }
+
+    fn run(trace: Trace, out: &Shared, pcs: std::ops::Range<u16>) -> Vec<String> {
+        let mut trace = trace;
+        for pc in pcs { trace.log(&registers(pc), [0x00, 0xC3, 0x13, 0x02]); }
+        drop(trace);
+        String::from_utf8(out.0.lock().unwrap().clone()).unwrap().lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn doctor_format() {
+        let out = Shared::default();
+        let lines = run(Trace::new(out.clone()), &out, 0x100..0x101);
+        assert_eq!(lines, ["A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0100 PCMEM:00,C3,13,02"]);
+    }
+
+    #[test]
+    fn bounds() {
+        let out = Shared::default();
+        let lines = run(Trace::new(out.clone()).start_at(0x102).stop_at(0x105), &out, 0x100..0x108);
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains("PC:0102") && lines[2].contains("PC:0104"));
+        let out = Shared::default();
+        assert_eq!(run(Trace::new(out.clone()).max_lines(2), &out, 0x100..0x108).len(), 2);
+    }
+}
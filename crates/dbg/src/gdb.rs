@@ -0,0 +1,391 @@
+//! GDB remote serial protocol stub, listening on localhost.
+//!
+//! The registers (`g`/`G`, `p`/`P`) are AF, BC, DE, HL, SP and PC, 16 bits little endian,
+//! in the order of the gdb z80 target. `monitor help` lists the bank-aware commands.
+
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use shared::breakpoints::{Access, Breakpoint, Value as Expected};
+use shared::cpu::{Reg, Value};
+use shared::emulator::{Emulator, WriteAccess};
+
+const REGISTERS: [Reg; 6] = [Reg::AF, Reg::BC, Reg::DE, Reg::HL, Reg::SP, Reg::PC];
+
+/// Client input between two packets, in a packet or in its checksum.
+enum Parse {
+    Idle,
+    Data(Vec<u8>),
+    Checksum(Vec<u8>, Vec<u8>),
+}
+
+/// What a byte of client input completes.
+#[derive(Debug, PartialEq)]
+enum Input {
+    Packet(String),
+    /// a packet with a wrong checksum, to be sent again
+    Corrupted,
+    Interrupt,
+}
+
+impl Parse {
+    fn next(self, byte: u8) -> (Self, Option<Input>) {
+        match (self, byte) {
+            (_, b'$') => (Parse::Data(vec![]), None),
+            (Parse::Idle, 0x03) => (Parse::Idle, Some(Input::Interrupt)),
+            (Parse::Idle, _) => (Parse::Idle, None),
+            (Parse::Data(data), b'#') => (Parse::Checksum(data, vec![]), None),
+            (Parse::Data(mut data), byte) => {
+                data.push(byte);
+                (Parse::Data(data), None)
+            }
+            (Parse::Checksum(data, mut cs), byte) => {
+                cs.push(byte);
+                if cs.len() < 2 { return (Parse::Checksum(data, cs), None); }
+                let expected = std::str::from_utf8(&cs).ok().and_then(|x| u8::from_str_radix(x, 16).ok());
+                let input = if expected != Some(Gdb::checksum(&data)) { Input::Corrupted } else {
+                    Input::Packet(String::from_utf8_lossy(&data).into_owned())
+                };
+                (Parse::Idle, Some(input))
+            }
+        }
+    }
+}
+
+enum Request {
+    Connected(TcpStream),
+    Packet(String),
+    Interrupt,
+    Disconnected,
+}
+
+pub struct Gdb {
+    port: u16,
+    rx: Receiver<Request>,
+    client: Option<TcpStream>,
+    /// a continue or step is running, the stop reply is sent when the emulator pauses
+    running: bool,
+}
+
+impl Gdb {
+    pub fn listen(port: u16) -> std::io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        let (tx, rx) = channel();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let Ok(client) = stream.try_clone() else { continue; };
+                if tx.send(Request::Connected(client)).is_err() { break; }
+                Self::read(stream, &tx);
+                if tx.send(Request::Disconnected).is_err() { break; }
+            }
+        });
+        log::info!("gdb server listening on localhost:{port}");
+        Ok(Self { port, rx, client: None, running: false })
+    }
+
+    pub fn port(&self) -> u16 { self.port }
+
+    /// Splits the client input in packets, acknowledged right away.
+    fn read(mut stream: TcpStream, tx: &Sender<Request>) {
+        let mut buf = [0; 4096];
+        let mut state = Parse::Idle;
+        while let Ok(n @ 1..) = stream.read(&mut buf) {
+            for &byte in &buf[..n] {
+                let (next, input) = state.next(byte);
+                state = next;
+                let request = match input {
+                    Some(Input::Packet(packet)) => {
+                        stream.write_all(b"+").ok();
+                        Request::Packet(packet)
+                    }
+                    Some(Input::Corrupted) => {
+                        stream.write_all(b"-").ok();
+                        continue;
+                    }
+                    Some(Input::Interrupt) => Request::Interrupt,
+                    None => continue,
+                };
+                if tx.send(request).is_err() { return; }
+            }
+        }
+    }
+
+    fn checksum(data: &[u8]) -> u8 {
+        data.iter().fold(0u8, |acc, x| acc.wrapping_add(*x))
+    }
+
+    fn reply(&mut self, data: &str) {
+        let Some(client) = self.client.as_mut() else { return; };
+        let packet = format!("${data}#{:02x}", Self::checksum(data.as_bytes()));
+        if let Err(e) = client.write_all(packet.as_bytes()) {
+            log::warn!("gdb client lost: {e}");
+            self.client = None;
+        }
+    }
+
+    /// Handles the pending requests, to call between two emulator cycles.
+    pub fn poll<E: Emulator + WriteAccess>(&mut self, emu: &mut E) {
+        while let Ok(request) = self.rx.try_recv() {
+            match request {
+                Request::Connected(client) => {
+                    log::info!("gdb client connected from {:?}", client.peer_addr());
+                    Self::detach(emu);
+                    self.client = Some(client);
+                    self.running = false;
+                    if emu.running() { emu.breakpoints().pause(); }
+                }
+                Request::Disconnected => {
+                    log::info!("gdb client disconnected");
+                    Self::detach(emu);
+                    self.client = None;
+                    self.running = false;
+                }
+                Request::Interrupt => if emu.running() { emu.breakpoints().pause(); },
+                Request::Packet(packet) => {
+                    if let Some(reply) = self.handle(emu, &packet) { self.reply(&reply); }
+                }
+            }
+        }
+        if self.running && !emu.running() {
+            self.running = false;
+            self.reply("S05");
+        }
+    }
+
+    fn handle<E: Emulator + WriteAccess>(&mut self, emu: &mut E, packet: &str) -> Option<String> {
+        let (cmd, args) = packet.split_at(packet.chars().next().map_or(0, char::len_utf8));
+        let reply = match cmd {
+            "?" => "S05".to_string(),
+            "g" => REGISTERS.iter()
+                .map(|reg| hex(&emu.cpu_register(*reg).u16().to_le_bytes()))
+                .collect(),
+            "G" => {
+                let Some(raw) = unhex(args).filter(|x| x.len() >= 2 * REGISTERS.len()) else { return Some("E01".to_string()); };
+                let set = REGISTERS.iter().zip(raw.chunks(2))
+                    .all(|(reg, v)| emu.set_cpu_register(*reg, Value::U16(u16::from_le_bytes([v[0], v[1]]))));
+                if set { "OK" } else { "E01" }.to_string()
+            }
+            "p" => match usize::from_str_radix(args, 16).ok().and_then(|n| REGISTERS.get(n)) {
+                Some(reg) => hex(&emu.cpu_register(*reg).u16().to_le_bytes()),
+                None => "E01".to_string(),
+            },
+            "P" => match register(args) {
+                Some((reg, v)) if emu.set_cpu_register(reg, v) => "OK".to_string(),
+                _ => "E01".to_string(),
+            },
+            "m" => match range(args) {
+                Some((addr, len)) => hex(&(0..len)
+                    .map(|i| emu.get_range(addr.wrapping_add(i), 1).first().copied().unwrap_or(0xFF))
+                    .collect::<Vec<_>>()),
+                None => "E01".to_string(),
+            },
+            "M" => {
+                let write = args.split_once(':').and_then(|(at, data)| Some((range(at)?, unhex(data)?)));
+                match write {
+                    Some(((addr, _), data)) => {
+                        for (i, v) in data.into_iter().enumerate() {
+                            emu.write(addr.wrapping_add(i as u16), v);
+                        }
+                        "OK".to_string()
+                    }
+                    None => "E01".to_string(),
+                }
+            }
+            "c" | "s" => {
+                if let Ok(pc) = u16::from_str_radix(args, 16) {
+                    emu.set_cpu_register(Reg::PC, Value::U16(pc));
+                }
+                if cmd == "s" { emu.breakpoints().step(); }
+                emu.play();
+                self.running = true;
+                return None;
+            }
+            "Z" | "z" => match watch(args) {
+                Some(bps) => {
+                    let list = emu.breakpoints().bp_mut();
+                    for bp in bps {
+                        if cmd == "Z" {
+                            if !list.contains(&bp) { list.push(bp); }
+                        } else {
+                            list.retain(|x| x != &bp);
+                        }
+                    }
+                    "OK".to_string()
+                }
+                None => String::new(),
+            },
+            "q" => self.query(emu, args),
+            "H" => "OK".to_string(),
+            "D" => {
+                Self::detach(emu);
+                emu.play();
+                self.reply("OK");
+                self.client = None;
+                return None;
+            }
+            "k" => {
+                Self::detach(emu);
+                self.client = None;
+                return None;
+            }
+            _ => String::new(),
+        };
+        Some(reply)
+    }
+
+    /// Drops the breakpoints of the client.
+    fn detach<E: Emulator>(emu: &mut E) {
+        emu.breakpoints().bp_mut().retain(|x| !x.is_remote());
+    }
+
+    fn query<E: Emulator>(&self, emu: &mut E, query: &str) -> String {
+        match query.split_once(',').unwrap_or((query, "")) {
+            _ if query.starts_with("Supported") => "PacketSize=4000".to_string(),
+            ("Attached", _) => "1".to_string(),
+            ("C", _) => "QC1".to_string(),
+            ("fThreadInfo", _) => "m1".to_string(),
+            ("sThreadInfo", _) => "l".to_string(),
+            ("Rcmd", cmd) => {
+                let cmd = unhex(cmd).map(|x| String::from_utf8_lossy(&x).into_owned()).unwrap_or_default();
+                hex(monitor(emu, cmd.trim()).as_bytes())
+            }
+            _ => String::new(),
+        }
+    }
+}
+
+/// `monitor` commands, answering bank-aware addresses.
+fn monitor<E: Emulator>(emu: &mut E, cmd: &str) -> String {
+    let mut args = cmd.split_whitespace();
+    match args.next() {
+        Some("banks") => {
+            let banks = emu.banks();
+            format!("ROM {:#04X} | SRAM {:#04X} | WRAM {:#04X} | VRAM {:#04X}\n",
+                banks.rom, banks.ram, banks.wram, banks.vram)
+        }
+        Some(cmd @ ("break" | "delete")) => {
+            let Some((bank, addr)) = args.next().and_then(|x| location(emu, x)) else {
+                return format!("usage: {cmd} bank:addr | label\n");
+            };
+            let bp = Breakpoint::banked(bank, addr).remote();
+            let list = emu.breakpoints().bp_mut();
+            if cmd == "break" {
+                if !list.contains(&bp) { list.push(bp); }
+            } else {
+                list.retain(|x| x != &bp);
+            }
+            "OK\n".to_string()
+        }
+        Some("where") => {
+            let addr = match args.next() {
+                Some(addr) => match u16::from_str_radix(addr.trim_start_matches("0x"), 16) {
+                    Ok(addr) => addr,
+                    Err(_) => return format!("invalid address {addr}\n"),
+                },
+                None => emu.cpu_register(Reg::PC).u16(),
+            };
+            format!("{}\n", banked(emu, addr))
+        }
+        _ => "monitor commands:\n  banks          current ROM, SRAM, WRAM and VRAM banks\n  where [addr]   bank:address of addr, PC by default\n  break <bank:addr | label>\n                 breaks on PC only while the bank is mapped\n  delete <bank:addr | label>\n                 removes such a breakpoint\n".to_string(),
+    }
+}
+
+/// `bank:addr` in hexadecimal, or a label of the symbol file.
+fn location<E: Emulator>(emu: &E, arg: &str) -> Option<(u16, u16)> {
+    match arg.split_once(':') {
+        Some((bank, addr)) => Some((u16::from_str_radix(bank, 16).ok()?, u16::from_str_radix(addr.trim_start_matches("0x"), 16).ok()?)),
+        None => emu.symbols().address(arg),
+    }
+}
+
+/// `addr` with the bank mapped at its location, as `bank:addr`, followed by its label.
+pub fn banked<E: Emulator>(emu: &E, addr: u16) -> String {
+    let bank = emu.banks().of(addr);
+    match emu.symbols().label(addr, Some(bank)) {
+        Some(label) => format!("{bank:02X}:{addr:04X} {label}"),
+        None => format!("{bank:02X}:{addr:04X}"),
+    }
+}
+
+/// Register write of a `P` packet: `n=value`, the value in target byte order.
+fn register(args: &str) -> Option<(Reg, Value)> {
+    let (n, v) = args.split_once('=')?;
+    let reg = REGISTERS.get(usize::from_str_radix(n, 16).ok()?)?;
+    let v = unhex(v).filter(|x| x.len() == 2)?;
+    Some((*reg, Value::U16(u16::from_le_bytes([v[0], v[1]]))))
+}
+
+/// Breakpoints of a `Z`/`z` packet: `type,addr,kind`.
+fn watch(args: &str) -> Option<Vec<Breakpoint>> {
+    let mut it = args.splitn(3, ',');
+    let kind = it.next()?;
+    let addr = u16::from_str_radix(it.next()?, 16).ok()?;
+    let len = u16::from_str_radix(it.next()?.split(';').next()?, 16).ok()?;
+    let bytes = (0..len.max(1)).map(|i| addr.wrapping_add(i));
+    let bps: Vec<Breakpoint> = match kind {
+        "0" | "1" => vec![Breakpoint::address(addr)],
+        "2" => bytes.map(|x| Breakpoint::access(Access::write(x, Expected::Any))).collect(),
+        "3" => bytes.map(|x| Breakpoint::access(Access::read(x, Expected::Any))).collect(),
+        "4" => bytes.flat_map(|x| [
+            Breakpoint::access(Access::read(x, Expected::Any)),
+            Breakpoint::access(Access::write(x, Expected::Any)),
+        ]).collect(),
+        _ => return None,
+    };
+    Some(bps.into_iter().map(Breakpoint::remote).collect())
+}
+
+/// `addr,len` in hex.
+fn range(args: &str) -> Option<(u16, u16)> {
+    let (addr, len) = args.split_once(',')?;
+    Some((u16::from_str_radix(addr, 16).ok()?, u16::from_str_radix(len, 16).ok()?))
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|x| format!("{x:02x}")).collect()
+}
+
+fn unhex(data: &str) -> Option<Vec<u8>> {
+    if data.len() % 2 != 0 { return None; }
+    (0..data.len()).step_by(2)
+        .map(|i| u8::from_str_radix(data.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &[u8]) -> Vec<Input> {
+        let mut state = Parse::Idle;
+        let mut inputs = vec![];
+        for &byte in input {
+            let (next, input) = state.next(byte);
+            state = next;
+            inputs.extend(input);
+        }
+        inputs
+    }
+
+    #[test]
+    fn packets() {
+        assert_eq!(Gdb::checksum(b"qSupported"), 0x37);
+        assert_eq!(parse(b"+$g#67$m100,2#5c"), vec![Input::Packet("g".to_string()), Input::Packet("m100,2".to_string())]);
+        assert_eq!(parse(b"$g#00\x03"), vec![Input::Corrupted, Input::Interrupt]);
+        assert_eq!(parse(b"$g#6"), vec![]);
+    }
+
+    #[test]
+    fn arguments() {
+        assert_eq!(range("c000,10"), Some((0xC000, 0x10)));
+        assert_eq!(unhex("3c00"), Some(vec![0x3C, 0x00]));
+        assert_eq!(unhex("3c0"), None);
+        assert_eq!(register("5=5001"), Some((Reg::PC, Value::U16(0x150))));
+        assert_eq!(register("6=5001"), None);
+        assert_eq!(register("5=50"), None);
+        assert_eq!(watch("0,150,1").unwrap(), vec![Breakpoint::address(0x150).remote()]);
+        assert_eq!(watch("2,c000,2").unwrap().len(), 2);
+        assert!(watch("5,c000,1").is_none());
+    }
+}
@@ -5,6 +5,9 @@ use shared::{breakpoints::Breakpoint, cpu::{Reg, Value}, egui::Context, emulator
 use shared::egui::{TextureHandle, TextureId};
 
 mod render;
+pub mod gdb;
+
+pub use gdb::Gdb;
 
 #[derive(Copy, Clone, Hash, PartialOrd, PartialEq, Eq)]
 enum Texture {
@@ -19,7 +22,7 @@ trait Debugger<E: Emulator> {
     fn pause(&mut self);
     fn play(&mut self, dice: &mut Disassembly<E>);
     fn step(&mut self, dice: &mut Disassembly<E>);
-    fn run_to(&mut self, dice: &mut Disassembly<E>, addr: u16);
+    fn run_to(&mut self, dice: &mut Disassembly<E>, bank: Option<u16>, addr: u16);
     fn step_into(&mut self, dice: &mut Disassembly<E>);
 
     fn schedule(&mut self, bp: Breakpoint);
@@ -42,8 +45,12 @@ impl<E: Emulator> Debugger<E> for E {
         Debugger::<E>::play(self, dice);
     }
 
-    fn run_to(&mut self, dice: &mut Disassembly<E>, addr: u16) {
-        self.breakpoints().schedule(Breakpoint::address(addr).once());
+    fn run_to(&mut self, dice: &mut Disassembly<E>, bank: Option<u16>, addr: u16) {
+        let bp = match bank {
+            Some(bank) => Breakpoint::banked(bank, addr),
+            None => Breakpoint::address(addr),
+        };
+        self.breakpoints().schedule(bp.once());
         Debugger::<E>::play(self, dice);
     }
 
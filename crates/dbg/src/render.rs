@@ -5,12 +5,13 @@ use egui_extras::Column;
 pub use disassembly::Disassembly;
 pub use memory::Viewer;
 use shared::{egui::{self, Align, CentralPanel, Color32, FontFamily, Layout, Response, Ui, Widget}, Event, Events};
-use shared::breakpoints::Breakpoint;
+use shared::breakpoints::{Breakpoint, Condition, Message};
 use shared::cpu::{Flags, Opcode, Reg, Value};
 use shared::egui::{ScrollArea, SidePanel, Vec2};
 use shared::emulator::Bus;
 use shared::input::{Debug, KeyCat};
 use shared::io::IO;
+use shared::symbols::Symbols;
 use shared::utils::convert::Converter;
 use shared::utils::DARK_BLACK;
 use shared::utils::image::ImageLoader;
@@ -46,19 +47,54 @@ pub struct Data {
     raw_op: String,
     raw: String,
     value: Value,
+    /// ROM bank of a `bank:addr` PC value
+    bank: Option<u16>,
     reg: Kind,
     count: usize,
+    raw_cond: String,
+    /// condition attached to the new breakpoints, or why it is invalid
+    condition: Result<Option<Condition>, String>,
+    raw_log: String,
+    /// message of the new breakpoints, which makes them logpoints
+    message: Result<Option<Message>, String>,
+    ignore: usize,
+    /// file the logpoint messages are about to be appended to
+    raw_logs: String,
 }
 
 impl Data {
     fn breakpoint(&self) -> Breakpoint {
-        match self.reg {
-            Kind::Reg(reg) => Breakpoint::register(reg, self.value),
-            Kind::Cycles => Breakpoint::cycles(self.count),
-            Kind::Instructions => Breakpoint::instructions(self.count),
+        match (self.reg, self.bank) {
+            (Kind::Reg(Reg::PC), Some(bank)) => Breakpoint::banked(bank, self.value.u16()),
+            (Kind::Reg(reg), _) => Breakpoint::register(reg, self.value),
+            (Kind::Cycles, _) => Breakpoint::cycles(self.count),
+            (Kind::Instructions, _) => Breakpoint::instructions(self.count),
         }
     }
 
+    /// Attaches the condition, message and ignored hits being edited.
+    fn with_options(&self, mut bp: Breakpoint) -> Breakpoint {
+        if let Ok(Some(condition)) = &self.condition { bp = bp.when(condition.clone()); }
+        if let Ok(Some(message)) = &self.message { bp = bp.log(message.clone()); }
+        bp.ignoring(self.ignore)
+    }
+
+    fn valid(&self) -> bool { self.condition.is_ok() && self.message.is_ok() }
+
+    fn parse_condition(&mut self) {
+        self.condition = match self.raw_cond.trim() {
+            "" => Ok(None),
+            raw => Condition::parse(raw).map(Some),
+        };
+    }
+
+    fn parse_message(&mut self) {
+        self.message = match self.raw_log.as_str() {
+            "" => Ok(None),
+            raw => Message::parse(raw).map(Some),
+        };
+    }
+
     fn parse_op(&mut self) {
         let opcode = u16::convert(&self.raw_op);
         let prefix = opcode & 0xFF00 == 0xCB00;
@@ -68,11 +104,25 @@ impl Data {
         }
     }
 
-    fn parse(&mut self) {
+    /// 16 bits values may also be given as a label of the symbol file, or as `bank:addr`
+    /// in hexadecimal: both only break on PC while that bank is mapped.
+    fn parse(&mut self, symbols: &Symbols) {
+        self.bank = None;
         match (self.value, self.reg) {
             (_, Kind::Cycles | Kind::Instructions) => { self.count = usize::from_str(&self.raw).unwrap_or(0); }
             (Value::U8(_v), _) => { self.value = Value::U8(u8::convert(&self.raw)); }
-            (Value::U16(_v), _) => { self.value = Value::U16(u16::convert(&self.raw)); }
+            (Value::U16(_v), _) => {
+                let raw = self.raw.trim();
+                let (bank, addr) = match raw.split_once(':') {
+                    Some((bank, addr)) => (u16::from_str_radix(bank, 16).ok(), u16::from_str_radix(addr.trim_start_matches("0x"), 16).unwrap_or(0)),
+                    None => match symbols.address(raw) {
+                        Some((bank, addr)) => (Some(bank), addr),
+                        None => (None, u16::convert(raw)),
+                    }
+                };
+                self.bank = bank;
+                self.value = Value::U16(addr);
+            }
         };
     }
 
@@ -87,7 +137,7 @@ impl Data {
 
 impl Default for Data {
     fn default() -> Self {
-        Self { op: "NOP", ins: Opcode::Nop, raw_op: Default::default(), reg: Kind::Reg(Reg::PC), count: 0, raw: "".to_string(), value: Value::U16(0) }
+        Self { op: "NOP", ins: Opcode::Nop, raw_op: Default::default(), reg: Kind::Reg(Reg::PC), count: 0, raw: "".to_string(), value: Value::U16(0), bank: None, raw_cond: String::new(), condition: Ok(None), raw_log: String::new(), message: Ok(None), ignore: 0, raw_logs: String::new() }
     }
 }
 
@@ -255,8 +305,9 @@ impl<E: Emulator> shared::Ui for Ninja<E> {
                                     if ui.add(egui::TextEdit::singleline(&mut self.render_data.raw_op).desired_width(64.)).changed() {
                                         self.render_data.parse_op();
                                     }
-                                    if ui.button("BREAK").clicked() {
-                                        ext.schedule(Breakpoint::instruction(self.render_data.ins));
+                                    let valid = self.render_data.valid();
+                                    if ui.add_enabled(valid, egui::Button::new("BREAK")).clicked() {
+                                        ext.schedule(self.render_data.with_options(Breakpoint::instruction(self.render_data.ins)));
                                     }
                                     ui.menu_button(self.render_data.reg.name(), |ui| {
                                         use Reg::*;
@@ -268,15 +319,44 @@ impl<E: Emulator> shared::Ui for Ninja<E> {
                                         }
                                     });
                                     if ui.add(egui::TextEdit::singleline(&mut self.render_data.raw).desired_width(64.)).changed() {
-                                        self.render_data.parse();
+                                        self.render_data.parse(ext.symbols());
                                     }
-                                    if ui.button("BREAK").clicked() {
-                                        ext.schedule(self.render_data.breakpoint());
+                                    if ui.add_enabled(valid, egui::Button::new("BREAK")).clicked() {
+                                        ext.schedule(self.render_data.with_options(self.render_data.breakpoint()));
                                     }
                                     let mut and = ext.breakpoints().and();
                                     ui.toggle_value(&mut and, "And");
                                     ext.breakpoints().set_and(and);
                                 });
+                                ui.horizontal(|ui| {
+                                    ui.label("if");
+                                    let color = if self.render_data.condition.is_err() { Color32::RED } else { ui.visuals().text_color() };
+                                    let edit = egui::TextEdit::singleline(&mut self.render_data.raw_cond)
+                                        .hint_text("A == 0x3C && [HL] & 0x80 && bank == 3")
+                                        .text_color(color)
+                                        .desired_width(260.);
+                                    if ui.add(edit).changed() {
+                                        self.render_data.parse_condition();
+                                    }
+                                    if let Err(e) = &self.render_data.condition {
+                                        ui.colored_label(Color32::RED, e);
+                                    }
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("log");
+                                    let color = if self.render_data.message.is_err() { Color32::RED } else { ui.visuals().text_color() };
+                                    let edit = egui::TextEdit::singleline(&mut self.render_data.raw_log)
+                                        .hint_text("HL = {HL}, [HL] = {[HL]:d}")
+                                        .text_color(color)
+                                        .desired_width(180.);
+                                    if ui.add(edit).changed() {
+                                        self.render_data.parse_message();
+                                    }
+                                    ui.add(egui::DragValue::new(&mut self.render_data.ignore).prefix("ignore "));
+                                    if let Err(e) = &self.render_data.message {
+                                        ui.colored_label(Color32::RED, e);
+                                    }
+                                });
                                 egui_extras::TableBuilder::new(ui)
                                     .columns(Column::remainder(), 3)
                                     .striped(true)
@@ -284,21 +364,68 @@ impl<E: Emulator> shared::Ui for Ninja<E> {
                                     .auto_shrink([false; 2])
                                     .cell_layout(Layout::left_to_right(Align::Center))
                                     .body(|mut body| {
+                                        let bps = ext.breakpoints().bp_mut().clone();
+                                        let mut names = bps.iter()
+                                            .filter(|bp| !bp.temp())
+                                            .map(|bp| bp.display(ext.symbols()))
+                                            .collect::<Vec<_>>()
+                                            .into_iter();
                                         ext.breakpoints()
                                             .bp_mut()
                                             .retain_mut(|bp| {
                                                 if bp.temp() { return true; };
                                                 let mut rem = false;
+                                                let name = names.next().unwrap_or_default();
                                                 body.row(30.0, |mut row| {
                                                     row.col(|ui| { if ui.button("-").clicked() { rem = true; } });
                                                     row.col(|ui| { ui.checkbox(&mut bp.enabled, ""); });
-                                                    row.col(|ui| { ui.label(bp.display()); });
+                                                    row.col(|ui| {
+                                                        ui.label(name);
+                                                        let hits = egui::Label::new(format!("{} hits", bp.hits())).sense(egui::Sense::click());
+                                                        if ui.add(hits).on_hover_text("Click to reset").clicked() { bp.reset_hits(); }
+                                                        ui.add(egui::DragValue::new(&mut bp.ignore).prefix("ignore "));
+                                                    });
                                                 });
                                                 !rem
                                             });
                                     });
                             });
                     });
+                    ui.allocate_ui_with_layout(Vec2::new(320., 366.), Layout::top_down(Align::LEFT), |ui| {
+                        egui::Frame::group(ui.style())
+                            .fill(DARK_BLACK)
+                            .show(ui, |ui| {
+                                let logs = ext.breakpoints().logs_mut();
+                                ui.horizontal(|ui| {
+                                    ui.label("Logpoints");
+                                    if let Some(path) = logs.path() {
+                                        ui.label(egui::RichText::new(path.display().to_string()).color(Color32::GRAY));
+                                        if ui.button("Close").on_hover_text("Stop writing the messages to the file").clicked() { logs.close(); }
+                                    } else {
+                                        let edit = egui::TextEdit::singleline(&mut self.render_data.raw_logs)
+                                            .hint_text("logpoints.log")
+                                            .desired_width(120.);
+                                        let res = ui.add(edit);
+                                        let enter = res.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                                        if (ui.button("Open").on_hover_text("Also append the messages to this file").clicked() || enter)
+                                            && !self.render_data.raw_logs.is_empty() {
+                                            logs.open(std::mem::take(&mut self.render_data.raw_logs).into());
+                                        }
+                                    }
+                                    if ui.button("Clear").clicked() { logs.clear(); }
+                                });
+                                let height = ui.text_style_height(&egui::TextStyle::Body);
+                                ScrollArea::vertical()
+                                    .id_source("logpoints")
+                                    .stick_to_bottom(true)
+                                    .auto_shrink([false; 2])
+                                    .show_rows(ui, height, logs.lines().len(), |ui, range| {
+                                        for line in logs.lines().range(range) {
+                                            ui.label(line);
+                                        }
+                                    });
+                            });
+                    });
                 });
                 egui::Frame::group(ui.style()) // IOregs
                     .fill(DARK_BLACK)
@@ -354,7 +481,7 @@ impl<E: Emulator> shared::Ui for Ninja<E> {
     fn handle(&mut self, event: &Event, _ctx: &mut Context, ext: &mut E) {
         match event {
             Event::UserEvent(Events::Loaded) => self.disassembly.reload(),
-            Event::WindowEvent { event: WindowEvent::MouseWheel { .. }, .. } => self.disassembly.fixed(&ext),
+            Event::WindowEvent { event: WindowEvent::MouseWheel { .. }, .. } => self.disassembly.fixed(ext),
             Event::UserEvent(Events::Press(KeyCat::Dbg(key))) => {
                 match key {
                     Debug::Pause => ext.pause(),
@@ -21,7 +21,7 @@ impl Op {
     }
 
     pub fn parse(pc: u16, range: &[u8]) -> Self {
-        let op = range.get(0).and_then(|x| (*x, false).try_into().ok());
+        let op = range.first().and_then(|x| (*x, false).try_into().ok());
         let op = match (op, range.get(1)) {
             (Some(Opcode::PrefixCB), Some(v)) => (*v, true).try_into().unwrap(),
             (Some(Opcode::PrefixCB), None) => { log::warn!("corrupted range ! (lone CB prefix)"); Opcode::PrefixCB },
@@ -39,6 +39,25 @@ impl Op {
     pub fn is_jmp(&self) -> bool {
         self.instruction.contains("JR") || self.instruction.contains("JP") || self.instruction.contains("RET")
     }
+
+    /// Destination of the jump, call or restart at `at`.
+    pub fn target(&self, at: u16) -> Option<u16> {
+        let name = self.instruction.split(' ').next()?;
+        match (name, self.data.as_slice()) {
+            ("JP" | "CALL", [_, lo, hi]) => Some(u16::from_le_bytes([*lo, *hi])),
+            ("JR", [_, offset]) => Some(at.wrapping_add(2).wrapping_add(*offset as i8 as u16)),
+            ("RST", _) => u16::from_str_radix(self.instruction.trim_start_matches("RST ").trim_end_matches('H'), 16).ok(),
+            _ => None,
+        }
+    }
+
+    /// Instruction with its last operand replaced by `label`.
+    pub fn labelled(&self, label: &str) -> String {
+        match self.instruction.rfind([' ', ',']) {
+            Some(i) => format!("{}{label}", &self.instruction[..=i]),
+            None => self.instruction.clone(),
+        }
+    }
 }
 
 impl Default for Op {
@@ -72,9 +91,9 @@ impl OpRange {
             if let Some((_, len)) = ignore.iter().find(|(x, _)| *x == st).copied() {
                 self.ops.push(Op {
                     offset: st as u16,
-                    size: len as usize,
+                    size: len,
                     instruction: "..".to_string(),
-                    data: input[st..(st + (len as usize).min(8))].to_vec()
+                    data: input[st..(st + len.min(8))].to_vec()
                 });
                 st += len;
                 continue;
@@ -110,12 +129,13 @@ impl<E: Emulator> MemRange<E> for DynRange {
 
 struct SromRange {
     current: usize,
+    browsed: Option<usize>,
     banks: HashMap<usize, OpRange>
 }
 
 impl Default for SromRange {
     fn default() -> Self {
-        Self { current: 1, banks: HashMap::with_capacity(0x200) }
+        Self { current: 1, browsed: None, banks: HashMap::with_capacity(0x200) }
     }
 }
 
@@ -135,11 +155,17 @@ impl<E: Emulator> MemRange<E> for SromRange {
     fn reload(&mut self) { self.banks.clear() }
     fn range(&self) -> Range<u16> { 0x4000..0x8000 }
     fn update(&mut self, emu: &E) {
-        let bank = emu.bus().mbc().rom_bank();
+        let mapped = emu.bus().mbc().rom_bank();
+        let bank = self.browsed.unwrap_or(mapped);
         self.current = bank;
-        self.banks.entry(bank).or_insert_with(|| OpRange::default().parse(emu.get_range(0x4000, 0x4000), vec![]));
+        self.banks.entry(bank).or_insert_with(|| {
+            let data = if bank == mapped { emu.get_range(0x4000, 0x4000) } else { emu.read_bank(bank) };
+            OpRange::default().parse(data, vec![])
+        });
     }
 
+    fn browse(&mut self, bank: Option<usize>) { self.browsed = bank; }
+
     fn ops(&self) -> &OpRange { &self.banks[&self.current] }
 
     fn count(&self) -> usize { self.banks.get(&self.current).map(|x| x.ops.len()).unwrap_or(0) }
@@ -152,12 +178,15 @@ pub trait MemRange<E: Emulator> {
     fn ops(&self) -> &OpRange;
     fn boxed(self) -> Box<dyn MemRange<E>> where Self: 'static + Sized { Box::new(self) }
     fn count(&self) -> usize;
+    /// Shows `bank` instead of the mapped one, for the banked ranges.
+    fn browse(&mut self, _bank: Option<usize>) {}
 }
 
 pub struct Disassembly<E: Emulator> {
     ranges: Vec<Box<dyn MemRange<E>>>,
     cursor: Cursor,
     hover: Option<usize>,
+    browsed: Option<usize>,
 }
 
 impl<E: Emulator> Disassembly<E> {
@@ -171,7 +200,8 @@ impl<E: Emulator> Disassembly<E> {
                 DynRange::new(SRAM, SRAM_END).boxed(),
                 DynRange::new(HRAM, HRAM_END).boxed()
             ],
-            hover: None
+            hover: None,
+            browsed: None,
         }
     }
 
@@ -179,6 +209,17 @@ impl<E: Emulator> Disassembly<E> {
         self.cursor = Cursor::Follow;
     }
 
+    /// Shows a ROM bank other than the mapped one at 0x4000-0x7FFF, `None` to follow the cartridge.
+    pub fn browse(&mut self, bank: Option<usize>) {
+        self.browsed = bank;
+        self.ranges.iter_mut().for_each(|x| x.browse(bank));
+    }
+
+    /// Whether PC may be in a ROM bank other than the displayed one.
+    fn elsewhere(&self, emu: &E, pc: u16) -> bool {
+        (SROM..=SROM_END).contains(&pc) && self.browsed.is_some_and(|x| x != emu.mbc().rom_bank())
+    }
+
     pub fn range(&mut self, pc: u16) -> Option<&mut Box<dyn MemRange<E>>> {
         self.ranges.iter_mut().find(|x| x.range().contains(&pc))
     }
@@ -204,6 +245,7 @@ impl<E: Emulator> Disassembly<E> {
 
     pub(crate) fn next(&mut self, emu: &E) -> Option<(u16, Op)> {
         let pc = emu.cpu_register(Reg::PC).u16();
+        if self.elsewhere(emu, pc) { return None; }
         if let Some((range, ops)) = self.range(pc)
             .map(|x| (x.range(), x.ops())) {
             let mut st = range.start;
@@ -227,19 +269,32 @@ impl<E: Emulator> Disassembly<E> {
 
     pub fn render(&mut self, emu: &mut E, ui: &mut Ui) {
         let pc = emu.cpu_register(Reg::PC).u16();
+        let rom_bank = emu.mbc().rom_bank();
+        ui.set_height(300.);
+        let mut browsed = self.browsed;
+        ui.horizontal(|ui| {
+            let mut mapped = browsed.is_none();
+            let mut bank = browsed.unwrap_or(rom_bank);
+            ui.label("ROM bank");
+            ui.checkbox(&mut mapped, "Mapped");
+            ui.add_enabled(!mapped, egui::DragValue::new(&mut bank).clamp_range(1..=emu.rom_banks().max(2) - 1));
+            browsed = if mapped { None } else { Some(bank) };
+        });
+        if browsed != self.browsed { self.browse(browsed); }
         self.ranges.iter_mut().for_each(|x| { x.update(emu); });
+        let elsewhere = self.elsewhere(emu, pc);
         let cursor = match self.cursor {
+            _ if elsewhere => usize::MAX,
             Cursor::Follow => self.row(pc),
             Cursor::Fixed(row) => row,
         };
-        ui.set_height(300.);
         let row = self.row(pc);
         let mut table = TableBuilder::new(ui)
             .columns(Column::remainder(), 3)
             .striped(true)
             .cell_layout(egui::Layout::left_to_right(Align::Center))
             .auto_shrink([false, false]);
-        if let Cursor::Follow = self.cursor {
+        if let (Cursor::Follow, false) = (self.cursor, elsewhere) {
             table = table.scroll_to_row(row, Some(Align::Center));
         }
         table.header(20., |mut header| {
@@ -257,6 +312,13 @@ impl<E: Emulator> Disassembly<E> {
                 let lines = self.lines();
                 let mut hover = None;
                 let mut run_to = None;
+                let symbols = emu.symbols();
+                let shown = self.browsed.unwrap_or(rom_bank) as u16;
+                let bank = |addr: u16| match addr {
+                    ROM..=ROM_END => Some(0),
+                    SROM..=SROM_END => Some(shown),
+                    _ => None,
+                };
                 body.rows(30., lines, |index, mut row| {
                     let mut st = index;
                     if let Some(current) = self.ranges.iter_mut()
@@ -277,12 +339,19 @@ impl<E: Emulator> Disassembly<E> {
                             if Some(index) == self.hover { ui.painter().rect_filled(rect, Rounding { nw: 4., sw: 4., ne: 0., se: 0. }, Color32::from_white_alpha(20)) }
                             else if index == cursor { ui.painter().rect_filled(rect, Rounding { nw: 4., sw: 4., ne: 0., se: 0. }, Color32::DARK_GREEN); }
                             ui.label(egui::RichText::new(format!("{:#06X}", addr + op.offset)));
+                            if let Some(label) = symbols.label(addr + op.offset, bank(addr + op.offset)) {
+                                ui.label(egui::RichText::new(label).color(Color32::GOLD));
+                            }
                         }).1 | row.col(|ui| {
                             let mut rect = ui.available_rect_before_wrap();
                             rect.max.x += 8.;
                             if Some(index) == self.hover { ui.painter().rect_filled(rect, 0., Color32::from_white_alpha(20)) }
                             else if index == cursor { ui.painter().rect_filled(rect, 0., Color32::DARK_GREEN); }
-                            ui.label(egui::RichText::new(&op.instruction));
+                            let label = op.target(addr + op.offset).and_then(|x| symbols.label(x, bank(x)));
+                            match label {
+                                Some(label) => ui.label(egui::RichText::new(op.labelled(label))),
+                                None => ui.label(egui::RichText::new(&op.instruction)),
+                            };
                         }).1 | row.col(|ui| {
                             let mut rect = ui.available_rect_before_wrap();
                             rect.max.x += 8.;
@@ -293,15 +362,15 @@ impl<E: Emulator> Disassembly<E> {
                             ui.label(egui::RichText::new(code));
                         }).1).context_menu(|ui| {
                             if ui.button("Run to cursor").clicked() {
-                                run_to = Some(addr + op.offset);
+                                run_to = Some((bank(addr + op.offset), addr + op.offset));
                             }
                         }).hovered() {
                             hover = Some(index);
                         };
                     }
                 });
-                if let Some(addr) = run_to {
-                    emu.run_to(self, addr);
+                if let Some((bank, addr)) = run_to {
+                    emu.run_to(self, bank, addr);
                 }
                 self.hover = hover;
             });
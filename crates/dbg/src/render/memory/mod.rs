@@ -47,6 +47,7 @@ pub struct Viewer {
     hover: Option<u16>,
     input: String,
     value: Value,
+    banked: bool,
 }
 
 impl Default for Viewer {
@@ -66,6 +67,7 @@ impl Default for Viewer {
             hover: None,
             input: String::new(),
             value: Value::Any,
+            banked: false,
         }
     }
 }
@@ -77,6 +79,9 @@ struct ContextMenu<'a> {
     input: &'a mut String,
     breaks: &'a mut Breakpoints,
     addr: u16,
+    /// bank mapped at `addr`, for the banked areas
+    bank: Option<u16>,
+    banked: &'a mut bool,
 }
 
 impl<'a> Widget for ContextMenu<'a> {
@@ -96,11 +101,18 @@ impl<'a> Widget for ContextMenu<'a> {
                 }
             });
         }
+        if let Some(bank) = self.bank {
+            ui.checkbox(self.banked, format!("Only in bank {bank:02X}"));
+        }
         use shared::breakpoints::Access;
         let rr = ui.button("Read");
         let rw = ui.button("Write");
         let rrw = ui.button("R/W");
         let access = if rr.clicked() { Access::read(self.addr, *self.value) } else if rw.clicked() { Access::write(self.addr, *self.value) } else if rrw.clicked() { Access::rw(self.addr, *self.value) } else { return rr | rw | rrw; };
+        let access = match self.bank {
+            Some(bank) if *self.banked => access.in_bank(bank),
+            _ => access,
+        };
         ui.close_menu();
         self.breaks.schedule(Breakpoint::access(access));
         rr | rw | rrw
@@ -147,6 +159,7 @@ impl Viewer {
                     .auto_shrink([false, true]);
                 let height = self.get_line_height(ui);
                 let max_lines = (space.range.len() + 15) / 16;
+                let banks = emu.banks();
                 scroll.show_rows(ui, height, max_lines, |ui, range| {
                     egui::Grid::new("viewer_grid")
                         .striped(true)
@@ -185,8 +198,11 @@ impl Viewer {
                                                 ui.add(ContextMenu {
                                                     value: &mut self.value,
                                                     input: &mut self.input,
-                                                    breaks: &mut emu.breakpoints(),
+                                                    breaks: emu.breakpoints(),
                                                     addr,
+                                                    bank: matches!(addr, SROM..=SROM_END | VRAM..=VRAM_END | SRAM..=SRAM_END | 0xD000..=0xDFFF)
+                                                        .then(|| banks.of(addr)),
+                                                    banked: &mut self.banked,
                                                 });
                                             });
                                         }
@@ -0,0 +1,78 @@
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use headless::Console;
+use headless::test_rom::{TestRom, Verdict};
+use shared::rom::Rom;
+
+const USAGE: &str = "usage: runner [--cgb] [--seconds N] <rom or directory>...";
+
+struct Args {
+    cgb: bool,
+    seconds: usize,
+    paths: Vec<PathBuf>,
+}
+
+impl Args {
+    fn parse() -> Result<Self, String> {
+        let mut args = Args { cgb: false, seconds: 30, paths: vec![] };
+        let mut it = std::env::args().skip(1);
+        while let Some(arg) = it.next() {
+            match arg.as_str() {
+                "--cgb" => args.cgb = true,
+                "--seconds" => {
+                    let n = it.next().ok_or("missing value for --seconds")?;
+                    args.seconds = n.parse().map_err(|_| format!("invalid value for --seconds: {n}"))?;
+                }
+                "-h" | "--help" => return Err(USAGE.to_string()),
+                flag if flag.starts_with("--") => return Err(format!("unknown flag {flag}")),
+                path => args.paths.push(PathBuf::from(path)),
+            }
+        }
+        if args.paths.is_empty() { return Err(USAGE.to_string()); }
+        Ok(args)
+    }
+}
+
+fn collect(path: &Path, roms: &mut Vec<PathBuf>) {
+    if path.is_dir() {
+        let Ok(entries) = std::fs::read_dir(path) else { return };
+        let mut entries = entries.flatten().map(|e| e.path()).collect::<Vec<_>>();
+        entries.sort();
+        entries.iter().for_each(|p| collect(p, roms));
+    } else if matches!(path.extension().and_then(|e| e.to_str()), Some("gb" | "gbc")) {
+        roms.push(path.to_path_buf());
+    }
+}
+
+fn run(path: &Path, args: &Args) -> Verdict {
+    let rom = match Rom::load(path) {
+        Ok(rom) => rom,
+        Err(e) => return Verdict::Failed(e.to_string()),
+    };
+    let cgb = rom.header.kind.cgb_mode(args.cgb);
+    let cycles = args.seconds * Console::CLOCK_PER_SECOND as usize;
+    std::panic::catch_unwind(AssertUnwindSafe(|| TestRom::new(&rom, cgb, cycles).run()))
+        .unwrap_or_else(|_| Verdict::Failed("emulator panicked".to_string()))
+}
+
+fn main() -> ExitCode {
+    let args = match Args::parse() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::from(2);
+        }
+    };
+    let mut roms = vec![];
+    args.paths.iter().for_each(|p| collect(p, &mut roms));
+    let mut failed = 0;
+    for rom in &roms {
+        let verdict = run(rom, &args);
+        if !verdict.passed() { failed += 1; }
+        println!("{verdict}: {}", rom.display());
+    }
+    println!("{} passed, {failed} failed", roms.len() - failed);
+    if failed == 0 { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+}
@@ -0,0 +1,80 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use headless::Console;
+use headless::screenshot::{self, Comparison};
+use shared::rom::Rom;
+
+const USAGE: &str = "usage: screenshot [--cgb] [--frames N] [--diff out.png] [--update] <rom> <reference.png>";
+
+struct Args {
+    cgb: bool,
+    frames: usize,
+    diff: Option<PathBuf>,
+    update: bool,
+    rom: PathBuf,
+    reference: PathBuf,
+}
+
+impl Args {
+    fn parse() -> Result<Self, String> {
+        let (mut cgb, mut frames, mut diff, mut update) = (false, 60, None, false);
+        let mut paths = vec![];
+        let mut it = std::env::args().skip(1);
+        while let Some(arg) = it.next() {
+            match arg.as_str() {
+                "--cgb" => cgb = true,
+                "--update" => update = true,
+                "--frames" => {
+                    let n = it.next().ok_or("missing value for --frames")?;
+                    frames = n.parse().map_err(|_| format!("invalid value for --frames: {n}"))?;
+                }
+                "--diff" => diff = Some(PathBuf::from(it.next().ok_or("missing value for --diff")?)),
+                "-h" | "--help" => return Err(USAGE.to_string()),
+                flag if flag.starts_with("--") => return Err(format!("unknown flag {flag}")),
+                path => paths.push(PathBuf::from(path)),
+            }
+        }
+        let [rom, reference]: [PathBuf; 2] = paths.try_into().map_err(|_| USAGE.to_string())?;
+        Ok(Args { cgb, frames, diff, update, rom, reference })
+    }
+}
+
+fn run(args: Args) -> Result<bool, String> {
+    let rom = Rom::load(&args.rom).map_err(|e| format!("{}: {e}", args.rom.display()))?;
+    let cgb = rom.header.kind.cgb_mode(args.cgb);
+    let mut console = Console::builder(&rom).cgb(cgb).build();
+    let frame = screenshot::capture(&mut console, args.frames);
+    if args.update {
+        frame.save(&args.reference).map_err(|e| format!("{}: {e}", args.reference.display()))?;
+        println!("updated {}", args.reference.display());
+        return Ok(true);
+    }
+    let reference = screenshot::load(&args.reference).map_err(|e| format!("{}: {e}", args.reference.display()))?;
+    let cmp = screenshot::compare(&frame, &reference);
+    println!("{}: {cmp} after {} frames", args.rom.display(), console.frames());
+    if let Comparison::Mismatch { diff, .. } = &cmp {
+        let path = args.diff.unwrap_or_else(|| args.reference.with_extension("diff.png"));
+        diff.save(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+        println!("diff written to {}", path.display());
+    }
+    Ok(cmp.matches())
+}
+
+fn main() -> ExitCode {
+    let args = match Args::parse() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::from(2);
+        }
+    };
+    match run(args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::from(2)
+        }
+    }
+}
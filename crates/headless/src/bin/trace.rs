@@ -0,0 +1,94 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use bus::Trace;
+use headless::Console;
+use shared::mem::IOBus;
+use shared::rom::Rom;
+
+const USAGE: &str = "usage: trace [--cgb] [--doctor] [--frames N] [--start PC] [--stop PC] [--max N] <rom> <out.log>";
+
+struct Args {
+    cgb: bool,
+    /// LY reads 0x90, as expected by gameboy-doctor
+    doctor: bool,
+    frames: usize,
+    start: Option<u16>,
+    stop: Option<u16>,
+    max: Option<usize>,
+    rom: PathBuf,
+    out: PathBuf,
+}
+
+fn pc(value: String) -> Result<u16, String> {
+    u16::from_str_radix(value.trim_start_matches("0x").trim_start_matches('$'), 16)
+        .map_err(|_| format!("invalid address {value}"))
+}
+
+impl Args {
+    fn parse() -> Result<Self, String> {
+        let (mut cgb, mut doctor, mut frames, mut start, mut stop, mut max) = (false, false, 600, None, None, None);
+        let mut paths = vec![];
+        let mut it = std::env::args().skip(1);
+        while let Some(arg) = it.next() {
+            let mut value = || it.next().ok_or(format!("missing value for {arg}"));
+            match arg.as_str() {
+                "--cgb" => cgb = true,
+                "--doctor" => doctor = true,
+                "--frames" => {
+                    let n = value()?;
+                    frames = n.parse().map_err(|_| format!("invalid value for --frames: {n}"))?;
+                }
+                "--start" => start = Some(pc(value()?)?),
+                "--stop" => stop = Some(pc(value()?)?),
+                "--max" => {
+                    let n = value()?;
+                    max = Some(n.parse().map_err(|_| format!("invalid value for --max: {n}"))?);
+                }
+                "-h" | "--help" => return Err(USAGE.to_string()),
+                flag if flag.starts_with("--") => return Err(format!("unknown flag {flag}")),
+                path => paths.push(PathBuf::from(path)),
+            }
+        }
+        let [rom, out]: [PathBuf; 2] = paths.try_into().map_err(|_| USAGE.to_string())?;
+        Ok(Args { cgb, doctor, frames, start, stop, max, rom, out })
+    }
+}
+
+fn run(args: Args) -> Result<usize, String> {
+    let rom = Rom::load(&args.rom).map_err(|e| format!("{}: {e}", args.rom.display()))?;
+    let mut trace = Trace::create(&args.out).map_err(|e| format!("{}: {e}", args.out.display()))?;
+    if let Some(pc) = args.start { trace = trace.start_at(pc); }
+    if let Some(pc) = args.stop { trace = trace.stop_at(pc); }
+    if let Some(max) = args.max { trace = trace.max_lines(max); }
+    let cgb = rom.header.kind.cgb_mode(args.cgb);
+    let mut console = Console::builder(&rom).cgb(cgb).build();
+    console.gb.cpu.set_trace(Some(trace));
+    console.bus.io_regs().stub_ly(args.doctor);
+    for _ in 0..args.frames {
+        console.frame();
+        if console.gb.cpu.trace().is_some_and(Trace::done) { break; }
+    }
+    Ok(console.gb.cpu.take_trace().map(|t| t.lines()).unwrap_or_default())
+}
+
+fn main() -> ExitCode {
+    let args = match Args::parse() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::from(2);
+        }
+    };
+    let out = args.out.clone();
+    match run(args) {
+        Ok(lines) => {
+            println!("{lines} lines written to {}", out.display());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::from(2)
+        }
+    }
+}
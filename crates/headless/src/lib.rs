@@ -0,0 +1,262 @@
+use apu::{Apu, Capture};
+use bus::{Devices, Settings};
+use bus::movie::{Movie, Tape};
+use serial::{LinkDevice, Loopback};
+use shared::audio_settings::AudioSettings;
+use shared::breakpoints::Breakpoints;
+use shared::emulator::BusWrapper;
+use shared::input::{Joypad, KeyCat, Keys};
+use shared::mem::{IOBus, MBCController};
+use shared::rom::Rom;
+use shared::utils::clock::Clock;
+
+pub mod screenshot;
+pub mod test_rom;
+
+pub struct Builder<'a> {
+    rom: &'a Rom,
+    cgb: bool,
+    sgb: bool,
+    skip: bool,
+    sample_rate: u32,
+}
+
+impl<'a> Builder<'a> {
+    pub fn cgb(mut self, cgb: bool) -> Self {
+        self.cgb = cgb;
+        self
+    }
+
+    /// Super Game Boy: colors the frame and draws the border, see [`Console::display`].
+    pub fn sgb(mut self, sgb: bool) -> Self {
+        self.sgb = sgb;
+        self
+    }
+
+    pub fn skip_boot(mut self, skip: bool) -> Self {
+        self.skip = skip;
+        self
+    }
+
+    pub fn sample_rate(mut self, sample_rate: u32) -> Self {
+        self.sample_rate = sample_rate;
+        self
+    }
+
+    pub fn build(self) -> Console {
+        let (apu, capture) = Apu::capture(self.sample_rate, self.cgb);
+        let gb = Devices::builder()
+            .skip_boot(self.skip)
+            .set_cgb(self.cgb)
+            .set_sgb(self.sgb)
+            .with_apu(apu)
+            .build();
+        let bus = bus::Bus::init(self.rom)
+            .cgb(self.cgb)
+            .skip_boot(self.skip)
+            .build();
+        log::info!("cartridge: {} | device: {} (headless)", self.rom.header.title, if self.cgb { "CGB" } else { "DMG" });
+        Console {
+            rom: self.rom.clone(),
+            bus,
+            gb,
+            breakpoints: Breakpoints::default(),
+            sound: AudioSettings::default(),
+            capture,
+            clock: Clock::new(4),
+            cycles: 0,
+            cgb: self.cgb,
+            tape: None,
+        }
+    }
+
+    /// Console at power on, recording its inputs.
+    pub fn record(self) -> Console {
+        let (cgb, skip) = (self.cgb, self.skip);
+        let mut console = self.build();
+        let movie = Movie::power_on(&console.rom, cgb, skip, &console.bus);
+        console.tape = Some(Tape::record(movie, &mut console.bus));
+        console
+    }
+
+    /// Console at the movie anchor, replaying its inputs.
+    pub fn play(self, movie: Movie) -> Option<Console> {
+        let mut console = self.cgb(movie.cgb).skip_boot(movie.skip_boot).build();
+        let (bus, gb, clock) = movie.console(console.rom())?;
+        let host = std::mem::take(&mut console.gb);
+        console.gb = host.reload(gb);
+        console.bus = bus;
+        console.clock.set_value(clock);
+        console.tape = Some(Tape::play(movie));
+        Some(console)
+    }
+}
+
+/// A console without window nor sound device: the frame buffer and the audio samples
+/// are kept in memory for the caller to read.
+pub struct Console {
+    rom: Rom,
+    pub bus: bus::Bus,
+    pub gb: Devices,
+    breakpoints: Breakpoints,
+    sound: AudioSettings,
+    capture: Capture,
+    clock: Clock,
+    cycles: usize,
+    cgb: bool,
+    tape: Option<Tape>,
+}
+
+impl Console {
+    pub const CLOCK_PER_SECOND: u32 = 4_194_304;
+    pub const CYCLES_PER_FRAME: usize = 70224;
+
+    pub fn builder(rom: &Rom) -> Builder<'_> {
+        Builder { rom, cgb: false, sgb: false, skip: true, sample_rate: 44100 }
+    }
+
+    pub fn new(rom: &Rom) -> Self {
+        Console::builder(rom).build()
+    }
+
+    /// Runs a single clock cycle, returns false if a breakpoint was hit.
+    ///
+    /// Does nothing while the link cable waits on the other console, see [`serial::Port::ready`].
+    pub fn cycle(&mut self) -> bool {
+        if !self.bus.link_ready(&mut self.gb) { return true; }
+        if let Some(tape) = self.tape.as_mut() {
+            if !tape.tick(&mut self.bus, &mut self.gb) {
+                log::info!("movie ended after {} frames", tape.frame());
+                self.tape = None;
+            }
+        }
+        let clock = self.clock.tick();
+        self.cycles += 1;
+        self.bus.tick(&mut self.gb, clock, Settings {
+            breakpoints: &mut self.breakpoints,
+            sound: &mut self.sound,
+        })
+    }
+
+    /// Runs up to `cycles` clock cycles, stops early and returns false on a breakpoint.
+    pub fn step(&mut self, cycles: usize) -> bool {
+        for _ in 0..cycles {
+            if !self.cycle() { return false; }
+        }
+        true
+    }
+
+    /// Runs until the next vblank, or for one frame worth of cycles if the LCD is off.
+    pub fn frame(&mut self) -> bool {
+        let frame = self.gb.lcd.frames();
+        for _ in 0..Console::CYCLES_PER_FRAME {
+            if !self.cycle() { return false; }
+            if self.gb.lcd.frames() != frame { break; }
+        }
+        true
+    }
+
+    /// RGBA frame buffer, 160 * 144 pixels.
+    pub fn framebuffer(&self) -> &[u8] { self.gb.lcd.frame() }
+
+    /// RGBA displayed frame and its size: the frame buffer, or 256 * 224 with the SGB border.
+    pub fn display(&self) -> (u32, u32, &[u8]) {
+        let (width, height) = self.gb.lcd.size();
+        (width, height, self.gb.lcd.display())
+    }
+
+    /// Appends the interleaved stereo samples produced since the last call to `buf`.
+    pub fn drain_audio(&mut self, buf: &mut Vec<f32>) -> usize {
+        self.capture.drain(buf)
+    }
+
+    /// Presses a key, while recording it is only applied on the next frame.
+    pub fn press(&mut self, key: Keys, pressed: bool) {
+        match self.tape.as_mut() {
+            Some(tape) => tape.update(KeyCat::Joy(key), pressed, self.bus.io_regs()),
+            None => Joypad::update(&mut self.gb.joy, KeyCat::Joy(key), pressed, self.bus.io_regs()),
+        }
+    }
+
+    /// Starts recording inputs from the current state.
+    pub fn record(&mut self) {
+        let movie = Movie::snapshot(&self.rom, self.cgb, &self.bus, &self.gb, self.clock.value());
+        self.tape = Some(Tape::record(movie, &mut self.bus));
+    }
+
+    /// Plugs a device in the link port, replacing the one already plugged if any.
+    pub fn plug(&mut self, device: impl LinkDevice + 'static) {
+        self.gb.serial.plug(Box::new(device));
+    }
+
+    /// Connects two consoles with an in-process cable, to be cycled alternately.
+    pub fn link(&mut self, other: &mut Console) {
+        let (a, b) = Loopback::pair();
+        self.plug(a);
+        other.plug(b);
+    }
+
+    /// Stops the recording or playback, returns the movie.
+    pub fn eject(&mut self) -> Option<Movie> { self.tape.take().map(|x| x.eject(&mut self.bus)) }
+
+    pub fn tape(&self) -> Option<&Tape> { self.tape.as_ref() }
+
+    pub fn cycles(&self) -> usize { self.cycles }
+    pub fn frames(&self) -> usize { self.gb.lcd.frames() }
+    pub fn rom(&self) -> &Rom { &self.rom }
+    pub fn breakpoints(&mut self) -> &mut Breakpoints { &mut self.breakpoints }
+    pub fn audio_settings(&mut self) -> &mut AudioSettings { &mut self.sound }
+}
+
+impl BusWrapper for Console {
+    fn bus(&self) -> Box<&dyn shared::emulator::Bus> { Box::new(&self.bus) }
+    fn mbc(&self) -> Box<&dyn MBCController> { self.bus.mbc() }
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::breakpoints::Inspect;
+
+    use super::*;
+
+    /// MBC3 cartridge with a clock, adding the pressed direction keys to 0xC000 forever.
+    fn rom() -> Rom {
+        let mut raw = vec![0; 0x8000];
+        let program = [
+            0x21, 0x00, 0xC0, // LD HL, 0xC000
+            0x3E, 0x20, // LD A, 0x20
+            0xE0, 0x00, // LDH (0x00), A
+            0xF0, 0x00, // LDH A, (0x00)
+            0x2F, // CPL
+            0xE6, 0x0F, // AND 0x0F
+            0x86, // ADD A, (HL)
+            0x77, // LD (HL), A
+            0x18, 0xF3, // JR 0x103
+        ];
+        raw[0x100..0x100 + program.len()].copy_from_slice(&program);
+        raw[0x147] = 0x10;
+        raw[0x149] = 0x02;
+        Rom::from_bytes("movie".to_string(), Default::default(), raw).unwrap()
+    }
+
+    #[test]
+    fn replays_a_movie() {
+        let rom = rom();
+        let mut console = Console::builder(&rom).record();
+        assert!(console.bus.mbc().save_path().is_none());
+        for frame in 0..20 {
+            if frame % 3 == 0 { console.press(Keys::Right, frame % 2 == 0); }
+            console.step(Console::CYCLES_PER_FRAME);
+        }
+        let movie = console.eject().unwrap();
+        assert!(console.bus.mbc().save_path().is_some());
+        assert!(!console.bus.emulated_time(false));
+
+        let mut replay = Console::builder(&rom).play(movie).unwrap();
+        replay.step(console.cycles());
+        assert_ne!(console.bus.peek(0xC000), 0);
+        assert_eq!(replay.bus.peek(0xC000), console.bus.peek(0xC000));
+        let clock = |console: &Console| console.bus.cartridge_ram()[0x2000..0x200A].to_vec();
+        assert_eq!(clock(&replay), clock(&console));
+    }
+}
@@ -0,0 +1,104 @@
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+use image::{ImageResult, Rgba, RgbaImage};
+use lcd::Lcd;
+
+use crate::Console;
+
+/// Highlight used for mismatching pixels in the diff image, matching ones are dimmed.
+const MISMATCH: Rgba<u8> = Rgba([0xFF, 0x00, 0x00, 0xFF]);
+
+pub enum Comparison {
+    Match,
+    /// The reference does not have the frame buffer dimensions.
+    Size { width: u32, height: u32 },
+    Mismatch { pixels: usize, diff: RgbaImage },
+}
+
+impl Comparison {
+    pub fn matches(&self) -> bool { matches!(self, Comparison::Match) }
+}
+
+impl Display for Comparison {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Comparison::Match => f.write_str("match"),
+            Comparison::Size { width, height } => f.write_fmt(format_args!("reference is {width}x{height}, expected {}x{}", Lcd::WIDTH, Lcd::HEIGHT)),
+            Comparison::Mismatch { pixels, .. } => f.write_fmt(format_args!("{pixels} pixels differ")),
+        }
+    }
+}
+
+/// Runs `console` for `frames` frames (or until a breakpoint is hit) and returns the last frame.
+pub fn capture(console: &mut Console, frames: usize) -> RgbaImage {
+    for _ in 0..frames {
+        if !console.frame() { break; }
+    }
+    screenshot(console)
+}
+
+pub fn screenshot(console: &Console) -> RgbaImage {
+    RgbaImage::from_raw(Lcd::WIDTH, Lcd::HEIGHT, console.framebuffer().to_vec()).expect("invalid frame buffer size")
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> ImageResult<RgbaImage> {
+    Ok(image::open(path)?.to_rgba8())
+}
+
+/// Compares the color channels of `frame` against `reference`, alpha is ignored.
+pub fn compare(frame: &RgbaImage, reference: &RgbaImage) -> Comparison {
+    if reference.dimensions() != frame.dimensions() {
+        let (width, height) = reference.dimensions();
+        return Comparison::Size { width, height };
+    }
+    let mut pixels = 0;
+    let diff = RgbaImage::from_fn(Lcd::WIDTH, Lcd::HEIGHT, |x, y| {
+        let (l, r) = (frame.get_pixel(x, y), reference.get_pixel(x, y));
+        if l.0[..3] == r.0[..3] {
+            let [r, g, b, _] = l.0;
+            Rgba([r / 4, g / 4, b / 4, 0xFF])
+        } else {
+            pixels += 1;
+            MISMATCH
+        }
+    });
+    if pixels == 0 { Comparison::Match } else { Comparison::Mismatch { pixels, diff } }
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::rom::Rom;
+
+    use super::*;
+
+    fn frame() -> RgbaImage {
+        RgbaImage::from_fn(Lcd::WIDTH, Lcd::HEIGHT, |x, y| Rgba([x as u8, y as u8, 0x80, 0xFF]))
+    }
+
+    #[test]
+    fn captures_the_lcd() {
+        let rom = Rom::from_bytes("blank".to_string(), Default::default(), vec![0; 0x8000]).unwrap();
+        let mut console = Console::builder(&rom).build();
+        assert_eq!(capture(&mut console, 1).dimensions(), (Lcd::WIDTH, Lcd::HEIGHT));
+    }
+
+    #[test]
+    fn compares_the_colors() {
+        let mut reference = frame();
+        assert!(compare(&frame(), &reference).matches());
+        reference.get_pixel_mut(3, 4).0[3] = 0;
+        assert!(compare(&frame(), &reference).matches());
+        reference.get_pixel_mut(3, 4).0[0] ^= 1;
+        let Comparison::Mismatch { pixels, diff } = compare(&frame(), &reference) else { panic!("frames should differ") };
+        assert_eq!(pixels, 1);
+        assert_eq!(*diff.get_pixel(3, 4), MISMATCH);
+        assert_eq!(*diff.get_pixel(0, 0), Rgba([0, 0, 0x20, 0xFF]));
+    }
+
+    #[test]
+    fn reports_the_reference_size() {
+        let reference = RgbaImage::new(Lcd::WIDTH / 2, Lcd::HEIGHT);
+        assert!(matches!(compare(&frame(), &reference), Comparison::Size { width: 80, height: 144 }));
+    }
+}
@@ -0,0 +1,139 @@
+use std::fmt::{Display, Formatter};
+
+use shared::breakpoints::Breakpoint;
+use shared::cpu::Opcode;
+use shared::rom::Rom;
+
+use crate::Console;
+
+/// Mooneye's success signature, loaded in B, C, D, E, H, L before `LD B,B`.
+const FIBONACCI: [u8; 6] = [3, 5, 8, 13, 21, 34];
+/// Mooneye's failure signature, every register set to 0x42.
+const FAILURE: [u8; 6] = [0x42; 6];
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Verdict {
+    Passed,
+    Failed(String),
+    Timeout,
+}
+
+impl Verdict {
+    pub fn passed(&self) -> bool { self == &Verdict::Passed }
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Passed => f.write_str("PASS"),
+            Verdict::Failed(reason) => f.write_fmt(format_args!("FAIL ({reason})")),
+            Verdict::Timeout => f.write_str("TIMEOUT"),
+        }
+    }
+}
+
+/// Runs a Blargg or Mooneye test rom for at most `cycles` clock cycles.
+/// Blargg roms report through the serial port ("Passed"/"Failed"),
+/// Mooneye roms through the registers once they execute `LD B,B`.
+pub struct TestRom {
+    console: Console,
+    cycles: usize,
+}
+
+impl TestRom {
+    pub fn new(rom: &Rom, cgb: bool, cycles: usize) -> Self {
+        let mut console = Console::builder(rom).cgb(cgb).build();
+        console.gb.serial.record();
+        console.breakpoints().schedule(Breakpoint::instruction(Opcode::LdBB));
+        Self { console, cycles }
+    }
+
+    pub fn serial(&self) -> String {
+        String::from_utf8_lossy(self.console.gb.serial.sent()).to_string()
+    }
+
+    fn blargg(&self) -> Option<Verdict> {
+        let out = self.serial();
+        if out.contains("Passed") {
+            Some(Verdict::Passed)
+        } else { out.find("Failed").map(|n| Verdict::Failed(out[n..].trim().to_string())) }
+    }
+
+    fn mooneye(&self) -> Option<Verdict> {
+        let regs = self.console.gb.cpu.registers();
+        let values = [regs.b(), regs.c(), regs.d(), regs.e(), regs.h(), regs.l()];
+        match values {
+            FIBONACCI => Some(Verdict::Passed),
+            FAILURE => Some(Verdict::Failed("mooneye failure signature".to_string())),
+            _ => None
+        }
+    }
+
+    pub fn run(&mut self) -> Verdict {
+        while self.console.cycles() < self.cycles {
+            if !self.console.frame() {
+                if let Some(verdict) = self.mooneye() { return verdict; }
+            }
+            if let Some(verdict) = self.blargg() { return verdict; }
+        }
+        Verdict::Timeout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rom running `program` from 0x100, with `data` at 0x150.
+    fn rom(program: &[u8], data: &[u8]) -> Rom {
+        let mut raw = vec![0; 0x8000];
+        raw[0x100..0x100 + program.len()].copy_from_slice(program);
+        raw[0x150..0x150 + data.len()].copy_from_slice(data);
+        Rom::from_bytes("test".to_string(), Default::default(), raw).unwrap()
+    }
+
+    /// Loads B, C, D, E, H, L then executes `LD B,B` forever.
+    fn mooneye(values: [u8; 6]) -> Rom {
+        let [b, c, d, e, h, l] = values;
+        rom(&[0x06, b, 0x0E, c, 0x16, d, 0x1E, e, 0x26, h, 0x2E, l, 0x40, 0x18, 0xFD], &[])
+    }
+
+    /// Prints the nul terminated `text` through the serial port.
+    fn blargg(text: &str) -> Rom {
+        let program = [
+            0x21, 0x50, 0x01, // LD HL, 0x150
+            0x2A, // LD A, (HL+)
+            0xB7, // OR A
+            0x28, 0x08, // JR Z, 0x10F
+            0xE0, 0x01, // LDH (SB), A
+            0x3E, 0x81, // LD A, 0x81
+            0xE0, 0x02, // LDH (SC), A
+            0x18, 0xF4, // JR 0x103
+            0x18, 0xFE, // JR 0x10F
+        ];
+        rom(&program, &[text.as_bytes(), &[0]].concat())
+    }
+
+    fn run(rom: &Rom) -> Verdict {
+        TestRom::new(rom, false, 10 * Console::CYCLES_PER_FRAME).run()
+    }
+
+    #[test]
+    fn mooneye_signatures() {
+        assert_eq!(run(&mooneye(FIBONACCI)), Verdict::Passed);
+        assert!(matches!(run(&mooneye(FAILURE)), Verdict::Failed(_)));
+    }
+
+    #[test]
+    fn blargg_output() {
+        assert_eq!(run(&blargg("cpu_instrs\n\nPassed")), Verdict::Passed);
+        assert_eq!(run(&blargg("01:ok\nFailed #3\n")), Verdict::Failed("Failed #3".to_string()));
+    }
+
+    #[test]
+    fn timeout() {
+        assert_eq!(run(&rom(&[0x18, 0xFE], &[])), Verdict::Timeout);
+        // other register values are not a verdict
+        assert_eq!(run(&mooneye([1, 2, 3, 4, 5, 6])), Verdict::Timeout);
+    }
+}
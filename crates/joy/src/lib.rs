@@ -3,24 +3,52 @@ use shared::io::{IO, IODevice, IORegs};
 use shared::mem::IOBus;
 use shared::serde::{Deserialize, Serialize};
 
+pub use crate::sgb::Packets;
+
+mod sgb;
+
 #[derive(Default, Serialize, Deserialize, Clone)]
 pub struct Joypad {
     state: u8,
+    /// Super Game Boy command packets, only received in SGB mode
+    sgb: Option<Packets>,
 }
 
 impl Joypad {
     pub fn new() -> Self {
-        Self { state: 0 }
+        Self { state: 0, sgb: None }
+    }
+
+    /// Joypad of a Super Game Boy, receiving command packets.
+    pub fn sgb() -> Self {
+        Self { state: 0, sgb: Some(Packets::new()) }
+    }
+
+    /// Pressed keys, one bit per [`shared::input::Keys`].
+    pub fn state(&self) -> u8 { self.state }
+
+    pub fn set_state(&mut self, state: u8, io: &mut IORegs) {
+        self.state = state;
+        self.update(io);
+    }
+
+    /// Takes the last SGB command received, see [`Packets::command`].
+    pub fn command(&mut self) -> Option<Vec<u8>> {
+        self.sgb.as_mut().and_then(Packets::command)
     }
 
     pub fn update(&mut self, io: &mut IORegs) {
         let joy = io.io_mut(IO::JOYP);
         let p4 = joy.bit(4);
         let p5 = joy.bit(5);
-        let dir = if p4 == 0 { self.state >> 4 } else { 0 };
-        let act = if p5 == 0 { self.state & 0xF } else { 0 };
+        let state = if self.sgb.as_ref().map_or(true, Packets::first) { self.state } else { 0 };
+        let dir = if p4 == 0 { state >> 4 } else { 0 };
+        let act = if p5 == 0 { state & 0xF } else { 0 };
         let p = joy.value() & 0xF;
-        let v = 0xF ^ (dir | act);
+        let v = match self.sgb.as_ref().and_then(Packets::id) {
+            Some(id) if p4 != 0 && p5 != 0 => id,
+            _ => 0xF ^ (dir | act),
+        };
         let int = (p ^ v) & p != 0;
         joy.direct_write((p4 << 4) | (p5 << 5) | v);
         if int { io.int_set(4); }
@@ -38,7 +66,10 @@ impl shared::input::Joypad for Joypad {
 }
 
 impl IODevice for Joypad {
-    fn write(&mut self, io: IO, _: u8, bus: &mut dyn IOBus) {
-        if io == IO::JOYP { self.update(bus.io_regs()) }
+    fn write(&mut self, io: IO, v: u8, bus: &mut dyn IOBus) {
+        if io == IO::JOYP {
+            if let Some(sgb) = self.sgb.as_mut() { sgb.write(v); }
+            self.update(bus.io_regs())
+        }
     }
 }
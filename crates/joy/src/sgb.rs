@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+
+/// MLT_REQ, handled by the joypad itself.
+const MLT_REQ: u8 = 0x11;
+
+/// Receives the Super Game Boy command packets, sent one bit at a time through P14/P15:
+/// a reset pulse (both low), 128 bits (P14 low for 0, P15 low for 1, each followed by
+/// both high) and a stop bit.
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub struct Packets {
+    /// bits received for the current packet, none while waiting for a reset pulse
+    bits: Option<usize>,
+    packet: [u8; 16],
+    command: Vec<u8>,
+    ready: Option<Vec<u8>>,
+    select: u8,
+    players: u8,
+    player: u8,
+}
+
+impl Packets {
+    pub fn new() -> Self {
+        Self { select: 0x30, players: 1, ..Default::default() }
+    }
+
+    /// Feeds a JOYP write.
+    pub fn write(&mut self, v: u8) {
+        let select = v & 0x30;
+        if self.players > 1 && select & 0x20 == 0 && self.select & 0x20 != 0 {
+            self.player = (self.player + 1) & (self.players - 1);
+        }
+        match (self.select, select) {
+            (_, 0x00) => {
+                self.bits = Some(0);
+                self.packet = [0; 16];
+            }
+            (0x30, 0x10 | 0x20) => if let Some(n) = self.bits {
+                if n < 128 {
+                    if select == 0x10 { self.packet[n / 8] |= 1 << (n % 8); }
+                    self.bits = Some(n + 1);
+                } else {
+                    self.bits = None;
+                    self.receive();
+                }
+            }
+            _ => {}
+        }
+        self.select = select;
+    }
+
+    fn receive(&mut self) {
+        self.command.extend_from_slice(&self.packet);
+        let len = (self.command[0] & 0x7).max(1) as usize;
+        if self.command.len() < len * 16 { return; }
+        let command = std::mem::take(&mut self.command);
+        log::debug!("sgb command {:#04X}", command[0] >> 3);
+        if command[0] >> 3 == MLT_REQ {
+            self.players = [1, 2, 1, 4][(command[1] & 0x3) as usize];
+            self.player = 0;
+        }
+        self.ready = Some(command);
+    }
+
+    /// Takes the last complete command, its packets concatenated.
+    pub fn command(&mut self) -> Option<Vec<u8>> { self.ready.take() }
+
+    /// Joypad read with both P14 and P15 high: the id of the current player, in multiplayer mode.
+    pub fn id(&self) -> Option<u8> {
+        (self.players > 1).then_some(0xF - self.player)
+    }
+
+    /// Only the first player has a controller plugged in.
+    pub fn first(&self) -> bool { self.player == 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sends `packet` bit by bit, as the SGB games do.
+    fn send(packets: &mut Packets, packet: &[u8; 16]) {
+        packets.write(0x00);
+        packets.write(0x30);
+        for n in 0..128 {
+            packets.write(if packet[n / 8] >> (n % 8) & 1 != 0 { 0x10 } else { 0x20 });
+            packets.write(0x30);
+        }
+        packets.write(0x20);
+        packets.write(0x30);
+    }
+
+    #[test]
+    fn decodes_packets() {
+        let mut packets = Packets::new();
+        let mut packet = [0; 16];
+        packet[0] = 0x01;
+        packet[1..5].copy_from_slice(&[0xFF, 0x7F, 0x5A, 0xA5]);
+        send(&mut packets, &packet);
+        assert_eq!(packets.command(), Some(packet.to_vec()));
+        assert_eq!(packets.command(), None);
+    }
+
+    #[test]
+    fn concatenates_commands() {
+        let mut packets = Packets::new();
+        let mut first = [0x3A; 16];
+        let second = [0x5C; 16];
+        first[0] = 0x22;
+        send(&mut packets, &first);
+        assert_eq!(packets.command(), None);
+        send(&mut packets, &second);
+        assert_eq!(packets.command(), Some([first, second].concat()));
+    }
+
+    #[test]
+    fn requests_players() {
+        let mut packets = Packets::new();
+        assert_eq!(packets.id(), None);
+        let mut packet = [0; 16];
+        packet[0] = MLT_REQ << 3 | 1;
+        packet[1] = 1;
+        send(&mut packets, &packet);
+        assert_eq!(packets.id(), Some(0xF));
+        assert!(packets.first());
+        packets.write(0x10);
+        packets.write(0x30);
+        assert_eq!(packets.id(), Some(0xE));
+        assert!(!packets.first());
+    }
+}
@@ -1,15 +1,29 @@
+#[cfg(feature = "window")]
 use pixels::{Pixels, PixelsBuilder, SurfaceTexture};
 
 use shared::io::{IO, IORegs};
 use shared::serde::{Deserialize, Serialize};
+#[cfg(feature = "window")]
 use shared::winit as winit;
 
+pub use crate::sgb::Sgb;
+
+mod sgb;
+
 #[derive(Serialize, Deserialize)]
 pub struct Lcd {
     enabled: bool,
     frame: Vec<u8>,
     #[serde(default, skip)]
+    frames: usize,
+    /// color of each pixel before the SGB palettes, read by the SGB VRAM transfers
+    #[serde(default, skip)]
+    shades: Vec<u8>,
+    #[cfg(feature = "window")]
+    #[serde(default, skip)]
     pub pixels: Option<Pixels>,
+    /// Super Game Boy border and colors, none on other models
+    sgb: Option<Box<Sgb>>,
 }
 
 impl Default for Lcd {
@@ -17,7 +31,11 @@ impl Default for Lcd {
         Self {
             enabled: false,
             frame: vec![0; (4 * Lcd::WIDTH * Lcd::HEIGHT) as usize],
+            frames: 0,
+            shades: vec![],
+            #[cfg(feature = "window")]
             pixels: None,
+            sgb: None,
         }
     }
 }
@@ -28,7 +46,7 @@ impl LCD for Lcd {
             return;
         }
         let f = (Lcd::WIDTH * 4) as usize;
-        self.frame[x * 4 + 0 + y * f] = color[0];
+        self.frame[x * 4 + y * f] = color[0];
         self.frame[x * 4 + 1 + y * f] = color[1];
         self.frame[x * 4 + 2 + y * f] = color[2];
         self.frame[x * 4 + 3 + y * f] = 0xFF;
@@ -40,20 +58,31 @@ impl LCD for Lcd {
 
     fn disable(&mut self, io: &IORegs) {
         self.enabled = false;
-        let white = if io.io(IO::CGB).bit(0) != 0 { [0xFF; 3] } else { io.palette().color(0) };
+        let white = match self.sgb.as_ref() {
+            Some(sgb) => sgb.color(0, 0, 0),
+            None if io.io(IO::CGB).bit(0) != 0 => [0xFF; 3],
+            None => io.palette().color(0),
+        };
+        for (i, p) in self.frame.iter_mut().enumerate() {
+            *p = if i % 4 == 3 { 0xFF } else { white[i % 4] };
+        }
+        if let Some(sgb) = self.sgb.as_mut() { sgb.draw(&self.frame); }
+        #[cfg(feature = "window")]
         if let Some(pixels) = self.pixels.as_mut() {
-            let pixels = pixels.frame_mut();
-            for i in 0..(4 * Lcd::WIDTH * Lcd::HEIGHT) as usize {
-                pixels[i] = if i % 4 == 3 { 0xFF } else { white[i % 4] };
-            }
+            let display = self.sgb.as_ref().map(|x| x.screen()).unwrap_or(self.frame.as_slice());
+            pixels.frame_mut().copy_from_slice(display);
         }
     }
 
     fn vblank(&mut self) {
+        self.frames += 1;
+        if let Some(sgb) = self.sgb.as_mut() { sgb.vblank(&self.shades, &self.frame); }
+        #[cfg(feature = "window")]
         if let Some(pixels) = self.pixels.as_mut() {
+            let display = self.sgb.as_ref().map(|x| x.screen()).unwrap_or(self.frame.as_slice());
             pixels.frame_mut()
                 .iter_mut()
-                .zip(self.frame.iter_mut())
+                .zip(display.iter())
                 .for_each(|(d, s)| *d = *s);
         }
     }
@@ -63,26 +92,80 @@ impl Lcd {
     pub const WIDTH: u32 = 160;
     pub const HEIGHT: u32 = 144;
 
+    /// RGBA buffer of the last drawn frame, `WIDTH * HEIGHT * 4` bytes long.
+    pub fn frame(&self) -> &[u8] { &self.frame }
+
+    /// Number of vblanks since power on (not kept in save states).
+    pub fn frames(&self) -> usize { self.frames }
+
+    /// Emulates a Super Game Boy: colors the screen with its palettes and draws the border around it.
+    pub fn set_sgb(&mut self, sgb: bool) {
+        self.sgb = sgb.then(Box::default);
+        self.shades = if sgb { vec![0; (Lcd::WIDTH * Lcd::HEIGHT) as usize] } else { vec![] };
+    }
+
+    pub fn sgb(&self) -> Option<&Sgb> { self.sgb.as_deref() }
+
+    /// Runs a command received by the SGB, see [`Sgb::command`].
+    pub fn command(&mut self, data: &[u8]) {
+        if let Some(sgb) = self.sgb.as_mut() { sgb.command(data); }
+    }
+
+    /// Sets a pixel from its shade (0-3, after BGP/OBP), colored by the SGB palettes.
+    pub fn shade(&mut self, x: usize, y: usize, shade: u8) {
+        let Some(color) = self.sgb.as_ref().map(|sgb| sgb.color(x, y, shade)) else { return; };
+        if !self.enabled { return; }
+        self.shades[y * Lcd::WIDTH as usize + x] = shade;
+        self.set(x, y, color);
+    }
+
+    /// Size of the displayed frame: the screen, or the screen in its border on a SGB.
+    pub fn size(&self) -> (u32, u32) {
+        match self.sgb {
+            Some(_) => (Sgb::WIDTH, Sgb::HEIGHT),
+            None => (Lcd::WIDTH, Lcd::HEIGHT),
+        }
+    }
+
+    /// RGBA buffer of the displayed frame, see [`Lcd::size`].
+    pub fn display(&self) -> &[u8] {
+        self.sgb.as_ref().map(|x| x.screen()).unwrap_or(self.frame.as_slice())
+    }
+
     pub fn reload(self, load: Self) -> Self {
         let mut t = load;
-        t.pixels = self.pixels;
+        t.frames = self.frames;
+        t.shades = self.shades;
+        t.shades.resize(if t.sgb.is_some() { (Lcd::WIDTH * Lcd::HEIGHT) as usize } else { 0 }, 0);
+        #[cfg(feature = "window")]
+        {
+            t.pixels = self.pixels;
+            let display = t.sgb.as_ref().map(|x| x.screen()).unwrap_or(t.frame.as_slice());
+            if let Some(pixels) = t.pixels.as_mut() {
+                pixels.frame_mut().copy_from_slice(display);
+            }
+        }
         t
     }
 
+    #[cfg(feature = "window")]
     pub fn init(&mut self, window: &winit::window::Window) {
         let sz = window.inner_size();
         let surf = SurfaceTexture::new(sz.width, sz.height, window);
-        let pixels = PixelsBuilder::new(Lcd::WIDTH, Lcd::HEIGHT, surf)
+        let (width, height) = self.size();
+        let pixels = PixelsBuilder::new(width, height, surf)
             .enable_vsync(false).build().unwrap();
         self.pixels.replace(pixels);
     }
 
+    #[cfg(feature = "window")]
     pub fn resize(&mut self, width: u32, height: u32) {
         if let Some(pixels) = self.pixels.as_mut() {
             pixels.resize_surface(width, height).ok();
         }
     }
 
+    #[cfg(feature = "window")]
     pub fn render(&mut self) {
         if let Some(pixels) = self.pixels.as_mut() { pixels.render().ok(); }
     }
@@ -0,0 +1,299 @@
+use serde::{Deserialize, Serialize};
+
+use shared::utils::serde_arrays;
+
+/// Super Game Boy commands, the first byte of a command is `code << 3 | packets`.
+mod cmd {
+    pub const PAL01: u8 = 0x00;
+    pub const PAL23: u8 = 0x01;
+    pub const PAL03: u8 = 0x02;
+    pub const PAL12: u8 = 0x03;
+    pub const ATTR_BLK: u8 = 0x04;
+    pub const ATTR_LIN: u8 = 0x05;
+    pub const ATTR_DIV: u8 = 0x06;
+    pub const ATTR_CHR: u8 = 0x07;
+    pub const PAL_SET: u8 = 0x0A;
+    pub const PAL_TRN: u8 = 0x0B;
+    pub const MLT_REQ: u8 = 0x11;
+    pub const CHR_TRN: u8 = 0x13;
+    pub const PCT_TRN: u8 = 0x14;
+    pub const MASK_EN: u8 = 0x17;
+}
+
+/// Data sent through VRAM, read from the screen of the frame following the command.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+enum Transfer {
+    Palettes,
+    /// border tiles 0x00-0x7F, or 0x80-0xFF
+    Tiles(bool),
+    Border,
+}
+
+const COLUMNS: usize = 20;
+const ROWS: usize = 18;
+
+/// Super Game Boy state: palettes and attributes colorizing the screen, and the border around it.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Sgb {
+    /// rgb555, color 0 is shared by the 4 palettes
+    palettes: [[u16; 4]; 4],
+    /// system palettes, sent with PAL_TRN and picked with PAL_SET
+    system: Vec<[u16; 4]>,
+    /// palette of each tile of the screen
+    #[serde(with = "serde_arrays")]
+    attrs: [u8; COLUMNS * ROWS],
+    mask: u8,
+    /// 4bpp SNES tiles of the border
+    tiles: Vec<u8>,
+    /// 32x28 tile map of the border, SNES format
+    map: Vec<u16>,
+    /// border palettes 4-7
+    border: [[u16; 16]; 4],
+    transfer: Option<Transfer>,
+    screen: Vec<u8>,
+}
+
+impl Default for Sgb {
+    fn default() -> Self {
+        Self {
+            palettes: [[0x7FFF, 0x56B5, 0x294A, 0x0000]; 4],
+            system: vec![[0; 4]; 512],
+            attrs: [0; COLUMNS * ROWS],
+            mask: 0,
+            tiles: vec![0; 256 * 32],
+            map: vec![0; 32 * 32],
+            border: [[0; 16]; 4],
+            transfer: None,
+            screen: vec![0xFF; (4 * Sgb::WIDTH * Sgb::HEIGHT) as usize],
+        }
+    }
+}
+
+fn rgb(rgb555: u16) -> [u8; 3] {
+    let r = (rgb555 & 0x1F) as u8;
+    let g = (rgb555 >> 5) as u8 & 0x1F;
+    let b = (rgb555 >> 10) as u8 & 0x1F;
+    [r << 3 | r >> 2, g << 3 | g >> 2, b << 3 | b >> 2]
+}
+
+fn word(data: &[u8], index: usize) -> u16 {
+    data[index] as u16 | (data[index + 1] as u16) << 8
+}
+
+impl Sgb {
+    pub const WIDTH: u32 = 256;
+    pub const HEIGHT: u32 = 224;
+    /// top left corner of the game screen in the border
+    const X: usize = 48;
+    const Y: usize = 40;
+
+    /// Color of a pixel of the game screen, `shade` being its color after BGP/OBP.
+    pub fn color(&self, x: usize, y: usize, shade: u8) -> [u8; 3] {
+        let palette = self.attrs[y / 8 * COLUMNS + x / 8] as usize;
+        rgb(self.palettes[palette][shade as usize & 0x3])
+    }
+
+    /// Frame with the border, `WIDTH * HEIGHT * 4` bytes long.
+    pub fn screen(&self) -> &[u8] { &self.screen }
+
+    pub fn command(&mut self, data: &[u8]) {
+        match data[0] >> 3 {
+            cmd::PAL01 => self.palettes_pair(0, 1, data),
+            cmd::PAL23 => self.palettes_pair(2, 3, data),
+            cmd::PAL03 => self.palettes_pair(0, 3, data),
+            cmd::PAL12 => self.palettes_pair(1, 2, data),
+            cmd::ATTR_BLK => self.attr_blk(data),
+            cmd::ATTR_LIN => self.attr_lin(data),
+            cmd::ATTR_DIV => self.attr_div(data),
+            cmd::ATTR_CHR => self.attr_chr(data),
+            cmd::PAL_SET => {
+                for (i, palette) in self.palettes.iter_mut().enumerate() {
+                    *palette = self.system[word(data, 1 + i * 2) as usize & 0x1FF];
+                }
+                let shared = self.palettes[0][0];
+                self.palettes.iter_mut().for_each(|x| x[0] = shared);
+                if data[9] & 0x40 != 0 { self.mask = 0; }
+            }
+            cmd::PAL_TRN => self.transfer = Some(Transfer::Palettes),
+            cmd::CHR_TRN => self.transfer = Some(Transfer::Tiles(data[1] & 0x1 != 0)),
+            cmd::PCT_TRN => self.transfer = Some(Transfer::Border),
+            cmd::MASK_EN => self.mask = data[1] & 0x3,
+            cmd::MLT_REQ => {}
+            n => log::warn!("unsupported SGB command {n:#04X}"),
+        }
+    }
+
+    /// PAL01, PAL23, PAL03 and PAL12: colors 0-3 of `a`, 1-3 of `b`, and the shared color 0.
+    fn palettes_pair(&mut self, a: usize, b: usize, data: &[u8]) {
+        let shared = word(data, 1);
+        self.palettes.iter_mut().for_each(|x| x[0] = shared);
+        for c in 1..4 {
+            self.palettes[a][c] = word(data, 1 + c * 2);
+            self.palettes[b][c] = word(data, 7 + c * 2);
+        }
+    }
+
+    fn attr_blk(&mut self, data: &[u8]) {
+        let sets = (data[1] as usize & 0x1F).min(18);
+        for set in data[2..].chunks_exact(6).take(sets) {
+            let control = set[0] & 0x7;
+            let inside = set[1] & 0x3;
+            let border = (set[1] >> 2) & 0x3;
+            let outside = (set[1] >> 4) & 0x3;
+            let (x1, y1, x2, y2) = (set[2] as usize, set[3] as usize, set[4] as usize, set[5] as usize);
+            // changing only the inside or the outside also changes the border
+            let border = match control {
+                0x1 => Some(inside),
+                0x4 => Some(outside),
+                _ if control & 0x2 != 0 => Some(border),
+                _ => None,
+            };
+            for y in 0..ROWS {
+                for x in 0..COLUMNS {
+                    let palette = if x > x1 && x < x2 && y > y1 && y < y2 {
+                        Some(inside).filter(|_| control & 0x1 != 0)
+                    } else if x < x1 || x > x2 || y < y1 || y > y2 {
+                        Some(outside).filter(|_| control & 0x4 != 0)
+                    } else {
+                        border
+                    };
+                    if let Some(palette) = palette { self.attrs[y * COLUMNS + x] = palette; }
+                }
+            }
+        }
+    }
+
+    fn attr_lin(&mut self, data: &[u8]) {
+        let sets = data[1] as usize;
+        for &line in data[2..].iter().take(sets) {
+            let n = line as usize & 0x1F;
+            let palette = (line >> 5) & 0x3;
+            if line & 0x80 != 0 {
+                if n < ROWS { self.attrs[n * COLUMNS..(n + 1) * COLUMNS].fill(palette); }
+            } else if n < COLUMNS {
+                (0..ROWS).for_each(|y| self.attrs[y * COLUMNS + n] = palette);
+            }
+        }
+    }
+
+    fn attr_div(&mut self, data: &[u8]) {
+        let after = data[1] & 0x3;
+        let before = (data[1] >> 2) & 0x3;
+        let line = (data[1] >> 4) & 0x3;
+        let horizontal = data[1] & 0x40 != 0;
+        let at = data[2] as usize;
+        for y in 0..ROWS {
+            for x in 0..COLUMNS {
+                let n = if horizontal { y } else { x };
+                self.attrs[y * COLUMNS + x] = match n.cmp(&at) {
+                    std::cmp::Ordering::Less => before,
+                    std::cmp::Ordering::Equal => line,
+                    std::cmp::Ordering::Greater => after,
+                };
+            }
+        }
+    }
+
+    fn attr_chr(&mut self, data: &[u8]) {
+        let (mut x, mut y) = (data[1] as usize, data[2] as usize);
+        let count = (word(data, 3) as usize).min(COLUMNS * ROWS);
+        let vertical = data[5] & 0x1 != 0;
+        for i in 0..count {
+            let Some(byte) = data.get(6 + i / 4) else { break; };
+            if x >= COLUMNS || y >= ROWS { break; }
+            self.attrs[y * COLUMNS + x] = (byte >> (6 - 2 * (i % 4))) & 0x3;
+            if vertical {
+                y += 1;
+                if y == ROWS { y = 0; x += 1; }
+            } else {
+                x += 1;
+                if x == COLUMNS { x = 0; y += 1; }
+            }
+        }
+    }
+
+    /// Completes a pending transfer with the 4KiB displayed by `shades`, the tiles of the screen
+    /// read left to right and top to bottom in the 2bpp format of the VRAM.
+    fn transfer(&mut self, shades: &[u8]) {
+        let Some(transfer) = self.transfer.take() else { return; };
+        let mut data = vec![0u8; 0x1000];
+        for (tile, raw) in data.chunks_exact_mut(16).enumerate() {
+            let (tx, ty) = (tile % COLUMNS * 8, tile / COLUMNS * 8);
+            for line in 0..8 {
+                for px in 0..8 {
+                    let shade = shades[(ty + line) * COLUMNS * 8 + tx + px];
+                    raw[line * 2] |= (shade & 0x1) << (7 - px);
+                    raw[line * 2 + 1] |= ((shade >> 1) & 0x1) << (7 - px);
+                }
+            }
+        }
+        log::debug!("sgb {transfer:?} transfer");
+        match transfer {
+            Transfer::Palettes => {
+                for (palette, raw) in self.system.iter_mut().zip(data.chunks_exact(8)) {
+                    *palette = [word(raw, 0), word(raw, 2), word(raw, 4), word(raw, 6)];
+                }
+            }
+            Transfer::Tiles(high) => {
+                let offset = if high { 0x1000 } else { 0 };
+                self.tiles[offset..offset + 0x1000].copy_from_slice(&data);
+            }
+            Transfer::Border => {
+                for (entry, raw) in self.map.iter_mut().zip(data.chunks_exact(2)) {
+                    *entry = word(raw, 0);
+                }
+                for (i, raw) in data[0x800..0x880].chunks_exact(2).enumerate() {
+                    self.border[i / 16][i % 16] = word(raw, 0);
+                }
+            }
+        }
+    }
+
+    /// Color index of a pixel of the border, 0 being transparent.
+    fn border_pixel(&self, x: usize, y: usize) -> (usize, u8) {
+        let entry = self.map[y / 8 * 32 + x / 8];
+        let tile = (entry & 0xFF) as usize;
+        let palette = ((entry >> 10) & 0x7) as usize;
+        let px = if entry & 0x4000 != 0 { 7 - x % 8 } else { x % 8 };
+        let line = if entry & 0x8000 != 0 { 7 - y % 8 } else { y % 8 };
+        let raw = &self.tiles[tile * 32..tile * 32 + 32];
+        let bit = |plane: usize| (raw[(plane / 2) * 16 + line * 2 + plane % 2] >> (7 - px)) & 0x1;
+        (palette.saturating_sub(4) & 0x3, bit(0) | bit(1) << 1 | bit(2) << 2 | bit(3) << 3)
+    }
+
+    /// Ends a frame: runs the pending transfer, then draws the frame.
+    pub fn vblank(&mut self, shades: &[u8], frame: &[u8]) {
+        self.transfer(shades);
+        self.draw(frame);
+    }
+
+    /// Draws the border and the game `frame` in it, unless masked.
+    pub fn draw(&mut self, frame: &[u8]) {
+        let width = Sgb::WIDTH as usize;
+        let backdrop = rgb(self.palettes[0][0]);
+        for y in 0..Sgb::HEIGHT as usize {
+            for x in 0..width {
+                let (gx, gy) = (x.wrapping_sub(Sgb::X), y.wrapping_sub(Sgb::Y));
+                let color = if gx < COLUMNS * 8 && gy < ROWS * 8 {
+                    match self.mask {
+                        0 => {
+                            let i = (gy * COLUMNS * 8 + gx) * 4;
+                            [frame[i], frame[i + 1], frame[i + 2]]
+                        }
+                        1 => continue,
+                        2 => [0; 3],
+                        _ => backdrop,
+                    }
+                } else {
+                    match self.border_pixel(x, y) {
+                        (_, 0) => backdrop,
+                        (palette, c) => rgb(self.border[palette][c as usize]),
+                    }
+                };
+                let i = (y * width + x) * 4;
+                self.screen[i..i + 3].copy_from_slice(&color);
+                self.screen[i + 3] = 0xFF;
+            }
+        }
+    }
+}
@@ -3,6 +3,7 @@ use serde::{Deserializer, Serializer};
 use shared::mem::Mem;
 use shared::rom::Rom;
 use shared::serde::{Deserialize, Serialize};
+use shared::utils::camera::Sensor;
 
 use crate::mbc::{Mbc, MbcKind, MemoryController, Unplugged};
 
@@ -27,6 +28,9 @@ impl BootSection for DmgBoot {
     fn contains(&self, addr: u16) -> bool { (0..0x100).contains(&addr) }
 }
 
+/// CGB boot ROM, its tables also give the palettes of DMG games (see [`crate::colorize`]).
+pub(crate) const CGB_BOOT: &[u8] = include_bytes!("../../../assets/boot/cgb_boot.bin");
+
 #[derive(Serialize, Deserialize)]
 struct CgbBoot {
     raw: Vec<u8>,
@@ -34,7 +38,7 @@ struct CgbBoot {
 
 impl BootSection for CgbBoot {
     fn new() -> Self where Self: Sized {
-        Self { raw: include_bytes!("../../../assets/boot/cgb_boot.bin").to_vec() }
+        Self { raw: CGB_BOOT.to_vec() }
     }
     fn contains(&self, addr: u16) -> bool {
         (0..0x100).contains(&addr) || (0x200..=0x08FF).contains(&addr)
@@ -113,6 +117,14 @@ impl Mbc for Boot {
     }
 
     fn unmap(&mut self) -> Box<dyn Mbc> { std::mem::replace(&mut self.inner, Box::new(Unplugged {})) }
+
+    fn emulated_time(&mut self, emulated: bool) -> bool { self.inner.emulated_time(emulated) }
+
+    fn tilt(&mut self, x: f32, y: f32) { self.inner.tilt(x, y); }
+
+    fn camera(&mut self, sensor: Sensor) { self.inner.camera(sensor); }
+
+    fn rom(&mut self) -> Option<&mut Vec<u8>> { self.inner.rom() }
 }
 
 impl MemoryController for Boot {
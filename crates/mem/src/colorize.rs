@@ -0,0 +1,177 @@
+use serde::{Deserialize, Serialize};
+
+use crate::boot::CGB_BOOT;
+
+/// title checksums of the Nintendo games with their own palettes
+const CHECKSUMS: usize = 0x06C7;
+const CHECKSUMS_LEN: usize = 0x4F;
+/// 4th title letter telling apart the games sharing a checksum, from index 0x41
+const LETTERS: usize = 0x0716;
+const AMBIGUOUS: usize = 0x41;
+/// palette entry of each game: combination in the low 5 bits, OBJ palettes selection in the high 3
+const ENTRIES: usize = 0x0733;
+const ENTRIES_LEN: usize = 0x5E;
+/// offsets in [`PALETTES`] of the 3 palettes of each combination
+const COMBINATIONS: usize = 0x0791;
+const PALETTES: usize = 0x07E8;
+/// keys held during the boot logo, and the palette entry they select
+const KEYS: usize = 0x08E4;
+const MANUAL: usize = 0x08F0;
+
+/// Button combinations held during the boot logo, in the boot ROM order, with the palette they give.
+pub const COMBOS: [&str; 12] = [
+    "Up (brown)",
+    "Up + A (red)",
+    "Up + B (dark brown)",
+    "Left (blue)",
+    "Left + A (dark blue)",
+    "Left + B (grayscale)",
+    "Down (pale yellow)",
+    "Down + A (orange)",
+    "Down + B (yellow)",
+    "Right (green)",
+    "Right + A (dark green)",
+    "Right + B (inverted)",
+];
+
+/// Background, OBJ0 and OBJ1 palettes, 4 RGB555 colors each.
+pub type Palettes = [[u16; 4]; 3];
+
+/// Palettes of a DMG game running on a CGB, picked like the CGB boot ROM does.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Default)]
+pub enum Colorization {
+    /// from the keys held at boot, or the title checksum of Nintendo games
+    #[default]
+    Auto,
+    /// one of the [`COMBOS`]
+    Manual(u8),
+    /// no colorization, the emulator DMG palette is used
+    Off,
+}
+
+
+impl Colorization {
+    /// Palettes for the cartridge `rom` (its first bank at least), `keys` being held when
+    /// the boot ends, one bit per [`shared::input::Keys`].
+    pub fn palettes(self, rom: &[u8], keys: u8) -> Option<Palettes> {
+        let entry = match self {
+            Colorization::Off => return None,
+            Colorization::Manual(n) => CGB_BOOT[MANUAL + n as usize % COMBOS.len()],
+            Colorization::Auto => manual(keys).unwrap_or_else(|| lookup(rom)),
+        };
+        Some(decode(entry))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Colorization::Auto => "Auto",
+            Colorization::Manual(n) => COMBOS[*n as usize % COMBOS.len()],
+            Colorization::Off => "Off",
+        }
+    }
+}
+
+/// The boot ROM compares the whole joypad state, the keys share their bits with [`shared::input::Keys`].
+fn manual(keys: u8) -> Option<u8> {
+    CGB_BOOT[KEYS..KEYS + COMBOS.len()].iter()
+        .position(|x| *x == keys)
+        .map(|n| CGB_BOOT[MANUAL + n])
+}
+
+fn lookup(rom: &[u8]) -> u8 {
+    let nintendo = match rom[0x14B] {
+        0x33 => rom[0x144..0x146] == *b"01",
+        licensee => licensee == 0x01,
+    };
+    if !nintendo { return CGB_BOOT[ENTRIES]; }
+    let checksum = rom[0x134..0x144].iter().fold(0u8, |acc, x| acc.wrapping_add(*x));
+    let index = CGB_BOOT[CHECKSUMS..CHECKSUMS + CHECKSUMS_LEN].iter()
+        .position(|x| *x == checksum)
+        .unwrap_or(0);
+    let index = if index < AMBIGUOUS {
+        index
+    } else {
+        (index..ENTRIES_LEN).step_by(CHECKSUMS_LEN - AMBIGUOUS)
+            .find(|x| CGB_BOOT[LETTERS + x - AMBIGUOUS] == rom[0x137])
+            .unwrap_or(0)
+    };
+    CGB_BOOT[ENTRIES + index]
+}
+
+fn decode(entry: u8) -> Palettes {
+    let combination = &CGB_BOOT[COMBINATIONS + (entry & 0x1F) as usize * 3..][..3];
+    let palette = |n: usize| {
+        let offset = PALETTES + combination[n] as usize;
+        [0, 1, 2, 3].map(|c| u16::from_le_bytes([CGB_BOOT[offset + c * 2], CGB_BOOT[offset + c * 2 + 1]]))
+    };
+    let flags = entry >> 5;
+    let obj0 = if flags & 1 != 0 { 0 } else { 2 };
+    let obj1 = match flags {
+        f if f & 4 != 0 => 1,
+        f if f & 2 != 0 => 0,
+        _ => 2,
+    };
+    [palette(2), palette(obj0), palette(obj1)]
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::input::Keys;
+
+    use super::*;
+
+    const GRAYSCALE: [u16; 4] = [0x7FFF, 0x5294, 0x294A, 0x0000];
+    const BROWN: [u16; 4] = [0x7FFF, 0x32BF, 0x00D0, 0x0000];
+    const GREEN: [u16; 4] = [0x7FFF, 0x1BEF, 0x6180, 0x0000];
+    const PINK: [u16; 4] = [0x7FFF, 0x421F, 0x1CF2, 0x0000];
+    const LIME: [u16; 4] = [0x7FFF, 0x1BEF, 0x0200, 0x0000];
+    const BLUE: [u16; 4] = [0x7FFF, 0x7E8C, 0x7C00, 0x0000];
+    const YELLOW: [u16; 4] = [0x7FFF, 0x03FF, 0x001F, 0x0000];
+
+    /// First bank of a game titled `title` from `licensee` (the new licensee code if 0x33).
+    fn rom(title: &[u8], licensee: u8) -> Vec<u8> {
+        let mut rom = vec![0; 0x4000];
+        rom[0x134..0x134 + title.len()].copy_from_slice(title);
+        rom[0x144..0x146].copy_from_slice(b"01");
+        rom[0x14B] = licensee;
+        rom
+    }
+
+    #[test]
+    fn manual_palettes() {
+        let rom = rom(b"TETRIS", 0x01);
+        let up = 1 << Keys::Up as u8;
+        let left_b = 1 << Keys::Left as u8 | 1 << Keys::B as u8;
+        assert_eq!(Colorization::Auto.palettes(&rom, up), Some([BROWN; 3]));
+        assert_eq!(Colorization::Auto.palettes(&rom, left_b), Some([GRAYSCALE; 3]));
+        assert_eq!(Colorization::Manual(5).palettes(&rom, up), Some([GRAYSCALE; 3]));
+        assert_eq!(Colorization::Manual(10).palettes(&rom, 0), Some([GREEN, PINK, PINK]));
+        assert_eq!(Colorization::Off.palettes(&rom, up), None);
+    }
+
+    #[test]
+    fn nintendo_titles() {
+        assert_eq!(Colorization::Auto.palettes(&rom(b"TETRIS", 0x01), 0), Some([YELLOW; 3]));
+        assert_eq!(Colorization::Auto.palettes(&rom(b"POKEMON RED", 0x33), 0), Some([PINK, LIME, PINK]));
+        assert_eq!(Colorization::Auto.palettes(&rom(b"POKEMON BLUE", 0x01), 0), Some([BLUE, PINK, BLUE]));
+        // other licensees and unknown titles get the dark green palettes
+        assert_eq!(Colorization::Auto.palettes(&rom(b"TETRIS", 0x08), 0), Some([GREEN, PINK, PINK]));
+        assert_eq!(Colorization::Auto.palettes(&rom(b"SNAKE", 0x01), 0), Colorization::Auto.palettes(&rom(b"", 0x08), 0));
+    }
+
+    #[test]
+    fn fourth_letter_disambiguates() {
+        // checksum shared by the 3 entries from 0x41
+        let shared = CGB_BOOT[CHECKSUMS + AMBIGUOUS];
+        let titled = |letter: u8| {
+            let mut title = [0; 4];
+            title[0] = shared.wrapping_sub(letter);
+            title[3] = letter;
+            lookup(&rom(&title, 0x01))
+        };
+        let entries = [b'B', b'U', b'R'].map(titled);
+        assert_eq!(entries, [0xA8, 0x60, 0x85]);
+        assert_eq!(titled(b'Z'), CGB_BOOT[ENTRIES]);
+    }
+}
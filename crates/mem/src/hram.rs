@@ -22,6 +22,12 @@ impl Mem for Hram {
     }
 }
 
+impl Default for Hram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Hram {
     pub fn new() -> Self {
         Hram {
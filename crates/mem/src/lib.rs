@@ -6,6 +6,7 @@ mod wram;
 mod boot;
 
 pub mod mbc;
+pub mod colorize;
 
 pub use hram::Hram;
 pub use oam::Oam;
@@ -0,0 +1,251 @@
+use serde::{Deserialize, Serialize};
+
+use shared::mem::*;
+use shared::rom::Rom;
+use shared::utils::camera::Sensor;
+
+use crate::mbc::{Mbc, MbcKind, MemoryController};
+
+const BANK_SIZE: usize = 0x4000;
+const RAM_SIZE: usize = 0x2000;
+
+const RAM_ENABLE: u16 = 0x0000;
+const RAM_ENABLE_END: u16 = 0x1FFF;
+const ROM_BANK: u16 = 0x2000;
+const ROM_BANK_END: u16 = 0x3FFF;
+const RAM_BANK: u16 = 0x4000;
+const RAM_BANK_END: u16 = 0x5FFF;
+
+/// Ram bank bit mapping the sensor registers at A000.
+const REGISTERS: usize = 0x10;
+const REGISTER_COUNT: usize = 0x36;
+
+const TRIGGER: usize = 0x0;
+const GAIN: usize = 0x1;
+const EXPOSURE_H: usize = 0x2;
+const EXPOSURE_L: usize = 0x3;
+const EDGE: usize = 0x4;
+const DITHER: usize = 0x6;
+
+/// Captured image, 16x14 tiles at 0100-0EFF of ram bank 0.
+const IMAGE: usize = 0x100;
+
+const EDGE_RATIOS: [f32; 8] = [0.5, 0.75, 1., 1.25, 2., 3., 4., 5.];
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Camera {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    enabled_ram: bool,
+    rom_bank: usize,
+    ram_bank: usize,
+    rom_banks: usize,
+    ram_banks: usize,
+    registers: Vec<u8>,
+    /// mbc ticks (128 clocks) left before the capture completes
+    busy: usize,
+    #[serde(default, skip)]
+    sensor: Sensor,
+}
+
+impl Camera {
+    fn start(&mut self) {
+        let exposure = (self.registers[EXPOSURE_H] as usize) << 8 | self.registers[EXPOSURE_L] as usize;
+        let n = self.registers[GAIN] & 0x80 != 0;
+        let cycles = 32446 + if n { 0 } else { 512 } + 16 * exposure;
+        self.busy = (cycles * 4 + 127) / 128;
+    }
+
+    /// Runs the sensor output through the chip pipeline: gain and exposure, edge
+    /// enhancement, inversion, then the 4x4 dithering matrix down to 2bpp tiles.
+    fn capture(&mut self) {
+        let regs = &self.registers;
+        let frame = self.sensor.capture();
+        let exposure = ((regs[EXPOSURE_H] as u16) << 8 | regs[EXPOSURE_L] as u16) as f32 / 0x1000 as f32;
+        let gain = 0.88 + 0.8 * (regs[GAIN] & 0x1F) as f32 / 31.;
+        let invert = regs[EDGE] & 0x8 != 0;
+        let ratio = EDGE_RATIOS[(regs[EDGE] >> 4) as usize & 0x7];
+        let (horizontal, vertical) = match (regs[GAIN] >> 5) & 0x3 {
+            0 => (false, false),
+            1 => (true, false),
+            2 => (false, true),
+            _ => (true, true),
+        };
+        let pixel = |x: isize, y: isize| {
+            let x = x.clamp(0, Sensor::WIDTH as isize - 1) as usize;
+            let y = y.clamp(0, Sensor::HEIGHT as isize - 1) as usize;
+            frame[y * Sensor::WIDTH + x] as f32 * gain * exposure
+        };
+        let mut image = vec![0u8; 16 * 14 * 16];
+        for y in 0..Sensor::HEIGHT {
+            for x in 0..Sensor::WIDTH {
+                let (xi, yi) = (x as isize, y as isize);
+                let mut v = pixel(xi, yi);
+                if horizontal { v += ratio * (2. * pixel(xi, yi) - pixel(xi - 1, yi) - pixel(xi + 1, yi)); }
+                if vertical { v += ratio * (2. * pixel(xi, yi) - pixel(xi, yi - 1) - pixel(xi, yi + 1)); }
+                let v = v.clamp(0., 255.) as u8;
+                let v = if invert { 255 - v } else { v };
+                let m = DITHER + ((y & 3) * 4 + (x & 3)) * 3;
+                let color = if v < regs[m] { 3 } else if v < regs[m + 1] { 2 } else if v < regs[m + 2] { 1 } else { 0 };
+                let tile = (y / 8) * 16 + x / 8;
+                let line = tile * 16 + (y & 7) * 2;
+                let bit = 7 - (x & 7);
+                image[line] |= (color & 1) << bit;
+                image[line + 1] |= (color >> 1) << bit;
+            }
+        }
+        self.ram[IMAGE..IMAGE + image.len()].copy_from_slice(&image);
+        self.registers[TRIGGER] &= !1;
+    }
+}
+
+impl Mem for Camera {
+    fn read(&self, addr: u16, absolute: u16) -> u8 {
+        match absolute {
+            ROM..=ROM_END => self.rom[addr as usize],
+            SROM..=SROM_END => {
+                let addr = addr as usize + self.rom_bank * BANK_SIZE;
+                if addr >= self.rom.len() {
+                    log::error!("out of bounds cartridge rom read at {absolute}");
+                    0xFF
+                } else { self.rom[addr] }
+            }
+            SRAM..=SRAM_END if self.ram_bank & REGISTERS != 0 => {
+                if addr & 0x7F == 0 { self.registers[TRIGGER] } else { 0x00 }
+            }
+            SRAM..=SRAM_END if self.busy != 0 => 0x00,
+            SRAM..=SRAM_END => {
+                let addr = addr as usize + self.ram_bank * RAM_SIZE;
+                if addr >= self.ram.len() {
+                    log::error!("out of bounds cartridge ram read at {absolute}");
+                    0xFF
+                } else { self.ram[addr] }
+            }
+            _ => unreachable!()
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8, absolute: u16) {
+        match absolute {
+            RAM_ENABLE..=RAM_ENABLE_END => self.enabled_ram = (value & 0xF) == 0xA,
+            ROM_BANK..=ROM_BANK_END => self.rom_bank = (value as usize & 0x3F) & self.rom_banks,
+            RAM_BANK..=RAM_BANK_END => self.ram_bank = value as usize & (REGISTERS | self.ram_banks),
+            SRAM..=SRAM_END if self.ram_bank & REGISTERS != 0 => {
+                match addr as usize & 0x7F {
+                    TRIGGER => {
+                        let start = value & 1 != 0 && self.busy == 0;
+                        self.registers[TRIGGER] = value & 0x7;
+                        if start { self.start(); }
+                    }
+                    n if n < REGISTER_COUNT => self.registers[n] = value,
+                    _ => {}
+                }
+            }
+            SRAM..=SRAM_END if self.enabled_ram && self.busy == 0 => {
+                let addr = addr as usize + self.ram_bank * RAM_SIZE;
+                if let Some(x) = self.ram.get_mut(addr) { *x = value; }
+            }
+            _ => {}
+        }
+    }
+
+    fn get_range(&self, st: u16, _: u16) -> Vec<u8> {
+        match st {
+            ROM => self.rom[..BANK_SIZE].to_vec(),
+            SROM => {
+                let st = BANK_SIZE * self.rom_bank;
+                self.rom[st..(st + BANK_SIZE)].to_vec()
+            }
+            _ => vec![]
+        }
+    }
+}
+
+impl MemoryController for Camera {
+    fn new(rom: &Rom, mut ram: Vec<u8>) -> Self {
+        ram.resize(rom.header.ram_size.size().max(RAM_SIZE), 0xFF);
+        Self {
+            rom: rom.raw(),
+            ram,
+            enabled_ram: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            rom_banks: rom.header.rom_size.mask(),
+            ram_banks: rom.header.ram_size.mask(),
+            registers: vec![0; REGISTER_COUNT],
+            busy: 0,
+            sensor: Sensor::default(),
+        }
+    }
+
+    fn ram_dump(&self) -> Vec<u8> { self.ram.clone() }
+
+    fn rom_bank(&self) -> usize { self.rom_bank }
+    fn ram_bank(&self) -> usize { self.ram_bank }
+}
+
+impl Mbc for Camera {
+    fn serialize(&self) -> Option<MbcKind> {
+        Some(MbcKind::CAMERA(bincode::serialize(self).expect("failed to serialize")))
+    }
+
+    fn rom(&mut self) -> Option<&mut Vec<u8>> { Some(&mut self.rom) }
+
+    fn deserialize(raw: &[u8]) -> Box<dyn Mbc> {
+        Box::new(bincode::deserialize::<Self>(raw).expect("deserialization failed"))
+    }
+
+    fn tick(&mut self) {
+        if self.busy == 0 { return; }
+        self.busy -= 1;
+        if self.busy == 0 { self.capture(); }
+    }
+
+    fn camera(&mut self, sensor: Sensor) { self.sensor = sensor; }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mbc::tests::rom;
+
+    use super::*;
+
+    fn camera() -> Camera {
+        Camera::new(&rom(64, 0, 0xFC, 0x04), vec![])
+    }
+
+    #[test]
+    fn switches_rom_banks() {
+        let mut camera = camera();
+        assert_eq!(camera.read(0, SROM), 1);
+        camera.write(0, 0x25, ROM_BANK);
+        assert_eq!(camera.read(0, SROM), 0x25);
+        camera.write(0, 0, ROM_BANK);
+        assert_eq!(camera.read(0, SROM), 0);
+    }
+
+    #[test]
+    fn switches_ram_banks() {
+        let mut camera = camera();
+        camera.write(0, 0x0A, RAM_ENABLE);
+        camera.write(0, 0x02, RAM_BANK);
+        camera.write(0x10, 0x42, SRAM);
+        assert_eq!(camera.read(0x10, SRAM), 0x42);
+        camera.write(0, 0x00, RAM_BANK);
+        assert_ne!(camera.read(0x10, SRAM), 0x42);
+    }
+
+    #[test]
+    fn maps_the_sensor_registers() {
+        let mut camera = camera();
+        camera.write(0, 0x0A, RAM_ENABLE);
+        camera.write(0, REGISTERS as u8, RAM_BANK);
+        camera.write(TRIGGER as u16, 0x01, SRAM);
+        assert_eq!(camera.read(0, SRAM) & 1, 1);
+        camera.write(0, 0x00, RAM_BANK);
+        assert_eq!(camera.read(IMAGE as u16, SRAM), 0x00, "ram reads 0 while capturing");
+        while camera.busy != 0 { camera.tick(); }
+        camera.write(0, REGISTERS as u8, RAM_BANK);
+        assert_eq!(camera.read(0, SRAM) & 1, 0);
+    }
+}
@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+
+use shared::mem::*;
+use shared::rom::Rom;
+
+use crate::mbc::{Mbc, MbcKind, MemoryController};
+
+const BANK_SIZE: usize = 0x4000;
+const RAM_SIZE: usize = 0x2000;
+
+const MODE: u16 = 0x0000;
+const MODE_END: u16 = 0x1FFF;
+const ROM_BANK: u16 = 0x2000;
+const ROM_BANK_END: u16 = 0x3FFF;
+const RAM_BANK: u16 = 0x4000;
+const RAM_BANK_END: u16 = 0x5FFF;
+
+/// Value written to the mode register to map the infrared port over the cartridge ram.
+pub(crate) const IR_MODE: u8 = 0xE;
+/// Infrared port read with no incoming light.
+pub(crate) const IR_DARK: u8 = 0xC0;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Huc1 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    ir: bool,
+    rom_bank: usize,
+    ram_bank: usize,
+    rom_banks: usize,
+    ram_banks: usize,
+}
+
+impl Mem for Huc1 {
+    fn read(&self, addr: u16, absolute: u16) -> u8 {
+        match absolute {
+            ROM..=ROM_END => self.rom[addr as usize],
+            SROM..=SROM_END => {
+                let addr = addr as usize + self.rom_bank * BANK_SIZE;
+                if addr >= self.rom.len() {
+                    log::error!("out of bounds cartridge rom read at {absolute}");
+                    0xFF
+                } else { self.rom[addr] }
+            }
+            SRAM..=SRAM_END if self.ir => IR_DARK,
+            SRAM..=SRAM_END => {
+                let addr = addr as usize + self.ram_bank * RAM_SIZE;
+                if addr >= self.ram.len() {
+                    log::error!("out of bounds cartridge ram read at {absolute}");
+                    0xFF
+                } else { self.ram[addr] }
+            }
+            _ => unreachable!()
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8, absolute: u16) {
+        match absolute {
+            MODE..=MODE_END => self.ir = (value & 0xF) == IR_MODE,
+            ROM_BANK..=ROM_BANK_END => self.rom_bank = (value as usize & 0x3F) & self.rom_banks,
+            RAM_BANK..=RAM_BANK_END => self.ram_bank = (value as usize & 0x3) & self.ram_banks,
+            SRAM..=SRAM_END if self.ir => {}
+            SRAM..=SRAM_END => {
+                let addr = addr as usize + self.ram_bank * RAM_SIZE;
+                if let Some(x) = self.ram.get_mut(addr) { *x = value; }
+            }
+            _ => {}
+        }
+    }
+
+    fn get_range(&self, st: u16, _: u16) -> Vec<u8> {
+        match st {
+            ROM => self.rom[..BANK_SIZE].to_vec(),
+            SROM => {
+                let st = BANK_SIZE * self.rom_bank;
+                self.rom[st..(st + BANK_SIZE)].to_vec()
+            }
+            SRAM => {
+                let st = RAM_SIZE * self.ram_bank;
+                self.ram[st..(st + RAM_SIZE).min(self.ram.len())].to_vec()
+            }
+            _ => vec![]
+        }
+    }
+}
+
+impl MemoryController for Huc1 {
+    fn new(rom: &Rom, mut ram: Vec<u8>) -> Self {
+        ram.resize(rom.header.ram_size.size(), 0xFF);
+        Self {
+            rom: rom.raw(),
+            ram,
+            ir: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            rom_banks: rom.header.rom_size.mask(),
+            ram_banks: rom.header.ram_size.mask(),
+        }
+    }
+
+    fn ram_dump(&self) -> Vec<u8> { self.ram.clone() }
+
+    fn rom_bank(&self) -> usize { self.rom_bank }
+    fn ram_bank(&self) -> usize { self.ram_bank }
+}
+
+impl Mbc for Huc1 {
+    fn serialize(&self) -> Option<MbcKind> {
+        Some(MbcKind::HUC1(bincode::serialize(self).expect("failed to serialize")))
+    }
+
+    fn rom(&mut self) -> Option<&mut Vec<u8>> { Some(&mut self.rom) }
+
+    fn deserialize(raw: &[u8]) -> Box<dyn Mbc> {
+        Box::new(bincode::deserialize::<Self>(raw).expect("deserialization failed"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mbc::tests::rom;
+
+    use super::*;
+
+    fn huc1() -> Huc1 {
+        Huc1::new(&rom(8, 0, 0xFF, 3), vec![])
+    }
+
+    #[test]
+    fn switches_banks() {
+        let mut mbc = huc1();
+        assert_eq!(mbc.read(0, SROM), 1);
+        mbc.write(0, 0x03, ROM_BANK);
+        assert_eq!(mbc.read(0, SROM), 3);
+        // only the existing banks are addressed
+        mbc.write(0, 0x09, ROM_BANK);
+        assert_eq!(mbc.read(0, SROM), 1);
+        mbc.write(0, 0x2, RAM_BANK);
+        mbc.write(0, 0x42, SRAM);
+        mbc.write(0, 0x0, RAM_BANK);
+        assert_eq!(mbc.read(0, SRAM), 0xFF);
+        mbc.write(0, 0x2, RAM_BANK);
+        assert_eq!(mbc.read(0, SRAM), 0x42);
+    }
+
+    #[test]
+    fn maps_the_infrared_port() {
+        let mut mbc = huc1();
+        mbc.write(0, IR_MODE, MODE);
+        mbc.write(0, 0x42, SRAM);
+        assert_eq!(mbc.read(0, SRAM), IR_DARK);
+        mbc.write(0, 0x0, MODE);
+        assert_eq!(mbc.read(0, SRAM), 0xFF);
+    }
+}
@@ -0,0 +1,282 @@
+use serde::{Deserialize, Serialize};
+
+use shared::mem::*;
+use shared::rom::Rom;
+use shared::utils::rtc::{MinuteClock, Rtc};
+
+use crate::mbc::{clocked, Mbc, MbcKind, MemoryController};
+use crate::mbc::huc1::{IR_DARK, IR_MODE};
+
+const BANK_SIZE: usize = 0x4000;
+const RAM_SIZE: usize = 0x2000;
+
+const MODE: u16 = 0x0000;
+const MODE_END: u16 = 0x1FFF;
+const ROM_BANK: u16 = 0x2000;
+const ROM_BANK_END: u16 = 0x3FFF;
+const RAM_BANK: u16 = 0x4000;
+const RAM_BANK_END: u16 = 0x5FFF;
+
+const RAM_READ: u8 = 0x0;
+const RAM_WRITE: u8 = 0xA;
+const COMMAND: u8 = 0xB;
+const RESPONSE: u8 = 0xC;
+const SEMAPHORE: u8 = 0xD;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Huc3 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    mode: u8,
+    rom_bank: usize,
+    ram_bank: usize,
+    rom_banks: usize,
+    ram_banks: usize,
+    rtc: MinuteClock,
+    /// nibble addressed clock memory, 0-2 are the minutes, 3-5 the days
+    memory: Vec<u8>,
+    index: u8,
+    flags: u8,
+    response: u8,
+    /// the clock only counts emulated time, see [`Mbc::emulated_time`]
+    emulated: bool,
+}
+
+/// [`Huc3`] as serialized in states from before [`MinuteClock`].
+#[derive(Deserialize)]
+struct Legacy {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    mode: u8,
+    rom_bank: usize,
+    ram_bank: usize,
+    rom_banks: usize,
+    ram_banks: usize,
+    rtc: Rtc,
+    memory: Vec<u8>,
+    index: u8,
+    flags: u8,
+    response: u8,
+    emulated: bool,
+}
+
+impl From<Legacy> for Huc3 {
+    fn from(old: Legacy) -> Self {
+        let Legacy { rom, ram, mode, rom_bank, ram_bank, rom_banks, ram_banks, rtc, memory, index, flags, response, emulated } = old;
+        Self { rom, ram, mode, rom_bank, ram_bank, rom_banks, ram_banks, rtc: rtc.into(), memory, index, flags, response, emulated }
+    }
+}
+
+impl Huc3 {
+    fn command(&mut self, value: u8) {
+        let arg = value & 0xF;
+        match value >> 4 & 0x7 {
+            0x1 => {
+                self.response = 0x10 | self.memory[self.index as usize];
+                self.index = self.index.wrapping_add(1);
+            }
+            0x2 => self.memory[self.index as usize] = arg,
+            0x3 => {
+                self.memory[self.index as usize] = arg;
+                self.index = self.index.wrapping_add(1);
+            }
+            0x4 => self.index = (self.index & 0xF0) | arg,
+            0x5 => self.index = (self.index & 0x0F) | arg << 4,
+            0x6 => self.flags = arg,
+            n => log::debug!("unknown HuC3 command {n:#X}"),
+        }
+    }
+
+    fn execute(&mut self) {
+        match self.flags {
+            0x0 => {
+                let (minutes, days) = self.rtc.minutes();
+                for i in 0..3 {
+                    self.memory[i] = (minutes >> (4 * i)) as u8 & 0xF;
+                    self.memory[i + 3] = (days >> (4 * i)) as u8 & 0xF;
+                }
+            }
+            0x1 => {
+                let memory = &self.memory;
+                let nibbles = |st: usize| (0..3).fold(0, |acc, i| acc | (memory[st + i] as u16) << (4 * i));
+                let (minutes, days) = (nibbles(0), nibbles(3));
+                self.rtc.set(minutes, days);
+            }
+            0x2 => self.response = 0x61,
+            n => log::debug!("unknown HuC3 extended command {n:#X}"),
+        }
+    }
+}
+
+impl Mem for Huc3 {
+    fn read(&self, addr: u16, absolute: u16) -> u8 {
+        match absolute {
+            ROM..=ROM_END => self.rom[addr as usize],
+            SROM..=SROM_END => {
+                let addr = addr as usize + self.rom_bank * BANK_SIZE;
+                if addr >= self.rom.len() {
+                    log::error!("out of bounds cartridge rom read at {absolute}");
+                    0xFF
+                } else { self.rom[addr] }
+            }
+            SRAM..=SRAM_END => match self.mode {
+                RAM_READ | RAM_WRITE => {
+                    let addr = addr as usize + self.ram_bank * RAM_SIZE;
+                    if addr >= self.ram.len() {
+                        log::error!("out of bounds cartridge ram read at {absolute}");
+                        0xFF
+                    } else { self.ram[addr] }
+                }
+                RESPONSE => 0x80 | self.response,
+                SEMAPHORE => 0x1,
+                IR_MODE => IR_DARK,
+                _ => 0xFF
+            }
+            _ => unreachable!()
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8, absolute: u16) {
+        match absolute {
+            MODE..=MODE_END => self.mode = value & 0xF,
+            ROM_BANK..=ROM_BANK_END => self.rom_bank = (value as usize & 0x7F) & self.rom_banks,
+            RAM_BANK..=RAM_BANK_END => self.ram_bank = (value as usize & 0x3) & self.ram_banks,
+            SRAM..=SRAM_END => match self.mode {
+                RAM_WRITE => {
+                    let addr = addr as usize + self.ram_bank * RAM_SIZE;
+                    if let Some(x) = self.ram.get_mut(addr) { *x = value; }
+                }
+                COMMAND => self.command(value),
+                SEMAPHORE if value & 1 == 0 => self.execute(),
+                _ => {}
+            }
+            _ => {}
+        }
+    }
+
+    fn get_range(&self, st: u16, _: u16) -> Vec<u8> {
+        match st {
+            ROM => self.rom[..BANK_SIZE].to_vec(),
+            SROM => {
+                let st = BANK_SIZE * self.rom_bank;
+                self.rom[st..(st + BANK_SIZE)].to_vec()
+            }
+            SRAM => {
+                let st = RAM_SIZE * self.ram_bank;
+                self.ram[st..(st + RAM_SIZE).min(self.ram.len())].to_vec()
+            }
+            _ => vec![]
+        }
+    }
+}
+
+impl MemoryController for Huc3 {
+    fn new(rom: &Rom, mut ram: Vec<u8>) -> Self {
+        let size = rom.header.ram_size.size();
+        let raw = if ram.len() > size { ram.split_off(size) } else { vec![] };
+        ram.resize(size, 0xFF);
+        Self {
+            rom: rom.raw(),
+            ram,
+            mode: RAM_READ,
+            rom_bank: 1,
+            ram_bank: 0,
+            rom_banks: rom.header.rom_size.mask(),
+            ram_banks: rom.header.ram_size.mask(),
+            rtc: MinuteClock::deserialize(raw).unwrap_or_default(),
+            memory: vec![0; 256],
+            index: 0,
+            flags: 0,
+            response: 0,
+            emulated: false,
+        }
+    }
+
+    fn ram_dump(&self) -> Vec<u8> {
+        let mut dump = self.ram.clone();
+        dump.append(&mut self.rtc.serialize());
+        dump
+    }
+
+    fn rom_bank(&self) -> usize { self.rom_bank }
+    fn ram_bank(&self) -> usize { self.ram_bank }
+}
+
+impl Mbc for Huc3 {
+    fn serialize(&self) -> Option<MbcKind> {
+        Some(MbcKind::HUC3(bincode::serialize(self).expect("failed to serialize")))
+    }
+
+    fn rom(&mut self) -> Option<&mut Vec<u8>> { Some(&mut self.rom) }
+
+    fn deserialize(raw: &[u8]) -> Box<dyn Mbc> {
+        let huc3 = bincode::deserialize::<Self>(raw).unwrap_or_else(|_| clocked::<Legacy>(raw).into());
+        Box::new(huc3)
+    }
+
+    fn tick(&mut self) { self.rtc.tick(); }
+
+    fn emulated_time(&mut self, emulated: bool) -> bool {
+        if emulated { self.rtc.ignore_host(); }
+        std::mem::replace(&mut self.emulated, emulated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mbc::tests::rom;
+
+    use super::*;
+
+    fn huc3() -> Huc3 {
+        Huc3::new(&rom(8, 0, 0xFE, 3), vec![])
+    }
+
+    fn command(mbc: &mut Huc3, command: u8) {
+        mbc.write(0, COMMAND, MODE);
+        mbc.write(0, command, SRAM);
+    }
+
+    fn execute(mbc: &mut Huc3, flags: u8) {
+        command(mbc, 0x60 | flags);
+        mbc.write(0, SEMAPHORE, MODE);
+        mbc.write(0, 0, SRAM);
+    }
+
+    #[test]
+    fn switches_banks() {
+        let mut mbc = huc3();
+        mbc.write(0, 0x05, ROM_BANK);
+        assert_eq!(mbc.read(0, SROM), 5);
+        mbc.write(0, 0x1, RAM_BANK);
+        mbc.write(0, RAM_WRITE, MODE);
+        mbc.write(0, 0x42, SRAM);
+        mbc.write(0, RAM_READ, MODE);
+        assert_eq!(mbc.read(0, SRAM), 0x42);
+        mbc.write(0, 0x0, RAM_BANK);
+        assert_eq!(mbc.read(0, SRAM), 0xFF);
+        // ram is read only outside of the write mode
+        mbc.write(0, 0x43, SRAM);
+        assert_eq!(mbc.read(0, SRAM), 0xFF);
+    }
+
+    #[test]
+    fn clock_counts_days_on_12_bits() {
+        let mut mbc = huc3();
+        mbc.emulated_time(true);
+        command(&mut mbc, 0x40);
+        command(&mut mbc, 0x50);
+        // 1439 minutes, day 0xABC
+        for nibble in [0xF, 0x9, 0x5, 0xC, 0xB, 0xA] { command(&mut mbc, 0x30 | nibble); }
+        execute(&mut mbc, 1);
+        for _ in 0..60 * 32768 { mbc.tick(); }
+        execute(&mut mbc, 0);
+        command(&mut mbc, 0x40);
+        let nibbles = (0..6).map(|_| {
+            command(&mut mbc, 0x10);
+            mbc.write(0, RESPONSE, MODE);
+            mbc.read(0, SRAM) & 0xF
+        }).collect::<Vec<_>>();
+        assert_eq!(nibbles, [0, 0, 0, 0xD, 0xB, 0xA]);
+    }
+}
@@ -48,6 +48,8 @@ impl Mbc for Mbc0 {
         Some(MbcKind::MBC0(bincode::serialize(self).expect("failed to serialize")))
     }
 
+    fn rom(&mut self) -> Option<&mut Vec<u8>> { Some(&mut self.rom) }
+
     fn deserialize(raw: &[u8]) -> Box<dyn Mbc> {
         Box::new(bincode::deserialize::<Self>(raw).expect("deserialization failed"))
     }
@@ -61,13 +61,12 @@ impl Mem for Mbc1 {
             ROM_BANK..=ROM_BANK_END => self.rom_reg_1 = value & 0x1F,
             RAM_BANK..=RAM_BANK_END => self.rom_reg_2 = value & 0x3,
             BANK_MODE..=BANK_MODE_END => self.bank_mode = value != 0,
-            SRAM..=SRAM_END => {
-                if self.ram_enable {
+            SRAM..=SRAM_END
+                if self.ram_enable => {
                     let bank = if self.bank_mode { self.rom_reg_2 as usize & self.ram_banks } else { 0 };
                     let addr = addr as usize | (bank << 13);
                     self.ram[addr] = value;
                 }
-            }
             _ => {}
         }
     }
@@ -137,6 +136,8 @@ impl Mbc for Mbc1 {
         Some(MbcKind::MBC1(bincode::serialize(self).expect("failed to serialize")))
     }
 
+    fn rom(&mut self) -> Option<&mut Vec<u8>> { Some(&mut self.rom) }
+
     fn deserialize(raw: &[u8]) -> Box<dyn Mbc> {
         Box::new(bincode::deserialize::<Self>(raw).expect("deserialization failed"))
     }
@@ -49,12 +49,11 @@ impl Mem for Mbc2 {
                 let bank = value & 0xF;
                 self.rom_bank = if bank == 0 { 1 } else { bank as usize };
             },
-            SRAM..=SRAM_END => {
-                if self.ram_enabled {
+            SRAM..=SRAM_END
+                if self.ram_enabled => {
                     let addr = addr as usize & 0x1FF;
                     self.ram[addr] = value & 0xF;
                 }
-            }
             _ => {}
         }
     }
@@ -105,6 +104,8 @@ impl Mbc for Mbc2 {
         Some(MbcKind::MBC2(bincode::serialize(self).expect("failed to serialize")))
     }
 
+    fn rom(&mut self) -> Option<&mut Vec<u8>> { Some(&mut self.rom) }
+
     fn deserialize(raw: &[u8]) -> Box<dyn Mbc> {
         Box::new(bincode::deserialize::<Self>(raw).expect("deserialization failed"))
     }
@@ -4,7 +4,7 @@ use shared::mem::*;
 use shared::rom::Rom;
 use shared::utils::rtc::Rtc;
 
-use crate::mbc::{Mbc, MbcKind, MemoryController};
+use crate::mbc::{clocked, Mbc, MbcKind, MemoryController};
 
 const BANK_SIZE: usize = 0x4000;
 const RAM_SIZE: usize = 0x2000;
@@ -28,6 +28,8 @@ pub struct Mbc3 {
     rom_banks: usize,
     rtc: Rtc,
     latch: bool,
+    /// the clock only counts emulated time, see [`Mbc::emulated_time`]
+    emulated: bool,
 }
 
 impl Mem for Mbc3 {
@@ -75,7 +77,7 @@ impl Mem for Mbc3 {
                 match self.ram_bank {
                     n @ 0x8..=0xC => self.rtc.write(n as u8, value),
                     n => {
-                        let addr = addr as usize + n as usize * RAM_SIZE;
+                        let addr = addr as usize + n * RAM_SIZE;
                         self.ram[addr] = value;
                     }
                 }
@@ -113,6 +115,7 @@ impl MemoryController for Mbc3 {
             ram_bank: 0,
             rom_banks: rom.header.rom_size.banks(),
             latch: true,
+            emulated: false,
         }
     }
 
@@ -131,9 +134,16 @@ impl Mbc for Mbc3 {
         Some(MbcKind::MBC3(bincode::serialize(self).expect("failed to serialize")))
     }
 
+    fn rom(&mut self) -> Option<&mut Vec<u8>> { Some(&mut self.rom) }
+
     fn deserialize(raw: &[u8]) -> Box<dyn Mbc> {
-        Box::new(bincode::deserialize::<Self>(raw).expect("deserialization failed"))
+        Box::new(clocked::<Self>(raw))
     }
 
     fn tick(&mut self) { self.rtc.tick(false); }
+
+    fn emulated_time(&mut self, emulated: bool) -> bool {
+        if emulated { self.rtc.ignore_host(); }
+        std::mem::replace(&mut self.emulated, emulated)
+    }
 }
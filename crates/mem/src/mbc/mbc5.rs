@@ -53,7 +53,7 @@ impl Mem for Mbc5 {
             ROM_BANK_H..=ROM_BANK_H_END => self.rom_bank = (((value as usize & 0x1) << 8) | (self.rom_bank & 0xFF)) & self.rom_banks,
             RAM_BANK..=RAM_BANK_END => self.ram_bank = (value as usize & 0xF) & self.ram_banks,
             SRAM..=SRAM_END => {
-                let addr = addr as usize + self.ram_bank as usize * RAM_SIZE;
+                let addr = addr as usize + self.ram_bank * RAM_SIZE;
                 self.ram[addr] = value;
             }
             _ => {}
@@ -100,6 +100,8 @@ impl Mbc for Mbc5 {
         Some(MbcKind::MBC5(bincode::serialize(self).expect("failed to serialize")))
     }
 
+    fn rom(&mut self) -> Option<&mut Vec<u8>> { Some(&mut self.rom) }
+
     fn deserialize(raw: &[u8]) -> Box<dyn Mbc> {
         Box::new(bincode::deserialize::<Self>(raw).expect("deserialization failed"))
     }
@@ -0,0 +1,336 @@
+use serde::{Deserialize, Serialize};
+
+use shared::mem::*;
+use shared::rom::Rom;
+
+use crate::mbc::{Mbc, MbcKind, MemoryController};
+
+const BANK_SIZE: usize = 0x4000;
+
+const RAM_ENABLE: u16 = 0x0000;
+const RAM_ENABLE_END: u16 = 0x1FFF;
+const ROM_BANK: u16 = 0x2000;
+const ROM_BANK_END: u16 = 0x3FFF;
+const RAM_ENABLE_2: u16 = 0x4000;
+const RAM_ENABLE_2_END: u16 = 0x5FFF;
+const REGISTERS_END: u16 = 0xAFFF;
+
+/// Accelerometer reading when flat, and the offset for a 1g tilt.
+const CENTER: f32 = 0x81D0 as f32;
+const GRAVITY: f32 = 0x70 as f32;
+
+const WORDS: usize = 128;
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+enum Command {
+    /// Waiting for the start bit.
+    Idle,
+    /// Receiving the opcode and address, 10 bits.
+    Header,
+    Read(u8, u8),
+    Write(u8),
+    WriteAll,
+    /// Command done, until CS goes low.
+    Done,
+}
+
+/// 93LC56 serial EEPROM, 128 16-bit words.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Eeprom {
+    words: Vec<u16>,
+    cs: bool,
+    clk: bool,
+    di: bool,
+    out: bool,
+    shift: u32,
+    bits: u8,
+    command: Command,
+    writable: bool,
+}
+
+impl Eeprom {
+    fn new(raw: &[u8]) -> Self {
+        let words = if raw.len() == 2 * WORDS {
+            raw.chunks(2).map(|x| u16::from_le_bytes([x[0], x[1]])).collect()
+        } else { vec![0xFFFF; WORDS] };
+        Self { words, cs: false, clk: false, di: false, out: true, shift: 0, bits: 0, command: Command::Idle, writable: false }
+    }
+
+    fn dump(&self) -> Vec<u8> {
+        self.words.iter().flat_map(|x| x.to_le_bytes()).collect()
+    }
+
+    fn read(&self) -> u8 {
+        (self.cs as u8) << 7 | (self.clk as u8) << 6 | (self.di as u8) << 1 | self.out as u8
+    }
+
+    fn write(&mut self, value: u8) {
+        let rising = value & 0x40 != 0 && !self.clk;
+        self.cs = value & 0x80 != 0;
+        self.clk = value & 0x40 != 0;
+        self.di = value & 0x2 != 0;
+        if !self.cs {
+            self.command = Command::Idle;
+            self.out = true;
+            return;
+        }
+        if rising { self.clock(); }
+    }
+
+    fn receive(&mut self) -> u32 {
+        self.shift = (self.shift << 1) | self.di as u32;
+        self.bits += 1;
+        self.shift
+    }
+
+    fn clock(&mut self) {
+        match self.command {
+            Command::Idle if self.di => {
+                self.command = Command::Header;
+                self.shift = 0;
+                self.bits = 0;
+            }
+            Command::Idle | Command::Done => {}
+            Command::Header => {
+                let header = self.receive();
+                if self.bits < 10 { return; }
+                let addr = (header & 0x7F) as u8;
+                self.shift = 0;
+                self.bits = 0;
+                self.command = match (header >> 8) & 0x3 {
+                    0b10 => {
+                        self.out = false;
+                        Command::Read(addr, 0)
+                    }
+                    0b01 => Command::Write(addr),
+                    0b11 => {
+                        if self.writable { self.words[addr as usize] = 0xFFFF; }
+                        Command::Done
+                    }
+                    _ => match (header >> 6) & 0x3 {
+                        0b11 => { self.writable = true; Command::Done }
+                        0b00 => { self.writable = false; Command::Done }
+                        0b10 => {
+                            if self.writable { self.words.fill(0xFFFF); }
+                            Command::Done
+                        }
+                        _ => Command::WriteAll
+                    }
+                };
+            }
+            Command::Read(addr, bit) => {
+                self.out = (self.words[addr as usize] >> (15 - bit)) & 1 != 0;
+                self.command = if bit == 15 {
+                    Command::Read((addr + 1) % WORDS as u8, 0)
+                } else { Command::Read(addr, bit + 1) };
+            }
+            Command::Write(addr) => {
+                let data = self.receive();
+                if self.bits < 16 { return; }
+                if self.writable { self.words[addr as usize] = data as u16; }
+                self.out = true;
+                self.command = Command::Done;
+            }
+            Command::WriteAll => {
+                let data = self.receive();
+                if self.bits < 16 { return; }
+                if self.writable { self.words.fill(data as u16); }
+                self.out = true;
+                self.command = Command::Done;
+            }
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Mbc7 {
+    rom: Vec<u8>,
+    eeprom: Eeprom,
+    enabled_ram: bool,
+    enabled_ram_2: bool,
+    rom_bank: usize,
+    rom_banks: usize,
+    tilt: (f32, f32),
+    latch: (u16, u16),
+    erased: bool,
+}
+
+impl Mbc7 {
+    fn enabled(&self) -> bool { self.enabled_ram && self.enabled_ram_2 }
+}
+
+impl Mem for Mbc7 {
+    fn read(&self, addr: u16, absolute: u16) -> u8 {
+        match absolute {
+            ROM..=ROM_END => self.rom[addr as usize],
+            SROM..=SROM_END => {
+                let addr = addr as usize + self.rom_bank * BANK_SIZE;
+                if addr >= self.rom.len() {
+                    log::error!("out of bounds cartridge rom read at {absolute}");
+                    0xFF
+                } else { self.rom[addr] }
+            }
+            SRAM..=REGISTERS_END if self.enabled() => {
+                let [xl, xh] = self.latch.0.to_le_bytes();
+                let [yl, yh] = self.latch.1.to_le_bytes();
+                match (addr >> 4) & 0xF {
+                    0x2 => xl,
+                    0x3 => xh,
+                    0x4 => yl,
+                    0x5 => yh,
+                    0x6 => 0x00,
+                    0x8 => self.eeprom.read(),
+                    _ => 0xFF
+                }
+            }
+            _ => 0xFF
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8, absolute: u16) {
+        match absolute {
+            RAM_ENABLE..=RAM_ENABLE_END => self.enabled_ram = value == 0xA,
+            ROM_BANK..=ROM_BANK_END => self.rom_bank = value as usize & self.rom_banks,
+            RAM_ENABLE_2..=RAM_ENABLE_2_END => self.enabled_ram_2 = value == 0x40,
+            SRAM..=REGISTERS_END if self.enabled() => {
+                match (addr >> 4) & 0xF {
+                    0x0 if value == 0x55 => {
+                        self.erased = true;
+                        self.latch = (0x8000, 0x8000);
+                    }
+                    0x1 if value == 0xAA && self.erased => {
+                        self.erased = false;
+                        let (x, y) = self.tilt;
+                        self.latch = ((CENTER + GRAVITY * x) as u16, (CENTER + GRAVITY * y) as u16);
+                    }
+                    0x8 => self.eeprom.write(value),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn get_range(&self, st: u16, _: u16) -> Vec<u8> {
+        match st {
+            ROM => self.rom[..BANK_SIZE].to_vec(),
+            SROM => {
+                let st = BANK_SIZE * self.rom_bank;
+                self.rom[st..(st + BANK_SIZE)].to_vec()
+            }
+            _ => vec![]
+        }
+    }
+}
+
+impl MemoryController for Mbc7 {
+    fn new(rom: &Rom, ram: Vec<u8>) -> Self {
+        Self {
+            rom: rom.raw(),
+            eeprom: Eeprom::new(&ram),
+            enabled_ram: false,
+            enabled_ram_2: false,
+            rom_bank: 1,
+            rom_banks: rom.header.rom_size.mask(),
+            tilt: (0., 0.),
+            latch: (0x8000, 0x8000),
+            erased: false,
+        }
+    }
+
+    fn ram_dump(&self) -> Vec<u8> { self.eeprom.dump() }
+
+    fn rom_bank(&self) -> usize { self.rom_bank }
+}
+
+impl Mbc for Mbc7 {
+    fn serialize(&self) -> Option<MbcKind> {
+        Some(MbcKind::MBC7(bincode::serialize(self).expect("failed to serialize")))
+    }
+
+    fn rom(&mut self) -> Option<&mut Vec<u8>> { Some(&mut self.rom) }
+
+    fn deserialize(raw: &[u8]) -> Box<dyn Mbc> {
+        Box::new(bincode::deserialize::<Self>(raw).expect("deserialization failed"))
+    }
+
+    fn tilt(&mut self, x: f32, y: f32) { self.tilt = (x.clamp(-1., 1.), y.clamp(-1., 1.)); }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mbc::tests::rom;
+
+    use super::*;
+
+    const EEPROM: u16 = 0x80;
+    const CS: u8 = 0x80;
+    const CLK: u8 = 0x40;
+    const DI: u8 = 0x2;
+
+    fn mbc7() -> Mbc7 {
+        let mut mbc = Mbc7::new(&rom(8, 0, 0x22, 0), vec![]);
+        mbc.write(0, 0xA, RAM_ENABLE);
+        mbc.write(0, 0x40, RAM_ENABLE_2);
+        mbc
+    }
+
+    /// Clocks `bits` into the eeprom, most significant first, returns the DO line after each edge.
+    fn send(mbc: &mut Mbc7, value: u32, bits: u8) -> u32 {
+        (0..bits).rev().fold(0, |out, n| {
+            let di = if value >> n & 1 != 0 { DI } else { 0 };
+            mbc.write(EEPROM, CS | di, SRAM + EEPROM);
+            mbc.write(EEPROM, CS | CLK | di, SRAM + EEPROM);
+            out << 1 | (mbc.read(EEPROM, SRAM + EEPROM) & 1) as u32
+        })
+    }
+
+    /// Start bit, 2 bits opcode and 8 bits address, then `data` until CS goes low.
+    fn command(mbc: &mut Mbc7, opcode: u32, addr: u32, data: (u32, u8)) -> u32 {
+        send(mbc, 1 << 10 | opcode << 8 | addr, 11);
+        let out = send(mbc, data.0, data.1);
+        mbc.write(EEPROM, 0, SRAM + EEPROM);
+        out
+    }
+
+    #[test]
+    fn switches_banks() {
+        let mut mbc = mbc7();
+        assert_eq!(mbc.read(0, SROM), 1);
+        mbc.write(0, 0x05, ROM_BANK);
+        assert_eq!(mbc.read(0, SROM), 5);
+        assert_eq!(mbc.rom_bank(), 5);
+        mbc.write(0, 0x0D, ROM_BANK);
+        assert_eq!(mbc.read(0, SROM), 5);
+    }
+
+    #[test]
+    fn latches_the_accelerometer() {
+        let mut mbc = mbc7();
+        mbc.tilt(1., -2.);
+        mbc.write(0x00, 0x55, SRAM);
+        mbc.write(0x10, 0xAA, SRAM + 0x10);
+        let read = |mbc: &Mbc7, reg: u16| mbc.read(reg, SRAM + reg);
+        assert_eq!(u16::from_le_bytes([read(&mbc, 0x20), read(&mbc, 0x30)]), 0x81D0 + 0x70);
+        assert_eq!(u16::from_le_bytes([read(&mbc, 0x40), read(&mbc, 0x50)]), 0x81D0 - 0x70);
+        // registers are unmapped while ram is disabled
+        mbc.write(0, 0, RAM_ENABLE_2);
+        assert_eq!(read(&mbc, 0x20), 0xFF);
+    }
+
+    #[test]
+    fn reads_and_writes_the_eeprom() {
+        let mut mbc = mbc7();
+        // write protected by default
+        command(&mut mbc, 0b01, 0x12, (0xBEEF, 16));
+        assert_eq!(mbc.ram_dump()[0x24..0x26], [0xFF, 0xFF]);
+        command(&mut mbc, 0b00, 0xC0, (0, 0));
+        command(&mut mbc, 0b01, 0x12, (0xBEEF, 16));
+        assert_eq!(mbc.ram_dump()[0x24..0x26], [0xEF, 0xBE]);
+        assert_eq!(command(&mut mbc, 0b10, 0x12, (0, 16)), 0xBEEF);
+        command(&mut mbc, 0b11, 0x12, (0, 0));
+        assert_eq!(command(&mut mbc, 0b10, 0x12, (0, 16)), 0xFFFF);
+        let dump = mbc.ram_dump();
+        assert_eq!(Mbc7::new(&rom(8, 0, 0x22, 0), dump.clone()).ram_dump(), dump);
+    }
+}
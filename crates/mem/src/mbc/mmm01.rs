@@ -0,0 +1,211 @@
+use serde::{Deserialize, Serialize};
+
+use shared::mem::*;
+use shared::rom::Rom;
+
+use crate::mbc::{Mbc, MbcKind, MemoryController};
+
+const BANK_SIZE: usize = 0x4000;
+const RAM_SIZE: usize = 0x2000;
+
+const RAM_ENABLE: u16 = 0x0000;
+const RAM_ENABLE_END: u16 = 0x1FFF;
+const ROM_BANK: u16 = 0x2000;
+const ROM_BANK_END: u16 = 0x3FFF;
+const RAM_BANK: u16 = 0x4000;
+const RAM_BANK_END: u16 = 0x5FFF;
+const MODE: u16 = 0x6000;
+const MODE_END: u16 = 0x7FFF;
+
+/// Multicart mapper: boots unmapped on the menu (last 32KiB of the rom), which selects
+/// a game by setting the outer bank bits then locks them, leaving an MBC1-like mapper.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Mmm01 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    enabled_ram: bool,
+    /// outer bank bits are frozen, the selected game is mapped
+    locked: bool,
+    rom_low: u8,
+    rom_mid: u8,
+    rom_high: u8,
+    /// bits of `rom_low` frozen when locked
+    rom_mask: u8,
+    ram_low: u8,
+    ram_high: u8,
+    /// bits of `ram_low` frozen when locked
+    ram_mask: u8,
+    mode: bool,
+    mode_locked: bool,
+    multiplex: bool,
+}
+
+impl Mmm01 {
+    fn rom_banks(&self) -> usize { (self.rom.len() / BANK_SIZE).max(2) }
+
+    /// Rom bank at 0000-3FFF, at 4000-7FFF and ram bank.
+    fn banks(&self) -> (usize, usize, usize) {
+        let banks = self.rom_banks();
+        if !self.locked { return (banks - 2, banks - 1, 0); }
+        // in multiplex mode the ram bank register drives the rom mid bits (and conversely)
+        let (mid, ram) = if self.multiplex {
+            (self.ram_low, self.rom_mid | self.ram_high << 2)
+        } else { (self.rom_mid, self.ram_low | self.ram_high << 2) };
+        let mid0 = if self.multiplex && self.mode { 0 } else { mid };
+        let high = (self.rom_high as usize) << 7;
+        let rom0 = (self.rom_low & self.rom_mask << 1) as usize | (mid0 as usize) << 5 | high;
+        let rom = self.rom_low as usize | (mid as usize) << 5 | high;
+        (rom0 % banks, rom % banks, ram as usize)
+    }
+}
+
+impl Mem for Mmm01 {
+    fn read(&self, addr: u16, absolute: u16) -> u8 {
+        let (rom0, rom, ram) = self.banks();
+        match absolute {
+            ROM..=ROM_END => self.rom[addr as usize + rom0 * BANK_SIZE],
+            SROM..=SROM_END => self.rom[addr as usize + rom * BANK_SIZE],
+            SRAM..=SRAM_END if self.enabled_ram && !self.ram.is_empty() => {
+                let addr = (addr as usize + ram * RAM_SIZE) % self.ram.len();
+                self.ram[addr]
+            }
+            SRAM..=SRAM_END => 0xFF,
+            _ => unreachable!()
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8, absolute: u16) {
+        match absolute {
+            RAM_ENABLE..=RAM_ENABLE_END => {
+                self.enabled_ram = (value & 0xF) == 0xA;
+                if !self.locked {
+                    self.ram_mask = (value >> 4) & 0x3;
+                    self.locked = value & 0x40 != 0;
+                    if self.locked { log::info!("mmm01 mapped rom banks {:?}", self.banks()); }
+                }
+            }
+            ROM_BANK..=ROM_BANK_END => {
+                if !self.locked { self.rom_mid = (value >> 5) & 0x3; }
+                let frozen = self.rom_mask << 1;
+                self.rom_low = (self.rom_low & frozen) | (value & !frozen & 0x1F);
+            }
+            RAM_BANK..=RAM_BANK_END => {
+                self.ram_low = (self.ram_low & self.ram_mask) | (value & !self.ram_mask & 0x3);
+                if !self.locked {
+                    self.ram_high = (value >> 2) & 0x3;
+                    self.rom_high = (value >> 4) & 0x3;
+                    self.mode_locked = value & 0x40 != 0;
+                }
+            }
+            MODE..=MODE_END => {
+                if !self.mode_locked { self.mode = value & 1 != 0; }
+                if !self.locked {
+                    self.rom_mask = (value >> 2) & 0xF;
+                    self.multiplex = value & 0x40 != 0;
+                }
+            }
+            SRAM..=SRAM_END if self.enabled_ram && !self.ram.is_empty() => {
+                let (_, _, ram) = self.banks();
+                let addr = (addr as usize + ram * RAM_SIZE) % self.ram.len();
+                self.ram[addr] = value;
+            }
+            _ => {}
+        }
+    }
+
+    fn get_range(&self, st: u16, _: u16) -> Vec<u8> {
+        let (rom0, rom, _) = self.banks();
+        match st {
+            ROM => self.rom[rom0 * BANK_SIZE..(rom0 + 1) * BANK_SIZE].to_vec(),
+            SROM => self.rom[rom * BANK_SIZE..(rom + 1) * BANK_SIZE].to_vec(),
+            _ => vec![]
+        }
+    }
+}
+
+impl MemoryController for Mmm01 {
+    fn new(rom: &Rom, mut ram: Vec<u8>) -> Self {
+        ram.resize(rom.header.ram_size.size(), 0xFF);
+        let mut raw = rom.raw();
+        raw.resize(((raw.len() + BANK_SIZE - 1) / BANK_SIZE * BANK_SIZE).max(2 * BANK_SIZE), 0xFF);
+        Self {
+            rom: raw,
+            ram,
+            enabled_ram: false,
+            locked: false,
+            rom_low: 0,
+            rom_mid: 0,
+            rom_high: 0,
+            rom_mask: 0,
+            ram_low: 0,
+            ram_high: 0,
+            ram_mask: 0,
+            mode: false,
+            mode_locked: false,
+            multiplex: false,
+        }
+    }
+
+    fn ram_dump(&self) -> Vec<u8> { self.ram.clone() }
+
+    fn rom_bank(&self) -> usize { self.banks().1 }
+    fn ram_bank(&self) -> usize { self.banks().2 }
+}
+
+impl Mbc for Mmm01 {
+    fn serialize(&self) -> Option<MbcKind> {
+        Some(MbcKind::MMM01(bincode::serialize(self).expect("failed to serialize")))
+    }
+
+    fn rom(&mut self) -> Option<&mut Vec<u8>> { Some(&mut self.rom) }
+
+    fn deserialize(raw: &[u8]) -> Box<dyn Mbc> {
+        Box::new(bincode::deserialize::<Self>(raw).expect("deserialization failed"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mbc::tests::rom;
+
+    use super::*;
+
+    /// The menu in the last 32KiB declares the MMM01 cartridge.
+    fn multicart() -> Mmm01 {
+        Mmm01::new(&rom(8, 6, 0x0B, 0), vec![])
+    }
+
+    fn mapped(mbc: &Mmm01) -> (u8, u8) {
+        (mbc.read(0, ROM), mbc.read(0, SROM))
+    }
+
+    #[test]
+    fn boots_on_the_menu() {
+        assert_eq!(mapped(&multicart()), (6, 7));
+    }
+
+    #[test]
+    fn locks_the_selected_game() {
+        let mut mbc = multicart();
+        mbc.write(0, 0x02, ROM_BANK);
+        mbc.write(0, 0x40, RAM_ENABLE);
+        assert_eq!(mapped(&mbc), (0, 2));
+        mbc.write(0, 0x03, ROM_BANK);
+        assert_eq!(mapped(&mbc), (0, 3));
+        // no MBC1 translation of bank 0 to 1
+        mbc.write(0, 0x00, ROM_BANK);
+        assert_eq!(mapped(&mbc), (0, 0));
+    }
+
+    #[test]
+    fn masks_the_outer_bits() {
+        let mut mbc = multicart();
+        // bits 1-2 of the rom bank are frozen on the game selected by the menu
+        mbc.write(0, 0x04, ROM_BANK);
+        mbc.write(0, 0x3 << 2, MODE);
+        mbc.write(0, 0x40, RAM_ENABLE);
+        assert_eq!(mapped(&mbc), (4, 4));
+        mbc.write(0, 0x01, ROM_BANK);
+        assert_eq!(mapped(&mbc), (4, 5));
+    }
+}
@@ -3,20 +3,30 @@ use std::path::PathBuf;
 
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+use camera::Camera;
+use huc1::Huc1;
+use huc3::Huc3;
 use mbc0::Mbc0;
 use mbc1::Mbc1;
 use mbc2::Mbc2;
 use mbc3::Mbc3;
 use mbc5::Mbc5;
-use shared::{mem::*, rom::{Mbc as Mbcs, Rom}};
+use mbc7::Mbc7;
+use mmm01::Mmm01;
+use shared::{cheats::Code, mem::*, rom::{Mbc as Mbcs, Rom}, utils::camera::Sensor};
 
 use crate::boot::Boot;
 
+pub mod camera;
+pub mod huc1;
+pub mod huc3;
 pub mod mbc0;
 pub mod mbc1;
 pub mod mbc2;
 pub mod mbc3;
 pub mod mbc5;
+pub mod mbc7;
+pub mod mmm01;
 
 pub trait MemoryController {
     fn new(rom: &Rom, ram: Vec<u8>) -> Self where Self: Sized;
@@ -32,6 +42,15 @@ pub(crate) trait Mbc: MemoryController + Mem {
     fn deserialize(raw: &[u8]) -> Box<dyn Mbc> where Self: Sized;
     fn unmap(&mut self) -> Box<dyn Mbc> { unreachable!() }
     fn tick(&mut self) {}
+    /// Whether the cartridge clock (if any) only counts emulated time, instead of catching up
+    /// with the host clock when loaded. Returns the previous mode.
+    fn emulated_time(&mut self, _emulated: bool) -> bool { false }
+    /// Sets the cartridge accelerometer (if any), in g along each axis.
+    fn tilt(&mut self, _x: f32, _y: f32) {}
+    /// Sets the image source of the cartridge camera (if any).
+    fn camera(&mut self, _sensor: Sensor) {}
+    /// Cartridge rom content (if any), moved out while taking snapshots.
+    fn rom(&mut self) -> Option<&mut Vec<u8>> { None }
 }
 
 #[derive(Default, Serialize, Deserialize)]
@@ -55,6 +74,8 @@ impl MemoryController for Unplugged {
 pub struct Controller {
     pub sav: Option<PathBuf>,
     inner: Box<dyn Mbc>,
+    #[serde(default, skip)]
+    genie: Vec<Code>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -65,6 +86,11 @@ pub(crate) enum MbcKind {
     MBC2(Vec<u8>),
     MBC3(Vec<u8>),
     MBC5(Vec<u8>),
+    MBC7(Vec<u8>),
+    HUC1(Vec<u8>),
+    HUC3(Vec<u8>),
+    MMM01(Vec<u8>),
+    CAMERA(Vec<u8>),
 }
 
 impl Serialize for Box<dyn Mbc> {
@@ -84,11 +110,23 @@ impl<'de> Deserialize<'de> for Box<dyn Mbc> {
                 Some(MbcKind::MBC2(m)) => <Mbc2 as Mbc>::deserialize(&m),
                 Some(MbcKind::MBC3(m)) => <Mbc3 as Mbc>::deserialize(&m),
                 Some(MbcKind::MBC5(m)) => <Mbc5 as Mbc>::deserialize(&m),
+                Some(MbcKind::MBC7(m)) => <Mbc7 as Mbc>::deserialize(&m),
+                Some(MbcKind::HUC1(m)) => <Huc1 as Mbc>::deserialize(&m),
+                Some(MbcKind::HUC3(m)) => <Huc3 as Mbc>::deserialize(&m),
+                Some(MbcKind::MMM01(m)) => <Mmm01 as Mbc>::deserialize(&m),
+                Some(MbcKind::CAMERA(m)) => <Camera as Mbc>::deserialize(&m),
             }
         })
     }
 }
 
+/// Clock cartridges end with their clock mode, which states saved before it was kept lack.
+fn clocked<T: serde::de::DeserializeOwned>(raw: &[u8]) -> T {
+    bincode::deserialize(raw)
+        .or_else(|_| bincode::deserialize(&[raw, &[0]].concat()))
+        .expect("deserialization failed")
+}
+
 impl Default for Controller {
     fn default() -> Self { Controller::unplugged() }
 }
@@ -97,7 +135,7 @@ impl Controller {
     pub fn new(rom: &Rom, cgb: bool) -> Self {
         let (sav, ram) = if rom.header.cartridge.capabilities().save() {
             let sav = rom.location.clone().join(&rom.filename);
-            let ram = if let Some(mut f) = std::fs::File::open(&sav.with_extension("sav")).ok() {
+            let ram = if let Ok(mut f) = std::fs::File::open(sav.with_extension("sav")) {
                 let mut v = Vec::with_capacity(rom.header.ram_size.size());
                 f.read_to_end(&mut v).expect("failed to read save");
                 v
@@ -106,21 +144,70 @@ impl Controller {
         } else {
             (None, vec![0xAF; rom.header.ram_size.size()])
         };
+        Self::with_ram(rom, cgb, ram).save_to(sav)
+    }
+
+    /// Cartridge with the given ram content (as returned by [`Controller::ram_dump`]), never saved to disk.
+    pub fn with_ram(rom: &Rom, cgb: bool, ram: Vec<u8>) -> Self {
         let inner: Box<dyn Mbc> = match rom.header.cartridge.mbc() {
             Mbcs::MBC0 => Box::new(Boot::new(cgb, Mbc0::new(rom, ram))),
             Mbcs::MBC1 => Box::new(Boot::new(cgb, Mbc1::new(rom, ram))),
             Mbcs::MBC2 => Box::new(Boot::new(cgb, Mbc2::new(rom, ram))),
             Mbcs::MBC3 => Box::new(Boot::new(cgb, Mbc3::new(rom, ram))),
             Mbcs::MBC5 => Box::new(Boot::new(cgb, Mbc5::new(rom, ram))),
+            Mbcs::MBC7 => Box::new(Boot::new(cgb, Mbc7::new(rom, ram))),
+            Mbcs::HUC1 => Box::new(Boot::new(cgb, Huc1::new(rom, ram))),
+            Mbcs::HUC3 => Box::new(Boot::new(cgb, Huc3::new(rom, ram))),
+            Mbcs::MMM01 => Box::new(Boot::new(cgb, Mmm01::new(rom, ram))),
+            Mbcs::CAMERA => Box::new(Boot::new(cgb, Camera::new(rom, ram))),
             Mbcs::Unknown => unimplemented!()
         };
 
         Self {
-            sav,
+            sav: None,
             inner,
+            genie: vec![],
         }
     }
 
+    fn save_to(mut self, sav: Option<PathBuf>) -> Self {
+        self.sav = sav;
+        self
+    }
+
+    pub fn ram_dump(&self) -> Vec<u8> { self.inner.ram_dump() }
+
+    pub fn emulated_time(&mut self, emulated: bool) -> bool { self.inner.emulated_time(emulated) }
+
+    /// Stops writing the battery save to disk, returns where it went.
+    pub fn detach_save(&mut self) -> Option<PathBuf> { self.sav.take() }
+
+    pub fn attach_save(&mut self, sav: Option<PathBuf>) { self.sav = sav; }
+
+    pub fn set_tilt(&mut self, x: f32, y: f32) { self.inner.tilt(x, y); }
+
+    pub fn set_camera(&mut self, sensor: Sensor) { self.inner.camera(sensor); }
+
+    /// Moves the rom out of the cartridge, leaving it empty until [`Controller::put_rom`].
+    pub fn take_rom(&mut self) -> Vec<u8> { self.inner.rom().map(std::mem::take).unwrap_or_default() }
+
+    pub fn put_rom(&mut self, rom: Vec<u8>) {
+        if let Some(x) = self.inner.rom() { *x = rom; }
+    }
+
+    /// Game Genie codes patching rom reads, other codes are ignored.
+    pub fn set_genie(&mut self, codes: impl IntoIterator<Item=Code>) {
+        self.genie = codes.into_iter().filter(|x| matches!(x, Code::Genie { .. })).collect();
+    }
+
+    fn patch(&self, absolute: u16, v: u8) -> u8 {
+        if absolute > SROM_END { return v; }
+        self.genie.iter().find_map(|code| match *code {
+            Code::Genie { addr, value, compare } if addr == absolute && compare.unwrap_or(v) == v => Some(value),
+            _ => None
+        }).unwrap_or(v)
+    }
+
     pub fn skip_boot(mut self) -> Self {
         self.post();
         self
@@ -145,7 +232,7 @@ impl Controller {
     }
 
     pub fn unplugged() -> Self {
-        Self { sav: None, inner: Box::new(Unplugged {}) }
+        Self { sav: None, inner: Box::new(Unplugged {}), genie: vec![] }
     }
 }
 
@@ -163,7 +250,7 @@ impl MBCController for Controller {
 
 impl Mem for Controller {
     fn read(&self, addr: u16, absolute: u16) -> u8 {
-        self.inner.read(addr, absolute)
+        self.patch(absolute, self.inner.read(addr, absolute))
     }
 
     fn value(&self, addr: u16, absolute: u16) -> u8 {
@@ -182,3 +269,21 @@ impl Mem for Controller {
     fn unlock(&mut self, access: Source) { self.inner.unlock(access) }
 }
 
+#[cfg(test)]
+pub(crate) mod tests {
+    use shared::rom::Rom;
+
+    const BANK_SIZE: usize = 0x4000;
+
+    /// Rom of `banks` 16KiB banks (a power of 2) each starting with its number, the header
+    /// in bank `at` declares `cartridge` and the `ram` size code.
+    pub(crate) fn rom(banks: usize, at: usize, cartridge: u8, ram: u8) -> Rom {
+        let mut raw = vec![0; banks * BANK_SIZE];
+        raw.chunks_mut(BANK_SIZE).enumerate().for_each(|(n, bank)| bank[0] = n as u8);
+        let header = at * BANK_SIZE + 0x147;
+        raw[header] = cartridge;
+        raw[header + 1] = banks.trailing_zeros() as u8 - 1;
+        raw[header + 2] = ram;
+        Rom::from_bytes("test".to_string(), Default::default(), raw).unwrap()
+    }
+}
@@ -63,12 +63,17 @@ impl Mem for Oam {
     }
 
     fn get_range(&self, _st: u16, _len: u16) -> Vec<u8> {
-        self.sprites.as_ref().iter().map(|x| [x.x, x.y, x.tile, x.flags])
-            .flatten()
+        self.sprites.as_ref().iter().flat_map(|x| [x.x, x.y, x.tile, x.flags])
             .collect()
     }
 }
 
+impl Default for Oam {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Oam {
     pub fn new() -> Self {
         Self { sprites: [Sprite::default(); 40] }
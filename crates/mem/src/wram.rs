@@ -88,7 +88,7 @@ impl Mem for Storage {
         match self {
             Storage::Dmg(v) => v[addr],
             Storage::Cgb(c) => {
-                match addr as usize {
+                match addr {
                     0..=INCLUSIVE_BANK_SIZE => c.bank(0)[addr],
                     BANK_SIZE..=INCLUSIVE_WRAM_SIZE => c.bank(c.selected)[addr - BANK_SIZE],
                     _ => unreachable!()
@@ -102,7 +102,7 @@ impl Mem for Storage {
         match self {
             Storage::Dmg(v) => v[addr] = value,
             Storage::Cgb(c) => {
-                match addr as usize {
+                match addr {
                     0..=INCLUSIVE_BANK_SIZE => c.mut_bank(0)[addr] = value,
                     BANK_SIZE..=INCLUSIVE_WRAM_SIZE => c.mut_bank(c.selected)[addr - BANK_SIZE] = value,
                     _ => unreachable!()
@@ -158,6 +158,16 @@ impl Wram {
         }
     }
 
+    /// Writes to the switchable half at `addr` in `bank` (clamped like SVBK) instead of the selected one.
+    pub fn write_bank(&mut self, bank: u8, addr: u16, value: u8) {
+        match &mut self.storage {
+            Storage::Cgb(c) if addr as usize >= BANK_SIZE => {
+                c.mut_bank(bank.clamp(1, 7) as usize)[addr as usize - BANK_SIZE] = value;
+            }
+            storage => storage.write(addr, value, addr),
+        }
+    }
+
     pub fn switch_bank(&mut self, new: u8) {
         if let Storage::Cgb(c) = &mut self.storage {
             c.selected = new.clamp(1, 7) as usize;
@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+use shared::utils::serde_arrays;
+
+pub(crate) const BUF_W: usize = 8;
+pub(crate) const BUF_SZ: usize = 64;
+
+#[derive(Serialize, Deserialize)]
+pub struct ColorBuffer {
+    #[serde(with = "serde_arrays")]
+    pub(crate) pixels: [Option<[u8; 4]>; BUF_SZ],
+}
+
+impl ColorBuffer {
+    pub fn new() -> Self { Self { pixels: [None; BUF_SZ] } }
+
+    pub fn color(&mut self, x: usize, y: usize, color: [u8; 3]) {
+        let [r, g, b] = color;
+        self.pixels[x + y * BUF_W] = Some([r, g, b, 255]);
+    }
+}
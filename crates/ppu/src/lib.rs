@@ -1,8 +1,5 @@
 extern crate core;
 
-use std::collections::HashMap;
-use std::marker::PhantomData;
-
 use serde::{Deserializer, Serializer};
 
 pub use dma::Dma;
@@ -10,20 +7,35 @@ pub use hdma::Hdma;
 use lcd::Lcd;
 use mem::{Oam, Vram};
 pub use ppu::Ppu;
-pub use render::{PpuAccess, VramAccess, VramViewer};
-use shared::emulator::Emulator;
+#[cfg(feature = "ui")]
+pub use render::VramViewer;
 use shared::io::{IO, IODevice, IORegs};
 use shared::mem::{IOBus, Lock};
 use shared::serde::{Deserialize, Serialize};
 
 use crate::ppu::states::{HState, Mode, OamState, TransferState, VState};
 
+#[cfg(feature = "ui")]
 mod render;
+mod buffer;
 mod ppu;
 
 mod dma;
 mod hdma;
 
+pub trait VramAccess {
+    fn vram(&self) -> &Vram;
+    fn vram_mut(&mut self) -> &mut Vram;
+
+    fn oam(&self) -> &Oam;
+    fn oam_mut(&mut self) -> &mut Oam;
+}
+
+pub trait PpuAccess: VramAccess {
+    fn ppu(&self) -> &Ppu;
+    fn ppu_mut(&mut self) -> &mut Ppu;
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Controller {
     ppu: Ppu,
@@ -57,6 +69,12 @@ impl<'de> Deserialize<'de> for ppu::PpuState {
     }
 }
 
+impl Default for Controller {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Controller {
     pub fn new() -> Self {
         Self {
@@ -71,6 +89,11 @@ impl Controller {
         self.ppu.release();
     }
 
+    /// See [`mem::colorize::Colorization`].
+    pub fn colorize(&mut self, palettes: Option<mem::colorize::Palettes>) {
+        self.ppu.cram.colorize(palettes);
+    }
+
     pub fn inner(&self) -> &Ppu { &self.ppu }
     pub fn inner_mut(&mut self) -> &mut Ppu { &mut self.ppu }
 
@@ -1,13 +1,15 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
 use lcd::{Lcd, LCD};
 use mem::{oam::{Oam, Sprite}, Vram};
 use pixel::Pixel;
-use shared::{egui::epaint::ahash::{HashMap, HashMapExt}, io::{IO, IODevice, IORegs, LCDC}};
+use shared::io::{IO, IODevice, IORegs, LCDC};
 use shared::mem::*;
 use states::*;
 
-use super::render::ColorBuffer;
+use super::buffer::ColorBuffer;
 
 mod fetcher;
 mod cram;
@@ -65,6 +67,12 @@ pub struct Ppu {
     pub(crate) draw_cache: HashMap<usize, ColorBuffer>,
 }
 
+impl Default for Ppu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Ppu {
     pub fn new() -> Self {
         let sprites = Vec::with_capacity(10);
@@ -168,7 +176,10 @@ impl Ppu {
     }
 
     pub fn set(&mut self, lcd: &mut Lcd, io: &mut IORegs, lx: usize, ly: usize, pixel: Pixel) {
-        lcd.set(lx, ly, self.cram.color(pixel, io));
+        match lcd.sgb() {
+            Some(_) => lcd.shade(lx, ly, self.cram.shade(pixel, io)),
+            None => lcd.set(lx, ly, self.cram.color(pixel, io)),
+        }
     }
 
     pub(crate) fn default_state() -> PpuState { VState::new().boxed() }
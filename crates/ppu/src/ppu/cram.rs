@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use mem::colorize::Palettes;
 use shared::io::{CGB_MODE, IO, IODevice, IORegs};
 use shared::mem::IOBus;
 use shared::utils::serde_arrays;
@@ -77,6 +78,16 @@ impl CRAM {
         }
     }
 
+    /// Shade (0-3) of a pixel in DMG mode, after BGP/OBP.
+    pub fn shade(&self, pixel: super::Pixel, io: &IORegs) -> u8 {
+        let palette = match (pixel.sprite, pixel.attrs.obp1()) {
+            (true, true) => io.io(IO::OBP1),
+            (true, false) => io.io(IO::OBP0),
+            (false, _) => io.io(IO::BGP),
+        };
+        (palette.read() >> (2 * pixel.color)) & 3
+    }
+
     fn palette_from_boot(&mut self) {
         for c in 0..4 {
             let rgb555 = self.bgdata[c * 2] as u16 | (self.bgdata[c * 2 + 1] as u16) << 8;
@@ -94,6 +105,19 @@ impl CRAM {
         }
         self.posted_in_cgb = true;
     }
+
+    /// Palettes of a DMG game in compatibility mode, replacing the ones left by the boot ROM,
+    /// the emulator DMG palette is used when `None`.
+    pub fn colorize(&mut self, palettes: Option<Palettes>) {
+        let Some([bg, obj0, obj1]) = palettes else {
+            self.posted_in_cgb = false;
+            return;
+        };
+        self.dmgbgpal = bg.map(Rgb555::to_bytes);
+        self.dmgobj0pal = obj0.map(Rgb555::to_bytes);
+        self.dmgobj1pal = obj1.map(Rgb555::to_bytes);
+        self.posted_in_cgb = true;
+    }
 }
 
 impl IODevice for CRAM {
@@ -113,12 +137,11 @@ impl IODevice for CRAM {
                 if inc { ocps.direct_write(0x80 | ((addr + 1) & 0x3F)); }
                 self.objdata[addr as usize] = v;
             }
-            IO::POST => {
+            IO::POST
                 // self.dump();
-                if bus.io(IO::CGB).value() != 0 {
+                if bus.io(IO::CGB).value() != 0 => {
                     self.palette_from_boot();
                 }
-            }
             _ => {}
         }
     }
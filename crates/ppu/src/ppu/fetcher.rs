@@ -93,7 +93,7 @@ impl Fetcher {
             (false, n, _) => (n, ly.wrapping_add(scy) as u16 / 8, (self.x + scx / 8) & 0x1F), //bg tile (LCDC.3)
             (true, _, n) => (n, ppu.win.y as u16 / 8, ppu.win.x) //window tile (LCDC.6)
         };
-        let addr = 0x1800 | (offset as u16) << 10 | y << 5 as u16 | x as u16;
+        let addr = 0x1800 | (offset as u16) << 10 | y << 5_u16 | x as u16;
         self.tile = ppu.vram().get(Source::Ppu, |vram| vram.read_bank(addr, 0)) as u16;
         if io.io(IO::KEY0).value() & CGB_MODE != 0 {
             self.attrs = Attributes(ppu.vram().get(Source::Ppu, |v| v.read_bank(addr, 1)));
@@ -113,7 +113,7 @@ impl Fetcher {
         } & wrap) as u16;
         self.y = if self.attrs.flip_y() { wrap as u16 - y } else { y } as u8;
         self.addr = (match self.mode {
-            Mode::Bg | Mode::Window => !(!ppu.lcdc.relative_addr() || (tile & 0x80) != 0) as u16,
+            Mode::Bg | Mode::Window => (ppu.lcdc.relative_addr() && (tile & 0x80) == 0) as u16,
             Mode::Sprite(..) => 0
         } << 12) | (tile << 4) | ((self.y as u16) << 1);
     }
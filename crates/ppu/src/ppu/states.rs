@@ -24,7 +24,6 @@ pub(crate) trait State: Debug {
     fn mode(&self) -> Mode;
     fn tick(&mut self, ppu: &mut Ppu, io: &mut IORegs, lcd: &mut Lcd) -> Option<Box<dyn State>>;
     fn boxed(self) -> Box<dyn State> where Self: 'static + Sized { Box::new(self) }
-    fn name(&self) -> String { format!("{:?}", self.mode()) }
     fn first_tick(&self) -> bool { false }
     fn raw(&self) -> Vec<u8> { vec![] }
 }
@@ -109,7 +108,7 @@ impl TransferState {
     }
 
     pub(crate) fn from_raw(raw: Vec<u8>) -> Box<dyn State> {
-        let t: Self = bincode::deserialize(&*raw).unwrap();
+        let t: Self = bincode::deserialize(&raw).unwrap();
         Box::new(t)
     }
 }
@@ -120,14 +119,13 @@ impl State for TransferState {
     fn tick(&mut self, ppu: &mut Ppu, io: &mut IORegs, lcd: &mut Lcd) -> Option<Box<dyn State>> {
         self.dots += 1;
         let wx = io.io(IO::WX).value();
-        if ppu.win.scan_enabled && wx <= self.lx + 7 {
-            if ppu.lcdc.win_enable() && !ppu.win.enabled {
+        if ppu.win.scan_enabled && wx <= self.lx + 7
+            && ppu.lcdc.win_enable() && !ppu.win.enabled {
                 self.scx = 7u8.saturating_sub(wx);
                 self.fetcher.set_mode(fetcher::Mode::Window);
                 self.bg.clear();
                 ppu.win.enabled = true;
             }
-        }
         self.fetcher.tick(ppu, io, &mut self.bg, &mut self.oam);
         if self.scx == 0 && ppu.lcdc.obj_enable() && self.bg.enabled() && !self.fetcher.fetching_sprite() {
             let idx = if let Some(sprite) = self.sprite { sprite + 1 } else { 0 };
@@ -135,7 +133,7 @@ impl State for TransferState {
                 let idx = ppu.sprites[i];
                 let sprite = ppu.oam_mut().get_mut(Source::Ppu)
                     .map(|x| x.sprites[idx])
-                    .unwrap_or_else(|| Sprite::unavailable());
+                    .unwrap_or_else(Sprite::unavailable);
                 if sprite.screen_x() == self.lx || (sprite.x != 0 && sprite.x < 8 && self.lx == 0) {
                     self.sprite = Some(i);
                     self.fetcher.set_mode(fetcher::Mode::Sprite(sprite, i as u8));
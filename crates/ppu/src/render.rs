@@ -1,13 +1,15 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
 
 use shared::egui::*;
 use shared::egui::epaint::ImageDelta;
+use shared::emulator::Emulator;
 use shared::Events;
-use shared::events::WindowEvent;
 use shared::utils::serde_arrays;
+use shared::events::WindowEvent;
 use shared::widgets::tabs;
 
-use crate::ppu::Ppu;
+use crate::buffer::{BUF_SZ, BUF_W, ColorBuffer};
 
 use super::*;
 
@@ -37,19 +39,6 @@ impl<E> Default for VramViewer<E> {
     }
 }
 
-pub trait VramAccess {
-    fn vram(&self) -> &Vram;
-    fn vram_mut(&mut self) -> &mut Vram;
-
-    fn oam(&self) -> &Oam;
-    fn oam_mut(&mut self) -> &mut Oam;
-}
-
-pub trait PpuAccess: VramAccess {
-    fn ppu(&self) -> &Ppu;
-    fn ppu_mut(&mut self) -> &mut Ppu;
-}
-
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub(crate) enum Textures {
     None,
@@ -60,9 +49,7 @@ pub(crate) enum Textures {
     Miniature,
 }
 
-const BUF_W: usize = 8;
 const BUF_H: usize = 8;
-const BUF_SZ: usize = 64;
 
 #[derive(Serialize, Deserialize, Clone)]
 pub(crate) struct PixelBuffer {
@@ -70,20 +57,7 @@ pub(crate) struct PixelBuffer {
     pixels: [u8; BUF_SZ],
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct ColorBuffer {
-    #[serde(with = "serde_arrays")]
-    pixels: [Option<[u8; 4]>; BUF_SZ],
-}
-
 impl ColorBuffer {
-    pub fn new() -> Self { Self { pixels: [None; BUF_SZ] } }
-
-    pub fn color(&mut self, x: usize, y: usize, color: [u8; 3]) {
-        let [r, g, b] = color;
-        self.pixels[x + y * BUF_W] = Some([r, g, b, 255]);
-    }
-
     pub(crate) fn image<const IW: usize, const IH: usize, const IS: usize>(&self, source: &PixelBuffer) -> ColorImage where [(); IS]: Sized {
         let sw = IW / BUF_W; // 8
         let sh = IH / BUF_H; // 8
@@ -216,7 +190,7 @@ impl<E: Emulator + PpuAccess> shared::Ui for VramViewer<E> {
         tiles.extend(vram.tile_cache.drain());
         for &tile in &tiles {
             self.tile_data[tile].blit(vram.tile_data(tile % 384, tile / 384));
-            let id = self.tex(Textures::Tile(tile)).expect(format!("can't access tile {tile}").as_str()).id();
+            let id = self.tex(Textures::Tile(tile)).unwrap_or_else(|| panic!("can't access tile {tile}")).id();
             let image = self.tile_data[tile].image::<64, 64, 16384>();
             ctx.tex_manager().write().set(id, ImageDelta::full(image, TextureOptions::NEAREST));
         }
@@ -225,7 +199,7 @@ impl<E: Emulator + PpuAccess> shared::Ui for VramViewer<E> {
         for (tile, color) in cache {
             was_empty = false;
             self.draw_cache.insert(tile);
-            let id = self.tex(Textures::Draw(tile)).expect(format!("can't access tile {tile}").as_str()).id();
+            let id = self.tex(Textures::Draw(tile)).unwrap_or_else(|| panic!("can't access tile {tile}")).id();
             let image = color.image::<64, 64, 16384>(&self.tile_data[tile]);
             ctx.tex_manager().write().set(id, ImageDelta::full(image, TextureOptions::NEAREST));
         }
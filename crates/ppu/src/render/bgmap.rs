@@ -3,7 +3,7 @@ use shared::egui::{Color32, Image, Response, Stroke, Ui, Vec2, Widget};
 use shared::emulator::Emulator;
 use shared::io::{IO, LCDC};
 
-use crate::render::PpuAccess;
+use crate::PpuAccess;
 use crate::VramViewer;
 
 pub struct BgMap<'a, E: Emulator + PpuAccess>(pub(crate) &'a mut VramViewer<E>, pub(crate) &'a mut E, pub(crate) &'a egui::Context);
@@ -78,7 +78,7 @@ impl<E: Emulator + PpuAccess> Widget for BgMap<'_, E> {
                     .fill(Color32::TRANSPARENT)
                     .show(ui, |ui| {
                         let data = self.0.bg_data.unwrap_or_default();
-                        let tex = self.0.get(data.tile as usize).id();
+                        let tex = self.0.get(data.tile).id();
                         ui.add(Image::new(tex, [256., 256.]));
                         ui.label(format!("x : {:02}", data.x));
                         ui.label(format!("Y : {:02}", data.y));
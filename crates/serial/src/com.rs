@@ -1,6 +1,7 @@
 use std::io::{ErrorKind, Read, Write};
-use std::net::{IpAddr, Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream};
-use std::sync::Arc;
+use std::net::{Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::ops::RangeInclusive;
+use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
 use std::time::Duration;
@@ -11,63 +12,249 @@ pub enum Event { Stop, Connected(SocketAddr), Disconnect }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum Msg {
-    Transfer(u8),
+    /// byte clocked out by the master, the transfer completes on cycle `done`
+    Transfer { byte: u8, done: u64 },
     Respond(u8),
-    Ack,
+    /// cycle count of the sender, and the duration of the shortest transfer it could start
+    Sync { cycle: u64, window: u16 },
+    /// first frame sent on a connection, handled by the server and never forwarded to the port
+    Hello(Hello),
 }
 
-impl From<&[u8; 8]> for Msg {
-    fn from(&[op, v, ..]: &[u8; 8]) -> Self {
-        match op {
-            104 => Msg::Transfer(v),
-            105 => Msg::Respond(v),
-            106 => Msg::Ack,
-            _ => panic!("out of range")
+impl Msg {
+    pub const SIZE: usize = 32;
+}
+
+impl TryFrom<&[u8; Msg::SIZE]> for Msg {
+    type Error = String;
+
+    fn try_from(buf: &[u8; Msg::SIZE]) -> Result<Self, Self::Error> {
+        let word = u64::from_le_bytes(buf[8..16].try_into().unwrap());
+        match buf[0] {
+            104 => Ok(Msg::Transfer { byte: buf[1], done: word }),
+            105 => Ok(Msg::Respond(buf[1])),
+            106 => match u16::from_le_bytes([buf[2], buf[3]]) {
+                0 => Err("sync frame with an empty window".to_string()),
+                window => Ok(Msg::Sync { cycle: word, window }),
+            },
+            107 if buf[2] > 2 => Err(format!("hello frame with invalid mode {}", buf[2])),
+            107 => Ok(Msg::Hello(Hello {
+                protocol: buf[1],
+                version: [buf[3], buf[4], buf[5]],
+                game: (buf[2] != 0).then(|| Game {
+                    title: buf[16..32].try_into().unwrap(),
+                    checksum: u16::from_le_bytes([buf[6], buf[7]]),
+                    cgb: buf[2] == 2,
+                }),
+            })),
+            n => Err(format!("unknown opcode {n}")),
         }
     }
 }
 
 impl Msg {
-    pub fn serialize(&self, target: &mut [u8; 8]) {
-        match self {
-            Msg::Transfer(v) => {
+    pub fn serialize(&self, target: &mut [u8; Msg::SIZE]) {
+        match *self {
+            Msg::Transfer { byte, done } => {
                 target[0] = 104;
-                target[1] = *v;
+                target[1] = byte;
+                target[8..16].copy_from_slice(&done.to_le_bytes());
             }
             Msg::Respond(v) => {
                 target[0] = 105;
-                target[1] = *v;
+                target[1] = v;
             }
-            Msg::Ack => {
+            Msg::Sync { cycle, window } => {
                 target[0] = 106;
+                target[2..4].copy_from_slice(&window.to_le_bytes());
+                target[8..16].copy_from_slice(&cycle.to_le_bytes());
             }
+            Msg::Hello(hello) => {
+                target[0] = 107;
+                target[1] = hello.protocol;
+                target[3..6].copy_from_slice(&hello.version);
+                if let Some(game) = hello.game {
+                    target[2] = 1 + game.cgb as u8;
+                    target[6..8].copy_from_slice(&game.checksum.to_le_bytes());
+                    target[16..32].copy_from_slice(&game.title);
+                }
+            }
+        }
+    }
+}
+
+/// Identifies each end of the cable, exchanged as soon as it connects.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Hello {
+    pub protocol: u8,
+    pub version: [u8; 3],
+    /// none until a game is loaded
+    pub game: Option<Game>,
+}
+
+/// Game running on one end of the cable.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Game {
+    pub title: [u8; 16],
+    pub checksum: u16,
+    pub cgb: bool,
+}
+
+impl Game {
+    pub fn new(title: &str, checksum: u16, cgb: bool) -> Self {
+        let mut raw = [0; 16];
+        title.bytes().take(16).zip(raw.iter_mut()).for_each(|(c, v)| *v = c);
+        Self { title: raw, checksum, cgb }
+    }
+
+    pub fn title(&self) -> String {
+        String::from_utf8_lossy(&self.title).replace(char::from(0), "")
+    }
+
+    pub fn mode(&self) -> &'static str { if self.cgb { "CGB" } else { "DMG" } }
+}
+
+impl Default for Hello {
+    fn default() -> Self { Hello::new(None) }
+}
+
+impl Hello {
+    /// Bumped on every change to the frames, peers with a different protocol are disconnected.
+    pub const PROTOCOL: u8 = 2;
+
+    pub fn new(game: Option<Game>) -> Self {
+        let mut version = [0; 3];
+        env!("CARGO_PKG_VERSION").split('.')
+            .zip(version.iter_mut())
+            .for_each(|(n, v)| *v = n.parse().unwrap_or(0));
+        Self { protocol: Hello::PROTOCOL, version, game }
+    }
+
+    pub fn version(&self) -> String {
+        let [major, minor, patch] = self.version;
+        format!("{major}.{minor}.{patch}")
+    }
+
+    /// Who the peer is, for the user.
+    pub fn describe(&self) -> String {
+        match self.game {
+            Some(game) => format!("{} ({}), version {}", game.title(), game.mode(), self.version()),
+            None => format!("no game, version {}", self.version()),
         }
     }
+
+    /// Why the link with `peer` is refused: another protocol, or games running in different modes.
+    pub fn refuse(&self, peer: &Hello) -> Option<String> {
+        if self.protocol != peer.protocol {
+            return Some(format!("incompatible link protocol v{} (expected v{})", peer.protocol, self.protocol));
+        }
+        match (self.game, peer.game) {
+            (Some(local), Some(peer)) if local.cgb != peer.cgb => {
+                Some(format!("peer runs in {} mode (this is {})", peer.mode(), local.mode()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Differences with the peer worth telling the user about, on a link that is not refused.
+    pub fn compare(&self, peer: &Hello) -> Vec<String> {
+        let mut warnings = vec![];
+        if self.version != peer.version {
+            warnings.push(format!("peer runs version {} (this is {})", peer.version(), self.version()));
+        }
+        match (self.game, peer.game) {
+            (Some(local), Some(peer)) if local.title != peer.title || local.checksum != peer.checksum => {
+                warnings.push(format!("peer plays {} ({:04X})", peer.title(), peer.checksum));
+            }
+            (Some(_), None) => warnings.push("peer has no game loaded".to_string()),
+            _ => {}
+        }
+        warnings
+    }
+}
+
+/// State of the connection, shared between the [`Serial`] handle and the server thread.
+#[derive(Clone, Default, Debug)]
+pub struct Status {
+    local: Hello,
+    /// set once the handshake succeeded
+    pub peer: Option<Hello>,
+    pub warnings: Vec<String>,
+    /// outcome of the last handshake, listed after the other warnings
+    handshake: Vec<String>,
+    rejected: usize,
+}
+
+impl Status {
+    const REJECTED: &'static str = "rejected frame";
+    const DIAL: &'static str = "cannot connect";
+
+    /// Only the last rejected frame is kept among the warnings, along with the count.
+    fn reject(&mut self, e: String) {
+        self.rejected += 1;
+        self.warnings.retain(|x| !x.starts_with(Self::REJECTED));
+        self.warnings.push(format!("{}: {e} ({} so far)", Self::REJECTED, self.rejected));
+    }
+
+    /// Only the last failed dial is kept among the warnings.
+    fn dial_failed(&mut self, e: String) {
+        self.warnings.retain(|x| !x.starts_with(Self::DIAL));
+        self.warnings.push(format!("{}: {e}", Self::DIAL));
+    }
+
+    /// Replaces the outcome of the previous handshake, returns false if the link is refused.
+    fn handshake(&mut self, peer: Hello) -> bool {
+        match self.local.refuse(&peer) {
+            Some(e) => {
+                self.handshake = vec![format!("refused the link: {e}")];
+                self.peer = None;
+                false
+            }
+            None => {
+                self.handshake = self.local.compare(&peer);
+                self.peer = Some(peer);
+                true
+            }
+        }
+    }
+
+    /// The connection dropped, its warnings are kept until the next one.
+    fn lost(&mut self) {
+        self.peer = None;
+    }
+
+    fn reset(&mut self) {
+        self.peer = None;
+        self.rejected = 0;
+        self.warnings.clear();
+        self.handshake.clear();
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item=&String> {
+        self.warnings.iter().chain(&self.handshake)
+    }
 }
 
 struct Client {
     inner: TcpStream,
-    data: Sender<Msg>,
+    data: Sender<Result<Msg, String>>,
     done: Sender<()>,
     connected: Arc<AtomicBool>,
 }
 
 impl Client {
-    pub fn spawn(client: TcpStream, data: Sender<Msg>, done: Sender<()>, connected: Arc<AtomicBool>) -> Self {
+    pub fn spawn(client: TcpStream, data: Sender<Result<Msg, String>>, done: Sender<()>, connected: Arc<AtomicBool>) -> Self {
         Self { inner: client, data, done, connected }
     }
     pub fn run(mut self) {
         std::thread::spawn(move || {
             self.inner.set_nonblocking(false).expect("block");
-            let mut buf = [0; 8];
+            let mut buf = [0; Msg::SIZE];
             loop {
                 match self.inner.read_exact(&mut buf) {
-                    Ok(()) => match self.data.send(Msg::from(&buf)) {
-                        Err(_) => {
-                            log::warn!("client: failed to send down recv data");
-                            break;
-                        }
-                        _ => {}
+                    Ok(()) => if self.data.send(Msg::try_from(&buf)).is_err() {
+                        log::warn!("client: failed to send down recv data");
+                        break;
                     },
                     Err(e) => {
                         match e.kind() {
@@ -87,10 +274,11 @@ impl Client {
 pub struct Serial {
     signal: Sender<Event>,
     events: Receiver<Event>,
-    connect: Sender<(Ipv4Addr, u16)>,
+    connect: Sender<(String, u16)>,
     recv: Receiver<Msg>,
     send: Sender<Msg>,
     connected: Arc<AtomicBool>,
+    status: Arc<Mutex<Status>>,
     pub(crate) port: u16,
 }
 
@@ -101,19 +289,30 @@ impl Default for Serial {
 }
 
 pub struct Server {
-    socket: TcpListener,
+    /// one listener per address family, unless the ipv6 one is dual-stack
+    sockets: Vec<TcpListener>,
     connected: Arc<AtomicBool>,
     client: Option<TcpStream>,
     signal: Option<Receiver<()>>,
-    data: Option<Receiver<Msg>>,
+    data: Option<Receiver<Result<Msg, String>>>,
+    status: Arc<Mutex<Status>>,
     recv: Receiver<Msg>,
     send: Sender<Msg>,
     events: Sender<Event>,
-    connect: Receiver<(Ipv4Addr, u16)>,
+    connect: Receiver<(String, u16)>,
     stop: Receiver<Event>,
+    /// address of the client, reported once the handshake succeeds
+    pending: Option<SocketAddr>,
+    /// frames received before the handshake, forwarded once it succeeds
+    early: Vec<Msg>,
+    /// outcome of the last dial, see [`Server::dial`]
+    dialing: Option<Receiver<Result<(TcpStream, SocketAddr), String>>>,
 }
 
 impl Server {
+    /// Frames held until the handshake, a peer sending more is not waiting for it.
+    const EARLY: usize = 64;
+
     pub fn connect(&mut self, stream: TcpStream, addr: SocketAddr) {
         let (tx, rec_x) = channel();
         let (td, rec_d) = channel();
@@ -123,29 +322,69 @@ impl Server {
         self.client = Some(stream.try_clone().expect("failed to clone socket"));
         log::info!("client connected from {addr:?}");
         if let Some(rd) = self.signal.as_mut() {
-            while let Ok(_) = rd.recv_timeout(Duration::from_secs(5)) {}
+            while rd.recv_timeout(Duration::from_secs(5)).is_ok() {}
         }
         self.signal = Some(rec_d);
+        let hello = self.status.lock().map(|mut x| {
+            x.reset();
+            x.local
+        }).unwrap_or_default();
+        self.early.clear();
+        self.pending = Some(addr);
+        self.write(Msg::Hello(hello));
         Client::spawn(stream, tx, td, self.connected.clone()).run();
-        self.connected.store(true, Ordering::Relaxed);
-        self.events.send(Event::Connected(addr)).ok();
+    }
+
+    /// Reports the connection once the peer is accepted, or closes it.
+    fn handshake(&mut self, peer: Hello) {
+        let Ok(mut status) = self.status.lock() else { return; };
+        let accepted = status.handshake(peer);
+        status.handshake.iter().for_each(|x| log::warn!("{x}"));
+        drop(status);
+        if !accepted {
+            self.early.clear();
+            self.client.as_ref().map(|x| x.shutdown(Shutdown::Both).ok());
+            return;
+        }
+        if let Some(addr) = self.pending.take() {
+            self.connected.store(true, Ordering::Relaxed);
+            self.events.send(Event::Connected(addr)).ok();
+        }
+        for msg in self.early.drain(..) { self.send.send(msg).ok(); }
     }
 
     fn recv(&mut self) {
-        if let Some(v) = match self.data.as_mut().map(|x| x.try_recv()) {
-            Some(Ok(v)) => Some(v),
+        let v = match self.data.as_mut().map(|x| x.try_recv()) {
+            Some(Ok(v)) => v,
             Some(Err(TryRecvError::Disconnected)) => {
                 self.data = None;
+                self.pending = None;
+                self.early.clear();
                 self.client.take().map(|x| x.shutdown(Shutdown::Both));
-                None
+                self.status.lock().map(|mut x| x.lost()).ok();
+                return;
+            }
+            _ => return
+        };
+        let peer = self.status.lock().map(|x| x.peer).unwrap_or_default();
+        match v {
+            Ok(Msg::Hello(hello)) => {
+                log::info!("peer: {}", hello.describe());
+                self.handshake(hello);
             }
-            _ => None
-        } { self.send.send(v).ok(); }
+            Ok(msg) if peer.is_some() => { self.send.send(msg).ok(); }
+            Ok(msg) if self.early.len() < Self::EARLY => self.early.push(msg),
+            Ok(msg) => log::warn!("dropped {msg:?}, received before the handshake"),
+            Err(e) => {
+                log::warn!("rejected frame: {e}");
+                self.status.lock().map(|mut x| x.reject(e)).ok();
+            }
+        }
     }
 
-    fn send(&mut self) {
-        if let (Ok(msg), Some(client)) = (self.recv.try_recv(), self.client.as_mut()) {
-            let mut buf = [0; 8];
+    fn write(&mut self, msg: Msg) {
+        if let Some(client) = self.client.as_mut() {
+            let mut buf = [0; Msg::SIZE];
             msg.serialize(&mut buf);
             if let Err(e) = client.write_all(&buf) {
                 log::warn!("error client send: {e:?}");
@@ -153,16 +392,28 @@ impl Server {
         }
     }
 
+    fn send(&mut self) {
+        let Ok(msg) = self.recv.try_recv() else { return; };
+        self.write(msg);
+        // a new game on this side may no longer suit the peer
+        if let Msg::Hello(_) = msg {
+            if let Some(peer) = self.status.lock().ok().and_then(|x| x.peer) { self.handshake(peer); }
+        }
+    }
+
     fn recv_events(&mut self) -> bool {
         match self.stop.try_recv().ok() {
             Some(Event::Stop) => true,
             Some(Event::Disconnect) => {
                 self.client.as_ref().map(|x| x.shutdown(Shutdown::Both).ok());
                 if let Some(rd) = self.signal.as_mut() {
-                    while let Ok(_) = rd.recv_timeout(Duration::from_secs(5)) {}
+                    while rd.recv_timeout(Duration::from_secs(5)).is_ok() {}
                 }
                 self.client = None;
                 self.signal = None;
+                self.pending = None;
+                self.early.clear();
+                self.status.lock().map(|mut x| x.reset()).ok();
                 false
             }
             None => { false }
@@ -170,22 +421,57 @@ impl Server {
         }
     }
 
+    /// Connects to the first address `host` resolves to that accepts the connection, on
+    /// another thread: resolving and connecting may take seconds.
+    fn dial(&mut self, host: String, port: u16) {
+        let (tx, rx) = channel();
+        self.dialing = Some(rx);
+        std::thread::spawn(move || {
+            let host = host.trim().trim_start_matches('[').trim_end_matches(']');
+            let stream = match (host, port).to_socket_addrs() {
+                Ok(addrs) => addrs.into_iter().find_map(|addr| {
+                    TcpStream::connect_timeout(&addr, Duration::from_secs(5)).ok().map(|stream| (stream, addr))
+                }).ok_or_else(|| format!("failed to connect to {host}:{port}")),
+                Err(e) => Err(format!("failed to resolve {host}: {e}")),
+            };
+            tx.send(stream).ok();
+        });
+    }
+
+    /// Connects once the last dial completes, the earlier ones are dropped.
+    fn dialed(&mut self) {
+        let stream = match self.dialing.as_ref().map(|x| x.try_recv()) {
+            Some(Ok(stream)) => stream,
+            Some(Err(TryRecvError::Disconnected)) => Err("dial aborted".to_string()),
+            _ => return,
+        };
+        self.dialing = None;
+        match stream {
+            Ok((stream, addr)) => self.connect(stream, addr),
+            Err(e) => {
+                log::warn!("{e}");
+                self.status.lock().map(|mut x| x.dial_failed(e)).ok();
+            }
+        }
+    }
+
     pub fn run(mut self) {
         loop {
-            match self.socket.accept() {
-                Ok((stream, addr)) => self.connect(stream, addr),
-                Err(e) if e.kind() != ErrorKind::WouldBlock => log::warn!("connection refused {e:?}"),
-                _ => {}
-            };
+            for i in 0..self.sockets.len() {
+                match self.sockets[i].accept() {
+                    Ok((stream, addr)) => self.connect(stream, addr),
+                    Err(e) if e.kind() != ErrorKind::WouldBlock => log::warn!("connection refused {e:?}"),
+                    _ => {}
+                };
+            }
             self.recv();
             self.send();
-            if let Some((addr, port)) = self.connect.try_recv().ok() {
-                if let Ok(stream) = TcpStream::connect((addr, port)) {
-                    self.connect(stream, SocketAddr::new(IpAddr::V4(addr), port));
-                }
+            if let Ok((host, port)) = self.connect.try_recv() {
+                self.dial(host, port);
             }
+            self.dialed();
             if self.recv_events() { break; }
-            if let Some(_) = self.stop.try_recv().ok() { break; }
+            if self.stop.try_recv().is_ok() { break; }
         }
     }
 }
@@ -205,24 +491,40 @@ impl Serial {
             recv: ri,
             send: to,
             connected: Arc::new(AtomicBool::new(false)),
+            status: Arc::default(),
         }
     }
 
-    pub fn build() -> Self {
+    /// Binds the first port of `ports` available on either address family.
+    fn listen(ports: RangeInclusive<u16>) -> Option<(u16, Vec<TcpListener>)> {
+        ports.into_iter().find_map(|port| {
+            // ipv6 first: when dual-stack it also takes the ipv4 port, and the second bind fails
+            let sockets: Vec<TcpListener> = [
+                TcpListener::bind(SocketAddr::from((Ipv6Addr::UNSPECIFIED, port))),
+                TcpListener::bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, port))),
+            ].into_iter().flatten().collect();
+            (!sockets.is_empty()).then_some((port, sockets))
+        })
+    }
+
+    /// Listens on the first available port of `ports`, on none (port 0) when they are all taken.
+    pub fn build(ports: RangeInclusive<u16>) -> Self {
         let connected = Arc::new(AtomicBool::new(false));
-        let srv = TcpListener::bind(
-            &(27542..27552).map(|port| SocketAddr::from(([0, 0, 0, 0], port)))
-                .collect::<Vec<SocketAddr>>()[..]).expect("failed to find available port");
-        srv.set_nonblocking(true).expect("failed to set nonblocking");
-        let port = srv.local_addr().map(|x| x.port()).unwrap_or_default();
+        let status = Arc::new(Mutex::new(Status::default()));
+        let (port, sockets) = Serial::listen(ports.clone()).unwrap_or_else(|| {
+            log::warn!("no available port to listen on in {ports:?}, peers can only be dialed");
+            (0, vec![])
+        });
+        sockets.iter().for_each(|x| x.set_nonblocking(true).expect("failed to set nonblocking"));
         let (tx_e, rx_e) = channel();
         let (tx_end, rx_end) = channel();
         let (tx_r, rx_r) = channel();
         let (tx_s, rx_s) = channel();
         let (tx_c, rx_c) = channel();
         let server = Server {
-            socket: srv,
+            sockets,
             connected: connected.clone(),
+            status: status.clone(),
             client: None,
             signal: None,
             data: None,
@@ -231,10 +533,14 @@ impl Serial {
             send: tx_r,
             connect: rx_c,
             stop: rx_end,
+            pending: None,
+            early: vec![],
+            dialing: None,
         };
         std::thread::spawn(move || server.run());
         Self {
             connected,
+            status,
             events: rx_e,
             connect: tx_c,
             signal: tx_end,
@@ -252,9 +558,21 @@ impl Serial {
         self.events.try_recv().ok()
     }
 
-    pub fn connect(&self, ip: Ipv4Addr, port: u16) {
-        log::info!("Connecting to peer {ip:?}:{port}");
-        self.connect.send((ip, port)).ok();
+    /// Connects to a peer, `host` being an ip address (v4 or v6) or a hostname.
+    pub fn connect(&self, host: &str, port: u16) {
+        log::info!("Connecting to peer {host}:{port}");
+        self.connect.send((host.to_string(), port)).ok();
+    }
+
+    /// Sets what is sent to peers during the handshake, and to the current one if any.
+    pub fn set_hello(&self, hello: Hello) {
+        let Ok(mut status) = self.status.lock() else { return; };
+        status.local = hello;
+        self.send.send(Msg::Hello(hello)).ok();
+    }
+
+    pub fn status(&self) -> Status {
+        self.status.lock().map(|x| x.clone()).unwrap_or_default()
     }
 
     pub fn disconnect(&self) {
@@ -266,3 +584,105 @@ impl Serial {
         self.recv.try_recv().ok()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(msg: Msg) -> Result<Msg, String> {
+        let mut buf = [0; Msg::SIZE];
+        msg.serialize(&mut buf);
+        Msg::try_from(&buf)
+    }
+
+    fn hello(cgb: bool) -> Hello { Hello::new(Some(Game::new("POKEMON RED", 0x91E6, cgb))) }
+
+    #[test]
+    fn frames() {
+        let transfer = Msg::Transfer { byte: 0xA5, done: 0x1234_5678_9ABC };
+        assert_eq!(round_trip(transfer), Ok(transfer));
+        assert_eq!(round_trip(Msg::Respond(0x5A)), Ok(Msg::Respond(0x5A)));
+        let sync = Msg::Sync { cycle: u64::MAX - 1, window: 512 };
+        assert_eq!(round_trip(sync), Ok(sync));
+        assert!(round_trip(Msg::Sync { cycle: 0, window: 0 }).is_err());
+    }
+
+    #[test]
+    fn hello_frames() {
+        assert_eq!(round_trip(Msg::Hello(hello(true))), Ok(Msg::Hello(hello(true))));
+        assert_eq!(round_trip(Msg::Hello(hello(false))), Ok(Msg::Hello(hello(false))));
+        assert_eq!(round_trip(Msg::Hello(Hello::new(None))), Ok(Msg::Hello(Hello::new(None))));
+        let mut buf = [0; Msg::SIZE];
+        Msg::Hello(hello(true)).serialize(&mut buf);
+        buf[2] = 3;
+        assert!(Msg::try_from(&buf).is_err());
+    }
+
+    #[test]
+    fn refuses_other_modes() {
+        assert!(hello(true).refuse(&hello(false)).is_some());
+        assert!(hello(true).refuse(&Hello::new(None)).is_none());
+        assert!(Hello::new(None).refuse(&hello(false)).is_none());
+        let old = Hello { protocol: Hello::PROTOCOL - 1, ..hello(true) };
+        assert!(hello(true).refuse(&old).is_some());
+    }
+
+    #[test]
+    fn handshake_keeps_the_warnings() {
+        let mut status = Status { local: hello(true), ..Default::default() };
+        status.reject("unknown opcode 7".to_string());
+        let peer = Hello::new(Some(Game::new("POKEMON BLUE", 0x9D0A, true)));
+        assert!(status.handshake(peer));
+        assert_eq!(status.peer, Some(peer));
+        assert_eq!(status.warnings().count(), 2);
+        assert!(!status.handshake(hello(false)));
+        assert_eq!(status.peer, None);
+        assert!(status.warnings().any(|x| x.starts_with(Status::REJECTED)));
+        assert!(status.warnings().any(|x| x.starts_with("refused")));
+        status.lost();
+        assert_eq!(status.warnings().count(), 2);
+    }
+
+    fn wait(until: impl Fn() -> bool) -> bool {
+        (0..500).any(|_| {
+            std::thread::sleep(Duration::from_millis(10));
+            until()
+        })
+    }
+
+    #[test]
+    fn connects_after_the_handshake() {
+        let (a, b) = (Serial::build(47300..=47399), Serial::build(47300..=47399));
+        a.set_hello(hello(true));
+        b.connect("localhost", a.port);
+        assert!(wait(|| a.connected() && b.connected()));
+        assert_eq!(b.status().peer, Some(hello(true)));
+        assert_eq!(a.status().peer, Some(Hello::new(None)));
+        b.send(Msg::Respond(0x42));
+        assert!(wait(|| a.recv() == Some(Msg::Respond(0x42))));
+
+        b.set_hello(hello(false));
+        assert!(wait(|| !a.connected() && !b.connected()));
+        assert!(a.status().warnings().any(|x| x.starts_with("refused")));
+    }
+
+    #[test]
+    fn listens_on_a_free_port() {
+        let a = Serial::build(47400..=47401);
+        let b = Serial::build(47400..=47401);
+        assert_ne!(a.port, b.port);
+        assert_eq!(Serial::build(47400..=47401).port, 0);
+        let (first, last) = (47409, 47405);
+        assert_eq!(Serial::build(first..=last).port, 0);
+    }
+
+    #[test]
+    fn keeps_the_last_failed_dial() {
+        let serial = Serial::build(47410..=47419);
+        for _ in 0..3 {
+            serial.connect("localhost", 1);
+            assert!(wait(|| serial.status().warnings().any(|x| x.starts_with(Status::DIAL))));
+        }
+        assert_eq!(serial.status().warnings().count(), 1);
+    }
+}
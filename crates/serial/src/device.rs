@@ -0,0 +1,117 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use crate::com::{Msg, Serial};
+
+/// Other end of the link cable, as seen by [`crate::Port`].
+pub trait LinkDevice: Send {
+    /// Whether something answers on the other end, transfers shift in 0xFF bits otherwise.
+    fn connected(&self) -> bool { true }
+    fn send(&mut self, msg: Msg);
+    fn recv(&mut self) -> Option<Msg>;
+
+    /// The tcp cable to another emulator, which the frontend keeps configuring once plugged.
+    fn cable(&mut self) -> Option<&mut Serial> { None }
+
+    /// Takes the tcp cable back out of an unplugged device.
+    fn into_cable(self: Box<Self>) -> Option<Serial> { None }
+}
+
+impl LinkDevice for Serial {
+    fn connected(&self) -> bool { Serial::connected(self) }
+    fn send(&mut self, msg: Msg) { Serial::send(self, msg) }
+    fn recv(&mut self) -> Option<Msg> { Serial::recv(self) }
+    fn cable(&mut self) -> Option<&mut Serial> { Some(self) }
+    fn into_cable(self: Box<Self>) -> Option<Serial> { Some(*self) }
+}
+
+/// Accessory clocked by the console, answering every byte it receives.
+pub trait Accessory: Send {
+    /// Exchanges a byte: receives the one shifted out by the console, returns the one shifted in.
+    fn transfer(&mut self, byte: u8) -> u8;
+}
+
+/// Plugs an [`Accessory`] in the port, answering the link protocol in place of a peer console.
+pub struct Plug<A: Accessory> {
+    accessory: A,
+    replies: VecDeque<Msg>,
+}
+
+impl<A: Accessory> Plug<A> {
+    pub fn new(accessory: A) -> Self { Self { accessory, replies: VecDeque::new() } }
+
+    pub fn accessory(&self) -> &A { &self.accessory }
+}
+
+impl<A: Accessory> LinkDevice for Plug<A> {
+    fn send(&mut self, msg: Msg) {
+        match msg {
+            Msg::Transfer { byte, .. } => self.replies.push_back(Msg::Respond(self.accessory.transfer(byte))),
+            Msg::Sync { .. } | Msg::Hello(_) => {}
+            Msg::Respond(_) => log::warn!("accessories don't clock transfers"),
+        }
+    }
+
+    fn recv(&mut self) -> Option<Msg> { self.replies.pop_front() }
+}
+
+/// In-process cable between two ports, see [`Loopback::pair`].
+pub struct Loopback {
+    send: Sender<Msg>,
+    recv: Receiver<Msg>,
+}
+
+impl Loopback {
+    pub fn pair() -> (Self, Self) {
+        let (ta, ra) = channel();
+        let (tb, rb) = channel();
+        (Self { send: ta, recv: rb }, Self { send: tb, recv: ra })
+    }
+}
+
+impl LinkDevice for Loopback {
+    fn send(&mut self, msg: Msg) { self.send.send(msg).ok(); }
+    fn recv(&mut self) -> Option<Msg> { self.recv.try_recv().ok() }
+}
+
+/// Keeps every byte sent by the console, answers like an unplugged port.
+#[derive(Clone, Default)]
+pub struct Sink {
+    bytes: Arc<Mutex<Vec<u8>>>,
+}
+
+impl Sink {
+    pub fn new() -> Self { Self::default() }
+
+    /// Bytes received so far, shared with the clones plugged in a port.
+    pub fn bytes(&self) -> Vec<u8> { self.bytes.lock().map(|x| x.clone()).unwrap_or_default() }
+}
+
+impl Accessory for Sink {
+    fn transfer(&mut self, byte: u8) -> u8 {
+        log::info!("link sink received {byte:#04X}");
+        if let Ok(mut bytes) = self.bytes.lock() { bytes.push(byte); }
+        0xFF
+    }
+}
+
+/// Answers with the given bytes in order, then with `fallback`.
+pub struct Script {
+    replies: VecDeque<u8>,
+    fallback: u8,
+}
+
+impl Script {
+    pub fn new(replies: impl IntoIterator<Item=u8>, fallback: u8) -> Self {
+        Self { replies: replies.into_iter().collect(), fallback }
+    }
+}
+
+impl Accessory for Script {
+    fn transfer(&mut self, _: u8) -> u8 { self.replies.pop_front().unwrap_or(self.fallback) }
+}
+
+impl<F: FnMut(u8) -> u8 + Send> Accessory for F {
+    fn transfer(&mut self, byte: u8) -> u8 { self(byte) }
+}
@@ -1,14 +1,16 @@
 extern crate core;
 
-use std::net::Ipv4Addr;
+use std::ops::RangeInclusive;
 
 use shared::io::{IO, IODevice, IORegs};
 use shared::mem::IOBus;
 
 use crate::com::{Msg, Serial};
+pub use crate::device::{Accessory, LinkDevice, Loopback, Plug, Script, Sink};
 
 pub mod com;
-mod protocol;
+mod device;
+pub mod printer;
 
 pub struct Link {
     pub port: u16,
@@ -16,8 +18,9 @@ pub struct Link {
 }
 
 impl Link {
-    pub fn new() -> Self {
-        let serial = Serial::build();
+    /// Listens for peers on the first available port of `ports`, `port` is 0 if none is.
+    pub fn new(ports: RangeInclusive<u16>) -> Self {
+        let serial = Serial::build(ports);
         let port = serial.port;
         Self {
             port,
@@ -38,8 +41,8 @@ impl Link {
         self.cable = Some(serial);
     }
 
-    pub fn connect(&mut self, addr: Ipv4Addr, port: u16) {
-        self.cable.as_mut().unwrap().connect(addr, port);
+    pub fn connect(&mut self, host: &str, port: u16) {
+        self.cable.as_mut().unwrap().connect(host, port);
     }
 
     pub fn borrowed(&self) -> bool { self.cable.is_none() }
@@ -48,104 +51,232 @@ impl Link {
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 enum State {
     Idle,
-    Transfer,
-    Respond,
-    Ack,
+    /// clocking a byte out, completes on the given cycle once the peer responded
+    Transfer(u64),
+    /// clocked by the peer, completes on the given cycle
+    Respond(u64),
 }
 
+/// Serial port of the console.
+///
+/// Both ends of a cable exchange their cycle count every half of the smallest of their windows and
+/// never run more than that window ahead of each other, a window being the duration of the shortest
+/// transfer a side could start. Transfers carry the cycle they complete on, so they end at the exact
+/// same emulated time on both consoles.
 pub struct Port {
-    cable: Serial,
     bits: u8,
     cycles: usize,
     data: Option<u8>,
     state: State,
+    sent: Option<Vec<u8>>,
+    /// tcp cable, accessory or other port, see [`LinkDevice`]
+    device: Option<Box<dyn LinkDevice>>,
+    /// 4MiHz clock cycles elapsed, independent of the double speed mode
+    clock: u64,
+    /// last cycle count received from the peer, none for accessories which don't send any
+    peer: Option<u64>,
+    window: u64,
+    peer_window: u64,
+    synced: u64,
+    /// next transfer from the peer, received before the current one completed
+    queued: Option<Msg>,
 }
 
 impl Default for Port {
-    fn default() -> Self { Port::new(Serial::phantom()) }
+    fn default() -> Self { Port::new() }
 }
 
 impl Port {
-    pub fn new(cable: Serial) -> Self {
-        Self { data: None, cable, bits: 0, cycles: 0, state: State::Idle }
+    /// Unplugged port, which shifts in 0xFF bits until a device is plugged.
+    pub fn new() -> Self {
+        Self {
+            data: None,
+            bits: 0,
+            cycles: 0,
+            state: State::Idle,
+            sent: None,
+            device: None,
+            clock: 0,
+            peer: None,
+            window: Port::duration(0, false),
+            peer_window: Port::duration(0, false),
+            synced: 0,
+            queued: None,
+        }
+    }
+
+    /// Plugs a device in the port, returns the one it replaces.
+    pub fn plug(&mut self, device: Box<dyn LinkDevice>) -> Option<Box<dyn LinkDevice>> {
+        self.reset();
+        self.device.replace(device)
+    }
+
+    pub fn unplug(&mut self) -> Option<Box<dyn LinkDevice>> {
+        self.reset();
+        self.device.take()
+    }
+
+    fn reset(&mut self) {
+        self.state = State::Idle;
+        self.data = None;
+        self.peer = None;
+        self.synced = self.clock;
+        self.queued = None;
+    }
+
+    fn connected(&self) -> bool { self.device.as_ref().is_some_and(|x| x.connected()) }
+
+    fn send(&mut self, msg: Msg) {
+        if let Some(device) = self.device.as_mut() { device.send(msg); }
     }
 
-    pub fn link(&mut self) -> &mut Serial { &mut self.cable }
+    fn recv(&mut self) -> Option<Msg> { self.device.as_mut()?.recv() }
 
-    pub fn connect(&mut self, addr: Ipv4Addr, port: u16) {
-        log::info!("trying to connect to remote...");
-        self.cable.connect(addr, port);
+    /// Whether an accessory or another port is plugged in place of the tcp cable.
+    pub fn plugged(&mut self) -> bool {
+        self.device.as_deref_mut().is_some_and(|x| x.cable().is_none())
+    }
+
+    /// The tcp cable, if it is the one plugged.
+    pub fn link(&mut self) -> Option<&mut Serial> { self.device.as_deref_mut()?.cable() }
+
+    /// Keeps a copy of every byte sent by the console from now on, see [`Port::sent`].
+    pub fn record(&mut self) {
+        self.sent.get_or_insert_with(Vec::new);
+    }
+
+    pub fn sent(&self) -> &[u8] {
+        self.sent.as_deref().unwrap_or(&[])
+    }
+
+    /// Clock cycles needed to shift a whole byte, at 8192Hz or 262144Hz (CGB fast clock)
+    /// doubled in double speed mode.
+    fn duration(sc: u8, ds: bool) -> u64 {
+        let bit = if sc & 0x2 == 0 { 512 } else { 16 };
+        8 * if ds { bit / 2 } else { bit }
+    }
+
+    /// Whether the console may run its next cycle: false while it is a window ahead of the peer,
+    /// or while a transfer is due and the peer has yet to respond.
+    pub fn ready(&mut self, io: &mut IORegs) -> bool {
+        if !self.blocked() { return true; }
+        if !self.connected() {
+            self.disconnected();
+            return !self.blocked();
+        }
+        // the peer may be blocked as well, waiting for a cycle count sent before this side stopped
+        if self.synced != self.clock { self.sync(); }
+        self.poll(io);
+        !self.blocked()
+    }
+
+    /// Completes a pending transfer with 0xFF, like an unplugged port.
+    fn disconnected(&mut self) {
+        self.peer = None;
+        if let State::Transfer(_) = self.state { self.data.get_or_insert(0xFF); }
+    }
+
+    fn sync(&mut self) {
+        self.synced = self.clock;
+        let (cycle, window) = (self.clock, self.window as u16);
+        self.send(Msg::Sync { cycle, window });
+    }
+
+    fn blocked(&self) -> bool {
+        let ahead = self.peer.is_some_and(|peer| self.clock > peer + self.window.min(self.peer_window));
+        let waiting = matches!(self.state, State::Transfer(done) if self.clock >= done && self.data.is_none());
+        ahead || waiting
+    }
+
+    fn poll(&mut self, io: &mut IORegs) {
+        let mut queued = self.queued.take();
+        while let Some(msg) = queued.take().or_else(|| self.recv()) {
+            match (self.state, msg) {
+                (_, Msg::Hello(_)) => {}
+                (_, Msg::Sync { cycle, window }) => {
+                    self.peer = Some(cycle);
+                    self.peer_window = window as u64;
+                }
+                (State::Idle, Msg::Transfer { byte, done }) => {
+                    self.state = State::Respond(done);
+                    self.data = Some(byte);
+                    let o = io.io(IO::SB).value();
+                    log::info!("responding {o:#02X}");
+                    self.send(Msg::Respond(o));
+                }
+                (State::Respond(_), Msg::Transfer { .. }) => self.queued = Some(msg),
+                (State::Transfer(_), Msg::Transfer { byte, .. }) => {
+                    log::warn!("both consoles are clocking the transfer");
+                    self.data = Some(byte);
+                    let o = io.io(IO::SB).value();
+                    self.send(Msg::Respond(o));
+                }
+                (State::Transfer(_), Msg::Respond(o)) => {
+                    self.data = Some(o);
+                }
+                (state, m) => log::warn!("received unexpected {m:?} while in {state:?} state"),
+            }
+        }
     }
 
     fn interrupt(&mut self, io: &mut IORegs) {
         let v = self.data.take().unwrap();
         log::info!("transfer finished, received {v:#02X}");
         io.io_mut(IO::SB).direct_write(v);
-        io.io_mut(IO::SC).reset(7);
-        io.int_set(3);
+        if io.io(IO::SC).bit(7) != 0 {
+            io.io_mut(IO::SC).reset(7);
+            io.int_set(3);
+        }
     }
 
     pub fn tick(&mut self, io: &mut IORegs) {
-        let sc = io.io(IO::SC);
-        if sc.value() & 0x81 == 0x81 {
-            self.cycles += if sc.bit(1) == 0 { 1 } else { 32 };
-            if self.cycles >= 128 {
-                self.cycles -= 128;
-                if !self.cable.connected() {
+        let ds = io.io(IO::KEY1).bit(7) != 0;
+        let sc = io.io(IO::SC).value();
+        self.clock += if ds { 2 } else { 4 };
+        let window = Port::duration(sc, ds);
+        let resync = window != self.window;
+        self.window = window;
+        let connected = self.connected();
+        if !connected {
+            self.disconnected();
+        } else if resync || self.clock >= self.synced + self.window.min(self.peer_window) / 2 {
+            self.sync();
+        }
+        self.poll(io);
+        match self.state {
+            State::Idle if sc & 0x81 == 0x81 && connected => {
+                let byte = io.io(IO::SB).value();
+                let done = self.clock + self.window;
+                log::info!("sending {byte:#02X}");
+                if let Some(sent) = self.sent.as_mut() { sent.push(byte); }
+                self.state = State::Transfer(done);
+                self.send(Msg::Transfer { byte, done });
+            }
+            State::Idle if sc & 0x81 == 0x81 => {
+                if self.bits == 0 && self.cycles == 0 {
+                    if let Some(sent) = self.sent.as_mut() { sent.push(io.io(IO::SB).value()); }
+                }
+                self.cycles += if sc & 0x2 == 0 { 1 } else { 32 };
+                if self.cycles >= 128 {
+                    self.cycles -= 128;
                     let sb = io.io_mut(IO::SB);
                     let v = sb.value();
                     sb.direct_write(v << 1 | 1);
-                }
-                log::info!("transfer bit {}", self.bits);
-                self.bits += 1;
-                if self.bits >= 8 {
-                    if !self.cable.connected() {
-                        self.state = State::Idle;
+                    self.bits += 1;
+                    if self.bits >= 8 {
                         self.data = Some(io.io(IO::SB).value());
                         self.interrupt(io);
-                    } else if let (State::Transfer, Some(_)) = (self.state, self.data) {
-                        self.state = State::Ack;
-                        self.cable.send(Msg::Ack);
-                    } else {
-                        log::info!("transfer too long, stalling ({} bits cycles passed)", self.bits - 8);
                     }
                 }
             }
-            if self.state == State::Idle {
-                self.state = State::Transfer;
-                let o = io.io(IO::SB).value();
-                log::info!("sending {o:#02X}");
-                self.cable.send(Msg::Transfer(o));
-            }
-        }
-        match (self.state, self.cable.recv()) {
-            (State::Idle, Some(Msg::Transfer(o))) => {
-                self.state = State::Respond;
-                self.data = Some(o);
-                let o = io.io(IO::SB).value();
-                log::info!("responding {o:#02X}");
-                self.cable.send(Msg::Respond(o));
-            }
-            (State::Transfer, Some(Msg::Respond(o))) => {
-                self.data = Some(o);
-            }
-            (State::Respond, Some(Msg::Ack)) => {
-                self.state = State::Idle;
-                self.interrupt(io);
-                self.cable.send(Msg::Ack);
-            }
-            (State::Ack, Some(Msg::Ack)) => {
+            State::Transfer(done) | State::Respond(done) if self.clock >= done && self.data.is_some() => {
+                if self.clock > done { log::warn!("transfer completed {} cycles late", self.clock - done); }
                 self.state = State::Idle;
                 self.interrupt(io);
             }
-            (state, Some(m)) => log::warn!("received unexpected {m:?} while in {state:?} state"),
-            (_, None) => {}
+            _ => {}
         }
     }
-
-    pub fn disconnect(&mut self) -> Serial {
-        std::mem::replace(&mut self.cable, Serial::phantom())
-    }
 }
 
 impl IODevice for Port {
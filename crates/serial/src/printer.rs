@@ -0,0 +1,274 @@
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::device::Accessory;
+
+const MAGIC: [u8; 2] = [0x88, 0x33];
+
+const INIT: u8 = 0x1;
+const PRINT: u8 = 0x2;
+const DATA: u8 = 0x4;
+const STATUS: u8 = 0xF;
+
+const ALIVE: u8 = 0x81;
+
+mod status {
+    pub const CHECKSUM: u8 = 0x01;
+    pub const PRINTING: u8 = 0x02;
+    pub const FULL: u8 = 0x04;
+    pub const UNPROCESSED: u8 = 0x08;
+}
+
+/// Image width in pixels (20 tiles).
+const WIDTH: usize = 160;
+const TILE_ROW: usize = WIDTH / 8 * 16;
+/// Largest print buffer, 9 data packets of 2 tile rows.
+const BUFFER: usize = 0x280 * 9;
+/// Status requests answered as busy after a print.
+const PRINT_POLLS: u8 = 4;
+
+const SHADES: [u8; 4] = [0xFF, 0xAA, 0x55, 0x00];
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Stage {
+    Magic(usize),
+    Command,
+    Compression,
+    Length(usize),
+    Data,
+    Checksum(usize),
+    Alive,
+    Status,
+}
+
+/// Game Boy Printer: decodes the packets sent by the console and writes every print as a PNG.
+pub struct Printer {
+    dir: PathBuf,
+    stage: Stage,
+    command: u8,
+    compressed: bool,
+    length: u16,
+    data: Vec<u8>,
+    sum: u16,
+    checksum: u16,
+    buffer: Vec<u8>,
+    status: u8,
+    printing: u8,
+    prints: usize,
+}
+
+impl Printer {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            stage: Stage::Magic(0),
+            command: 0,
+            compressed: false,
+            length: 0,
+            data: vec![],
+            sum: 0,
+            checksum: 0,
+            buffer: vec![],
+            status: 0,
+            printing: 0,
+            prints: 0,
+        }
+    }
+
+    fn decompress(raw: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(raw.len() * 2);
+        let mut it = raw.iter();
+        while let Some(&n) = it.next() {
+            if n & 0x80 != 0 {
+                let Some(&v) = it.next() else { break; };
+                out.extend(std::iter::repeat(v).take((n & 0x7F) as usize + 2));
+            } else {
+                out.extend(it.by_ref().take(n as usize + 1));
+            }
+        }
+        out
+    }
+
+    fn execute(&mut self) {
+        if self.sum != self.checksum {
+            log::warn!("printer: checksum mismatch ({:#06X} != {:#06X})", self.sum, self.checksum);
+            self.status |= status::CHECKSUM;
+            return;
+        }
+        self.status &= !status::CHECKSUM;
+        match self.command {
+            INIT => {
+                self.buffer.clear();
+                self.status = 0;
+                self.printing = 0;
+            }
+            DATA => {
+                let data = if self.compressed { Printer::decompress(&self.data) } else { std::mem::take(&mut self.data) };
+                let room = BUFFER.saturating_sub(self.buffer.len());
+                self.buffer.extend(data.into_iter().take(room));
+                if !self.buffer.is_empty() { self.status |= status::UNPROCESSED; }
+                if self.buffer.len() >= BUFFER { self.status |= status::FULL; }
+            }
+            PRINT => {
+                // a null palette selects the default one
+                let palette = self.data.get(2).copied().filter(|&x| x != 0).unwrap_or(0xE4);
+                self.print(palette);
+                self.buffer.clear();
+                self.status = status::PRINTING;
+                self.printing = PRINT_POLLS;
+            }
+            STATUS => {
+                if self.printing > 0 {
+                    self.printing -= 1;
+                    if self.printing == 0 { self.status &= !status::PRINTING; }
+                }
+            }
+            n => log::warn!("printer: unknown command {n:#04X}"),
+        }
+    }
+
+    fn print(&mut self, palette: u8) {
+        let height = self.buffer.len() / TILE_ROW * 8;
+        if height == 0 { return; }
+        let mut image = image::GrayImage::new(WIDTH as u32, height as u32);
+        for (tile, bytes) in self.buffer.chunks_exact(16).enumerate() {
+            let (tx, ty) = (tile % (WIDTH / 8), tile / (WIDTH / 8));
+            for (row, line) in bytes.chunks_exact(2).enumerate() {
+                for bit in 0..8 {
+                    let color = (line[0] >> (7 - bit)) & 1 | ((line[1] >> (7 - bit)) & 1) << 1;
+                    let shade = (palette >> (2 * color)) & 0x3;
+                    image.put_pixel((tx * 8 + bit) as u32, (ty * 8 + row) as u32, image::Luma([SHADES[shade as usize]]));
+                }
+            }
+        }
+        let time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|x| x.as_secs()).unwrap_or(0);
+        let path = self.dir.join(format!("print-{time}-{}.png", self.prints));
+        self.prints += 1;
+        match std::fs::create_dir_all(&self.dir).map_err(image::ImageError::from).and_then(|_| image.save(&path)) {
+            Ok(()) => log::info!("printed {path:?}"),
+            Err(e) => log::warn!("printer: failed to write {path:?}: {e:?}"),
+        }
+    }
+}
+
+impl Accessory for Printer {
+    fn transfer(&mut self, byte: u8) -> u8 {
+        let (next, reply) = match self.stage {
+            Stage::Magic(n) if byte == MAGIC[n] => (if n == 0 { Stage::Magic(1) } else { Stage::Command }, 0),
+            Stage::Magic(_) => (Stage::Magic(if byte == MAGIC[0] { 1 } else { 0 }), 0),
+            Stage::Command => {
+                self.command = byte;
+                self.sum = byte as u16;
+                (Stage::Compression, 0)
+            }
+            Stage::Compression => {
+                self.compressed = byte & 1 != 0;
+                self.sum = self.sum.wrapping_add(byte as u16);
+                (Stage::Length(0), 0)
+            }
+            Stage::Length(n) => {
+                self.sum = self.sum.wrapping_add(byte as u16);
+                if n == 0 {
+                    self.length = byte as u16;
+                    (Stage::Length(1), 0)
+                } else {
+                    self.length |= (byte as u16) << 8;
+                    self.data.clear();
+                    (if self.length == 0 { Stage::Checksum(0) } else { Stage::Data }, 0)
+                }
+            }
+            Stage::Data => {
+                self.sum = self.sum.wrapping_add(byte as u16);
+                self.data.push(byte);
+                (if self.data.len() >= self.length as usize { Stage::Checksum(0) } else { Stage::Data }, 0)
+            }
+            Stage::Checksum(0) => {
+                self.checksum = byte as u16;
+                (Stage::Checksum(1), 0)
+            }
+            Stage::Checksum(_) => {
+                self.checksum |= (byte as u16) << 8;
+                (Stage::Alive, 0)
+            }
+            Stage::Alive => {
+                self.execute();
+                (Stage::Status, ALIVE)
+            }
+            Stage::Status => (Stage::Magic(0), self.status),
+        };
+        self.stage = next;
+        reply
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sends a whole packet, returns the alive and status replies.
+    fn packet(printer: &mut Printer, command: u8, compressed: bool, data: &[u8]) -> (u8, u8) {
+        let header = [command, compressed as u8, data.len() as u8, (data.len() >> 8) as u8];
+        let sum = header.iter().chain(data).fold(0u16, |sum, &x| sum.wrapping_add(x as u16)).to_le_bytes();
+        let bytes = MAGIC.iter().chain(&header).chain(data).chain(&sum).copied();
+        assert!(bytes.map(|x| printer.transfer(x)).all(|x| x == 0));
+        (printer.transfer(0), printer.transfer(0))
+    }
+
+    fn printer(name: &str) -> Printer {
+        Printer::new(std::env::temp_dir().join(format!("printer-{name}-{}", std::process::id())))
+    }
+
+    #[test]
+    fn decompresses() {
+        assert_eq!(Printer::decompress(&[0x81, 0xAA, 0x01, 1, 2, 0x80]), [0xAA, 0xAA, 0xAA, 1, 2]);
+    }
+
+    #[test]
+    fn answers_packets() {
+        let mut printer = printer("packets");
+        // garbage before the magic bytes is ignored
+        printer.transfer(0x88);
+        printer.transfer(0x00);
+        assert_eq!(packet(&mut printer, INIT, false, &[]), (ALIVE, 0));
+        assert_eq!(packet(&mut printer, DATA, false, &[0; TILE_ROW]), (ALIVE, status::UNPROCESSED));
+        assert_eq!(packet(&mut printer, DATA, true, &[0xFE, 0xAA]), (ALIVE, status::UNPROCESSED));
+        assert_eq!(printer.buffer.len(), TILE_ROW + 0x80);
+        assert_eq!(packet(&mut printer, STATUS, false, &[]), (ALIVE, status::UNPROCESSED));
+        assert_eq!(packet(&mut printer, INIT, false, &[]), (ALIVE, 0));
+        assert!(printer.buffer.is_empty());
+    }
+
+    #[test]
+    fn rejects_bad_checksums() {
+        let mut printer = printer("checksum");
+        for x in MAGIC.iter().chain(&[INIT, 0, 0, 0, 0x42, 0]) { printer.transfer(*x); }
+        assert_eq!((printer.transfer(0), printer.transfer(0)), (ALIVE, status::CHECKSUM));
+        assert_eq!(packet(&mut printer, STATUS, false, &[]), (ALIVE, 0));
+    }
+
+    #[test]
+    fn fills_the_buffer() {
+        let mut printer = printer("full");
+        for _ in 0..10 { packet(&mut printer, DATA, false, &[0; 0x280]); }
+        assert_eq!(printer.buffer.len(), BUFFER);
+        assert_eq!(packet(&mut printer, STATUS, false, &[]), (ALIVE, status::UNPROCESSED | status::FULL));
+    }
+
+    #[test]
+    fn prints() {
+        let mut printer = printer("print");
+        packet(&mut printer, DATA, false, &[0xFF; TILE_ROW]);
+        assert_eq!(packet(&mut printer, PRINT, false, &[1, 0x13, 0, 0x40]), (ALIVE, status::PRINTING));
+        for _ in 1..PRINT_POLLS {
+            assert_eq!(packet(&mut printer, STATUS, false, &[]), (ALIVE, status::PRINTING));
+        }
+        assert_eq!(packet(&mut printer, STATUS, false, &[]), (ALIVE, 0));
+
+        let file = std::fs::read_dir(&printer.dir).unwrap().next().unwrap().unwrap().path();
+        let image = image::open(&file).unwrap().into_luma8();
+        assert_eq!(image.dimensions(), (WIDTH as u32, 8));
+        // color 3 with the default palette
+        assert!(image.pixels().all(|x| x.0 == [0x00]));
+        std::fs::remove_dir_all(&printer.dir).unwrap();
+    }
+}
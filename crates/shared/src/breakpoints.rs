@@ -1,17 +1,27 @@
+use std::collections::VecDeque;
 use std::fmt::Formatter;
+use std::fs::{File, OpenOptions};
+use std::io::{LineWriter, Write};
+use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 
 use crate::{
     cpu::{Cpu, Op, Opcode, Reg},
+    symbols::Symbols,
     utils::convert::Converter,
     value,
 };
 
+pub use expr::{Condition, Message};
+
+mod expr;
+
 #[derive(Default)]
 pub struct Breakpoints {
     breakpoints: Vec<Breakpoint>,
     and: bool,
+    logs: Logs,
 }
 
 impl Breakpoints {
@@ -19,6 +29,7 @@ impl Breakpoints {
         Self {
             breakpoints,
             and,
+            logs: Logs::default(),
         }
     }
 
@@ -30,7 +41,9 @@ impl Breakpoints {
 }
 
 #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[derive(Default)]
 pub enum Value {
+    #[default]
     Any,
     Eq(u8),
     And(u8),
@@ -39,20 +52,10 @@ pub enum Value {
 
 impl PartialEq for Value {
     fn eq(&self, other: &Self) -> bool {
-        use Value::*;
-        match (self, other) {
-            (Any, Any) => true,
-            (Eq(_), Eq(_)) => true,
-            (And(_), And(_)) => true,
-            (Not(_), Not(_)) => true,
-            _ => false
-        }
+        std::mem::discriminant(self) == std::mem::discriminant(other)
     }
 }
 
-impl Default for Value {
-    fn default() -> Self { Self::Any }
-}
 
 impl PartialEq<u8> for Value {
     fn eq(&self, other: &u8) -> bool {
@@ -79,7 +82,7 @@ impl Value {
 
     pub fn parse(&mut self, input: &mut String) {
         match self {
-            Value::Eq(v) | Value::And(v) | Value::Not(v) => *v = u8::convert(&input),
+            Value::Eq(v) | Value::And(v) | Value::Not(v) => *v = u8::convert(input),
             _ => {}
         }
     }
@@ -96,34 +99,74 @@ impl std::fmt::Display for Value {
     }
 }
 
+/// Machine state read by the breakpoints, besides the cpu.
+pub trait Inspect {
+    fn banks(&self) -> Banks;
+    /// Reads memory like the debugger does, without side effects.
+    fn peek(&self, addr: u16) -> u8;
+}
+
+/// Banks mapped when the breakpoints are checked.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub struct Banks {
+    pub rom: u16,
+    pub ram: u16,
+    pub wram: u16,
+    pub vram: u16,
+}
+
+impl Banks {
+    /// Bank mapped at `addr`, 0 in the areas without banks.
+    pub fn of(&self, addr: u16) -> u16 {
+        match addr {
+            0x4000..=0x7FFF => self.rom,
+            0x8000..=0x9FFF => self.vram,
+            0xA000..=0xBFFF => self.ram,
+            0xD000..=0xDFFF => self.wram,
+            _ => 0,
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct Access {
     addr: u16,
     kind: super::io::Access,
     value: Value,
+    /// only matches while this bank is mapped at `addr`
+    #[serde(default)]
+    bank: Option<u16>,
 }
 
 impl Access {
     fn new(addr: u16, kind: super::io::Access, value: Value) -> Self {
-        Self { addr, kind, value }
+        Self { addr, kind, value, bank: None }
+    }
+
+    pub fn in_bank(mut self, bank: u16) -> Self {
+        self.bank = Some(bank);
+        self
     }
 
     pub fn read(addr: u16, value: Value) -> Self { Access::new(addr, super::io::Access::R, value) }
     pub fn write(addr: u16, value: Value) -> Self { Access::new(addr, super::io::Access::W, value) }
     pub fn rw(addr: u16, value: Value) -> Self { Access::new(addr, super::io::Access::RW, value) }
 
-    pub fn matches(&self, op: Op) -> bool {
+    pub fn matches(&self, op: Op, machine: &impl Inspect) -> bool {
         use super::io::Access as Kind;
         match (self.kind, op) {
             (Kind::R, Op::Read(addr, v)) |
             (Kind::RW | Kind::W, Op::Write(addr, v))
-            if addr == self.addr => self.value == v,
+            if addr == self.addr => self.value == v && self.bank.map_or(true, |bank| machine.banks().of(addr) == bank),
             _ => false
         }
     }
 
-    pub fn format(&self) -> String {
-        format!("{:#06X}{:?} {}", self.addr, self.kind, self.value)
+    pub fn format(&self, symbols: &Symbols) -> String {
+        match self.bank {
+            Some(bank) => format!("{bank:02X}:{}{:?} {}", symbols.format(self.addr, Some(bank)), self.kind, self.value),
+            None => format!("{}{:?} {}", symbols.format(self.addr, None), self.kind, self.value),
+        }
     }
 }
 
@@ -134,24 +177,27 @@ pub enum Break {
     Instructions(usize),
     Instruction(Opcode),
     Register(Reg, value::Value),
+    /// PC reaching an address while a given bank is mapped there
+    Banked(u16, u16),
 }
 
 impl Break {
-    pub fn tick(&mut self, runner: &impl Cpu, last: Option<Op>) -> bool {
+    pub fn tick(&mut self, runner: &impl Cpu, last: Option<Op>, machine: &impl Inspect) -> bool {
         match self {
             Break::Cycles(n) if *n == 0 => true,
             Break::Cycles(n) => {
-                *n = *n - 1;
+                *n -= 1;
                 false
             }
             Break::Instruction(op) if runner.done() && runner.previous() == *op => true,
             Break::Instructions(n) if runner.done() && *n == 0 => true,
             Break::Instructions(n) if runner.done() => {
-                *n = *n - 1;
+                *n -= 1;
                 *n == 0
             }
             Break::Register(r, v) if runner.done() && runner.register(*r) == *v => true,
-            Break::Access(access) if last.is_some() => access.matches(last.unwrap()),
+            Break::Banked(bank, addr) if runner.done() && runner.register(Reg::PC).u16() == *addr => machine.banks().of(*addr) == *bank,
+            Break::Access(access) if last.is_some() => access.matches(last.unwrap(), machine),
             _ => false
         }
     }
@@ -161,24 +207,84 @@ impl Break {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Breakpoint {
     kind: Break,
     once: bool,
     pub enabled: bool,
+    #[serde(default)]
+    condition: Option<Condition>,
+    /// logged on each hit instead of stopping
+    #[serde(default)]
+    message: Option<Message>,
+    /// number of hits which neither stop nor log
+    #[serde(default)]
+    pub ignore: usize,
+    #[serde(skip)]
+    hits: usize,
+    /// set by a remote debugger, which owns it: it is neither saved nor part of the AND
+    #[serde(skip)]
+    remote: bool,
 }
 
 impl Breakpoint {
     fn new(kind: Break, once: bool) -> Self {
-        Self { kind, once, enabled: true }
+        Self { kind, once, enabled: true, condition: None, message: None, ignore: 0, hits: 0, remote: false }
+    }
+}
+
+/// Hits aside, they change as the game runs.
+impl PartialEq for Breakpoint {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind && self.once == other.once && self.enabled == other.enabled
+            && self.condition == other.condition && self.message == other.message && self.ignore == other.ignore
+            && self.remote == other.remote
     }
 }
 
 impl Breakpoint {
-    pub fn tick(&mut self, runner: &impl Cpu, last: Option<Op>) -> (bool, bool) {
-        (self.once, self.kind.tick(runner, last) && self.enabled)
+    /// The condition is only evaluated when the breakpoint itself is hit, and the hits are
+    /// counted once it holds. Logpoints never stop. Returns whether the breakpoint is done
+    /// (a temporary one that fired) and whether it stops the emulation.
+    pub fn tick(&mut self, runner: &impl Cpu, last: Option<Op>, machine: &impl Inspect, logs: &mut Logs) -> (bool, bool) {
+        let hit = self.kind.tick(runner, last, machine) && self.enabled
+            && self.condition.as_ref().map_or(true, |x| x.eval(runner, machine));
+        if !hit { return (false, false); }
+        self.hits += 1;
+        if self.hits <= self.ignore { return (false, false); }
+        match &self.message {
+            Some(message) => {
+                logs.push(message.format(runner, machine));
+                (self.once, false)
+            }
+            None => (self.once, true),
+        }
     }
 
+    pub fn when(mut self, condition: Condition) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+
+    /// Turns the breakpoint into a logpoint.
+    pub fn log(mut self, message: Message) -> Self {
+        self.message = Some(message);
+        self
+    }
+
+    pub fn ignoring(mut self, hits: usize) -> Self {
+        self.ignore = hits;
+        self
+    }
+
+    pub fn condition(&self) -> Option<&Condition> { self.condition.as_ref() }
+
+    pub fn logpoint(&self) -> bool { self.message.is_some() }
+
+    pub fn hits(&self) -> usize { self.hits }
+
+    pub fn reset_hits(&mut self) { self.hits = 0; }
+
     pub fn pause() -> Self { Self::cycles(0) }
     pub fn step() -> Self { Self::instructions(1) }
 
@@ -196,6 +302,11 @@ impl Breakpoint {
         Self::new(Break::address(addr), false)
     }
 
+    /// Breaks at `addr` only while `bank` is mapped there, see [`Banks::of`].
+    pub fn banked(bank: u16, addr: u16) -> Self {
+        Self::new(Break::Banked(bank, addr), false)
+    }
+
     pub fn access(access: Access) -> Self { Self::new(Break::Access(access), false) }
 
     pub fn register(reg: Reg, value: value::Value) -> Self {
@@ -211,36 +322,57 @@ impl Breakpoint {
         self
     }
 
+    pub fn remote(mut self) -> Self {
+        self.remote = true;
+        self
+    }
+
     pub fn temp(&self) -> bool { self.once }
 
-    pub fn display(&self) -> String {
-        match self.kind {
+    pub fn is_remote(&self) -> bool { self.remote }
+
+    pub fn display(&self, symbols: &Symbols) -> String {
+        let name = match self.kind {
             Break::Cycles(_) => unreachable!(),
             Break::Instructions(_) => unreachable!(),
+            Break::Register(Reg::PC, value) => format!("PC == {}", symbols.format(value.u16(), None)),
             Break::Register(reg, value) => format!("{reg:?} == {value:#06x}"),
+            Break::Banked(bank, addr) => match symbols.label(addr, Some(bank)) {
+                Some(label) => format!("PC == {label} ({bank:02X}:{addr:04X})"),
+                None => format!("PC == {bank:02X}:{addr:04X}"),
+            },
             Break::Instruction(op) => crate::opcodes::dbg::dbg_opcodes(op).1.to_string(),
-            Break::Access(access) => access.format(),
+            Break::Access(access) => access.format(symbols),
+        };
+        let name = match &self.condition {
+            Some(condition) => format!("{name} if {condition}"),
+            None => name,
+        };
+        match &self.message {
+            Some(message) => format!("{name} log \"{message}\""),
+            None => name,
         }
     }
 }
 
 impl Breakpoints {
-    pub fn tick(&mut self, cpu: &impl Cpu, last: Option<Op>) -> bool {
+    pub fn tick(&mut self, cpu: &impl Cpu, last: Option<Op>, machine: &impl Inspect) -> bool {
         let mut stop = false;
         if self.and {
-            let mut all_match = !self.breakpoints.is_empty();
+            let anded = |bp: &Breakpoint| !bp.logpoint() && !bp.is_remote();
+            let mut all_match = self.breakpoints.iter().any(anded);
             self.breakpoints.retain_mut(|bp| {
-                let (once, res) = bp.tick(cpu, last);
-                if once { stop |= res; }
-                all_match &= res;
-                !(once && res)
+                let (done, res) = bp.tick(cpu, last, machine, &mut self.logs);
+                if bp.temp() || bp.is_remote() { stop |= res; }
+                if anded(bp) { all_match &= res; }
+                !done
             });
             stop |= all_match
         } else {
             self.breakpoints.retain_mut(|bp| {
-                let (once, res) = bp.tick(cpu, last);
+                let (done, res) = bp.tick(cpu, last, machine, &mut self.logs);
                 stop |= res;
-                !(once && res)
+                !done
             });
         }
         !stop
@@ -248,6 +380,10 @@ impl Breakpoints {
 
     pub fn bp_mut(&mut self) -> &mut Vec<Breakpoint> { &mut self.breakpoints }
 
+    pub fn logs(&self) -> &Logs { &self.logs }
+
+    pub fn logs_mut(&mut self) -> &mut Logs { &mut self.logs }
+
     pub fn pause(&mut self) {
         self.breakpoints.push(Breakpoint::pause());
     }
@@ -263,3 +399,60 @@ impl Breakpoints {
         self.and = state;
     }
 }
+
+/// Messages of the logpoints: the last ones for the debugger, all of them in the log file if any.
+#[derive(Default)]
+pub struct Logs {
+    lines: VecDeque<String>,
+    file: Option<(PathBuf, LineWriter<File>)>,
+}
+
+impl Logs {
+    const KEPT: usize = 1000;
+
+    pub fn push(&mut self, line: String) {
+        let failed = match &mut self.file {
+            Some((path, file)) => writeln!(file, "{line}")
+                .map_err(|e| log::warn!("cannot write to the logpoints file {path:?}: {e}"))
+                .is_err(),
+            None => false,
+        };
+        if failed { self.file = None; }
+        if self.lines.len() == Self::KEPT { self.lines.pop_front(); }
+        self.lines.push_back(line);
+    }
+
+    /// Also appends the messages to `path`.
+    pub fn open(&mut self, path: PathBuf) {
+        match OpenOptions::new().create(true).append(true).open(&path) {
+            Ok(file) => self.file = Some((path, LineWriter::new(file))),
+            Err(e) => log::warn!("cannot open the logpoints file {path:?}: {e}"),
+        }
+    }
+
+    pub fn close(&mut self) { self.file = None; }
+
+    pub fn path(&self) -> Option<&Path> { self.file.as_ref().map(|(path, _)| path.as_path()) }
+
+    pub fn lines(&self) -> &VecDeque<String> { &self.lines }
+
+    pub fn clear(&mut self) { self.lines.clear(); }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn banks_of() {
+        let banks = Banks { rom: 5, ram: 2, wram: 3, vram: 1 };
+        assert_eq!(banks.of(0x3FFF), 0);
+        assert_eq!(banks.of(0x4000), 5);
+        assert_eq!(banks.of(0x7FFF), 5);
+        assert_eq!(banks.of(0x8000), 1);
+        assert_eq!(banks.of(0xA000), 2);
+        assert_eq!(banks.of(0xC000), 0);
+        assert_eq!(banks.of(0xDFFF), 3);
+        assert_eq!(banks.of(0xFF80), 0);
+    }
+}
@@ -0,0 +1,501 @@
+//! Conditions attached to a breakpoint, such as `A == 0x3C && [HL] & 0x80 && LY > 100 && bank == 3`,
+//! and the messages of the logpoints, which interpolate the same expressions.
+//!
+//! Operands are numbers (decimal, `0x` or `$` hexadecimal, `0b` binary), the cpu registers,
+//! the flags `ZF`, `NF`, `HF` and `CF`, the IO registers by name, the mapped banks (`bank` for
+//! the ROM, `ram_bank`, `wram_bank` and `vram_bank`) and memory bytes as `[addr]`.
+//! Bitwise operators bind tighter than comparisons, which bind tighter than `&&` and `||`.
+
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+use crate::cpu::{Cpu, Reg, Value};
+use crate::io::IO;
+
+use super::Inspect;
+
+const REGISTERS: [Reg; 14] = [
+    Reg::A, Reg::F, Reg::B, Reg::C, Reg::D, Reg::E, Reg::H, Reg::L,
+    Reg::AF, Reg::BC, Reg::DE, Reg::HL, Reg::SP, Reg::PC,
+];
+
+/// Longest first, so that `<=` is not read as `<`.
+const OPERATORS: [&str; 16] = ["||", "&&", "==", "!=", "<=", ">=", "<<", ">>", "<", ">", "|", "^", "&", "+", "-", "!"];
+
+#[derive(Copy, Clone, Debug)]
+enum Bin { Or, And, Eq, Ne, Lt, Le, Gt, Ge, BitOr, BitXor, BitAnd, Shl, Shr, Add, Sub }
+
+impl Bin {
+    fn from(op: &str) -> Option<Self> {
+        Some(match op {
+            "||" => Bin::Or,
+            "&&" => Bin::And,
+            "==" => Bin::Eq,
+            "!=" => Bin::Ne,
+            "<" => Bin::Lt,
+            "<=" => Bin::Le,
+            ">" => Bin::Gt,
+            ">=" => Bin::Ge,
+            "|" => Bin::BitOr,
+            "^" => Bin::BitXor,
+            "&" => Bin::BitAnd,
+            "<<" => Bin::Shl,
+            ">>" => Bin::Shr,
+            "+" => Bin::Add,
+            "-" => Bin::Sub,
+            _ => return None,
+        })
+    }
+
+    fn precedence(self) -> u8 {
+        match self {
+            Bin::Or => 1,
+            Bin::And => 2,
+            Bin::Eq | Bin::Ne | Bin::Lt | Bin::Le | Bin::Gt | Bin::Ge => 3,
+            Bin::BitOr => 4,
+            Bin::BitXor => 5,
+            Bin::BitAnd => 6,
+            Bin::Shl | Bin::Shr => 7,
+            Bin::Add | Bin::Sub => 8,
+        }
+    }
+
+    fn apply(self, a: i64, b: i64) -> i64 {
+        match self {
+            Bin::Or => (a != 0 || b != 0) as i64,
+            Bin::And => (a != 0 && b != 0) as i64,
+            Bin::Eq => (a == b) as i64,
+            Bin::Ne => (a != b) as i64,
+            Bin::Lt => (a < b) as i64,
+            Bin::Le => (a <= b) as i64,
+            Bin::Gt => (a > b) as i64,
+            Bin::Ge => (a >= b) as i64,
+            Bin::BitOr => a | b,
+            Bin::BitXor => a ^ b,
+            Bin::BitAnd => a & b,
+            Bin::Shl => a.checked_shl(b as u32).unwrap_or(0),
+            Bin::Shr => a.checked_shr(b as u32).unwrap_or(0),
+            Bin::Add => a.wrapping_add(b),
+            Bin::Sub => a.wrapping_sub(b),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Expr {
+    Num(i64),
+    Reg(Reg),
+    /// bit of the F register
+    Flag(u8),
+    /// bank mapped at an address of its area
+    Bank(u16),
+    Mem(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(Bin, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, cpu: &impl Cpu, machine: &impl Inspect) -> i64 {
+        match self {
+            Expr::Num(n) => *n,
+            Expr::Reg(reg) => match cpu.register(*reg) {
+                Value::U8(v) => v as i64,
+                Value::U16(v) => v as i64,
+            },
+            Expr::Flag(bit) => (cpu.register(Reg::F).u8() >> bit & 1) as i64,
+            Expr::Bank(addr) => machine.banks().of(*addr) as i64,
+            Expr::Mem(addr) => machine.peek(addr.eval(cpu, machine) as u16) as i64,
+            Expr::Not(e) => (e.eval(cpu, machine) == 0) as i64,
+            Expr::Binary(Bin::And, a, b) => (a.eval(cpu, machine) != 0 && b.eval(cpu, machine) != 0) as i64,
+            Expr::Binary(Bin::Or, a, b) => (a.eval(cpu, machine) != 0 || b.eval(cpu, machine) != 0) as i64,
+            Expr::Binary(op, a, b) => op.apply(a.eval(cpu, machine), b.eval(cpu, machine)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Num(i64),
+    Name(String),
+    Op(&'static str),
+    Open(char),
+    Close(char),
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Num(n) => write!(f, "{n}"),
+            Token::Name(name) => write!(f, "{name}"),
+            Token::Op(op) => write!(f, "{op}"),
+            Token::Open(c) | Token::Close(c) => write!(f, "{c}"),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut rest = source.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let len = if c.is_ascii_alphanumeric() || c == '_' || c == '$' {
+            let len = rest.find(|x: char| !(x.is_ascii_alphanumeric() || x == '_' || x == '$')).unwrap_or(rest.len());
+            let word = &rest[..len];
+            tokens.push(if c.is_ascii_digit() || c == '$' { Token::Num(number(word)?) } else { Token::Name(word.to_string()) });
+            len
+        } else if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+            tokens.push(Token::Op(op));
+            op.len()
+        } else {
+            tokens.push(match c {
+                '(' | '[' => Token::Open(c),
+                ')' | ']' => Token::Close(c),
+                _ => return Err(format!("unexpected character '{c}'")),
+            });
+            1
+        };
+        rest = rest[len..].trim_start();
+    }
+    Ok(tokens)
+}
+
+fn number(word: &str) -> Result<i64, String> {
+    let lower = word.to_ascii_lowercase();
+    let parsed = if let Some(hex) = lower.strip_prefix("0x").or_else(|| lower.strip_prefix('$')) {
+        i64::from_str_radix(hex, 16)
+    } else if let Some(bin) = lower.strip_prefix("0b") {
+        i64::from_str_radix(bin, 2)
+    } else {
+        lower.parse()
+    };
+    parsed.map_err(|_| format!("invalid number {word}"))
+}
+
+fn name(name: &str) -> Result<Expr, String> {
+    let upper = name.to_ascii_uppercase();
+    if let Some(reg) = REGISTERS.into_iter().find(|x| x.name() == upper) {
+        return Ok(Expr::Reg(reg));
+    }
+    Ok(match upper.as_str() {
+        "ZF" => Expr::Flag(7),
+        "NF" => Expr::Flag(6),
+        "HF" => Expr::Flag(5),
+        "CF" => Expr::Flag(4),
+        "BANK" => Expr::Bank(0x4000),
+        "RAM_BANK" => Expr::Bank(0xA000),
+        "WRAM_BANK" => Expr::Bank(0xD000),
+        "VRAM_BANK" => Expr::Bank(0x8000),
+        _ => {
+            let io = (0xFF00u16..=0xFFFF).filter_map(|x| IO::try_from(x).ok()).find(|x| x.name() == upper);
+            match io {
+                Some(io) => Expr::Mem(Box::new(Expr::Num(io as u16 as i64))),
+                None => return Err(format!("unknown name {name}")),
+            }
+        }
+    })
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn binary(&self) -> Option<Bin> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) => Bin::from(op),
+            _ => None,
+        }
+    }
+
+    /// Precedence climbing, operators of the same precedence are left associative.
+    fn expr(&mut self, min: u8) -> Result<Expr, String> {
+        let mut lhs = self.operand()?;
+        while let Some(op) = self.binary().filter(|x| x.precedence() >= min) {
+            self.pos += 1;
+            let rhs = self.expr(op.precedence() + 1)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn operand(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Num(n)) => Ok(Expr::Num(n)),
+            Some(Token::Name(x)) => name(&x),
+            Some(Token::Op("!")) => Ok(Expr::Not(Box::new(self.operand()?))),
+            Some(Token::Open(c)) => {
+                let e = self.expr(0)?;
+                let close = if c == '(' { ')' } else { ']' };
+                match self.next() {
+                    Some(Token::Close(x)) if x == close => {}
+                    Some(token) => return Err(format!("expected '{close}', found {token}")),
+                    None => return Err(format!("missing '{close}'")),
+                }
+                Ok(if c == '[' { Expr::Mem(Box::new(e)) } else { e })
+            }
+            Some(token) => Err(format!("unexpected {token}")),
+            None => Err("unexpected end".to_string()),
+        }
+    }
+}
+
+fn parse(source: &str) -> Result<Expr, String> {
+    let mut parser = Parser { tokens: tokenize(source)?, pos: 0 };
+    let expr = parser.expr(0)?;
+    match parser.next() {
+        Some(token) => Err(format!("unexpected {token}")),
+        None => Ok(expr),
+    }
+}
+
+/// Expression checked when its breakpoint is hit, the breakpoint only stops when it isn't 0.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Condition {
+    source: String,
+    expr: Expr,
+}
+
+impl Condition {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        Ok(Self { source: source.trim().to_string(), expr: parse(source)? })
+    }
+
+    pub fn eval(&self, cpu: &impl Cpu, machine: &impl Inspect) -> bool {
+        self.expr.eval(cpu, machine) != 0
+    }
+}
+
+impl PartialEq for Condition {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl TryFrom<String> for Condition {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+impl From<Condition> for String {
+    fn from(value: Condition) -> Self {
+        value.source
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Part {
+    Text(String),
+    /// expression and its format: `x`, `d` or `b`
+    Value(Expr, char),
+}
+
+/// Text of a logpoint: `{expr}` is replaced by its value in hexadecimal, `{expr:d}` in decimal
+/// and `{expr:b}` in binary, `{{` and `}}` are braces.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Message {
+    source: String,
+    parts: Vec<Part>,
+}
+
+impl Message {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut parts = vec![];
+        let mut text = String::new();
+        let mut chars = source.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' | '}' if chars.peek() == Some(&c) => {
+                    chars.next();
+                    text.push(c);
+                }
+                '{' => {
+                    let mut inner = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(x) => inner.push(x),
+                            None => return Err("missing '}'".to_string()),
+                        }
+                    }
+                    let (expr, format) = match inner.rsplit_once(':') {
+                        Some((expr, f @ ("x" | "d" | "b"))) => (expr, f.chars().next().unwrap_or('x')),
+                        _ => (inner.as_str(), 'x'),
+                    };
+                    if !text.is_empty() { parts.push(Part::Text(std::mem::take(&mut text))); }
+                    parts.push(Part::Value(parse(expr).map_err(|e| format!("{{{inner}}}: {e}"))?, format));
+                }
+                '}' => return Err("unmatched '}'".to_string()),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() { parts.push(Part::Text(text)); }
+        Ok(Self { source: source.to_string(), parts })
+    }
+
+    pub fn format(&self, cpu: &impl Cpu, machine: &impl Inspect) -> String {
+        self.parts.iter().map(|part| match part {
+            Part::Text(text) => text.clone(),
+            Part::Value(expr, 'd') => expr.eval(cpu, machine).to_string(),
+            Part::Value(expr, radix) => {
+                let value = expr.eval(cpu, machine);
+                let sign = if value < 0 { "-" } else { "" };
+                match radix {
+                    'b' => format!("{sign}{:#b}", value.unsigned_abs()),
+                    _ => format!("{sign}{:#X}", value.unsigned_abs()),
+                }
+            }
+        }).collect()
+    }
+}
+
+impl PartialEq for Message {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Display for Message {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl TryFrom<String> for Message {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+impl From<Message> for String {
+    fn from(value: Message) -> Self {
+        value.source
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::breakpoints::{Banks, Breakpoint, Breakpoints};
+    use crate::cpu::Opcode;
+
+    use super::*;
+
+    struct Stub {
+        ly: u8,
+        bank: u16,
+    }
+
+    impl Cpu for Stub {
+        fn done(&self) -> bool { true }
+
+        fn previous(&self) -> Opcode { Opcode::Nop }
+
+        fn register(&self, reg: Reg) -> Value {
+            match reg {
+                Reg::A => Value::U8(0x3C),
+                Reg::F => Value::U8(0x80),
+                Reg::HL => Value::U16(0xC000),
+                Reg::AF | Reg::BC | Reg::DE | Reg::SP | Reg::PC => Value::U16(0),
+                _ => Value::U8(0),
+            }
+        }
+    }
+
+    impl Inspect for Stub {
+        fn banks(&self) -> Banks { Banks { rom: self.bank, ..Default::default() } }
+
+        fn peek(&self, addr: u16) -> u8 {
+            match addr {
+                0xC000 => 0x81,
+                0xFF44 => self.ly,
+                _ => 0,
+            }
+        }
+    }
+
+    const STUB: Stub = Stub { ly: 120, bank: 3 };
+
+    fn eval(source: &str, stub: &Stub) -> bool {
+        Condition::parse(source).unwrap().eval(stub, stub)
+    }
+
+    #[test]
+    fn condition() {
+        let source = "A == 0x3C && [HL] & 0x80 && LY > 100 && bank == 3";
+        assert!(eval(source, &STUB));
+        assert!(!eval(source, &Stub { ly: 100, ..STUB }));
+        assert!(!eval(source, &Stub { bank: 2, ..STUB }));
+        assert!(eval("ZF && !CF", &STUB));
+    }
+
+    #[test]
+    fn comparisons() {
+        assert!(eval("LY <= 120", &STUB));
+        assert!(!eval("LY < 120", &STUB));
+        assert!(eval("LY >= 120", &STUB));
+        assert!(!eval("LY > 120", &STUB));
+    }
+
+    #[test]
+    fn numbers() {
+        assert!(eval("$3C == 60", &STUB));
+        assert!(eval("0b00111100 == 0x3C", &STUB));
+        assert!(eval("A == $3c", &STUB));
+        assert!(Condition::parse("0b102").is_err());
+    }
+
+    #[test]
+    fn messages() {
+        let message = Message::parse("{{A}} = {A}, LY = {LY:d}, ZF = {ZF:b}").unwrap();
+        assert_eq!(message.format(&STUB, &STUB), "{A} = 0x3C, LY = 120, ZF = 0b1");
+        assert_eq!(Message::parse("}}{{").unwrap().format(&STUB, &STUB), "}{");
+        assert!(Message::parse("{A").is_err());
+        assert!(Message::parse("A}").is_err());
+        assert!(Message::parse("{FOO}").is_err());
+    }
+
+    #[test]
+    fn signed_messages() {
+        let message = Message::parse("{A - 0x40} {A - 0x40:d} {A - 0x40:b}").unwrap();
+        assert_eq!(message.format(&STUB, &STUB), "-0x4 -4 -0b100");
+    }
+
+    #[test]
+    fn once_logpoint() {
+        let logpoint = Breakpoint::address(0).log(Message::parse("{LY:d}").unwrap()).once();
+        let mut breakpoints = Breakpoints::new(vec![logpoint], false);
+        assert!(breakpoints.tick(&STUB, None, &STUB));
+        assert!(breakpoints.tick(&STUB, None, &STUB));
+        assert_eq!(breakpoints.logs().lines().len(), 1);
+        assert!(breakpoints.take().is_empty());
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Condition::parse("FOO == 1").unwrap_err(), "unknown name FOO");
+        assert!(Condition::parse("[HL").is_err());
+        assert!(Condition::parse("HL]").is_err());
+        assert!(Condition::parse("(A == 1").is_err());
+        assert!(Condition::parse("A == 1)").is_err());
+        assert!(Condition::parse("[HL)").is_err());
+    }
+}
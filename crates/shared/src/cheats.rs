@@ -0,0 +1,127 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Error, Clone, Eq, PartialEq)]
+pub enum Error {
+    #[error("invalid cheat code: {0}")]
+    Invalid(String),
+    #[error("unsupported GameShark code type (cartridge ram bank switch): {0}")]
+    Unsupported(String),
+}
+
+/// A single decoded code.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Code {
+    /// Game Genie: cartridge rom reads at `addr` return `value`, only if the original byte is `compare` when given.
+    Genie { addr: u16, value: u8, compare: Option<u8> },
+    /// GameShark: `value` is written at `addr` every vblank. `bank` is the code type byte,
+    /// `0x9X` writes to work ram bank `X`, others target the currently mapped bank.
+    Shark { bank: u8, addr: u16, value: u8 },
+}
+
+impl Code {
+    fn genie(code: &str) -> Result<Self, Error> {
+        let digits = code.chars()
+            .filter(|c| *c != '-')
+            .map(|c| c.to_digit(16).map(|d| d as u16))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| Error::Invalid(code.to_string()))?;
+        if digits.len() != 6 && digits.len() != 9 { return Err(Error::Invalid(code.to_string())); }
+        let value = (digits[0] << 4 | digits[1]) as u8;
+        let addr = ((digits[5] ^ 0xF) << 12) | (digits[2] << 8) | (digits[3] << 4) | digits[4];
+        let compare = (digits.len() == 9).then(|| ((digits[6] << 4 | digits[8]) as u8).rotate_right(2) ^ 0xBA);
+        if addr >= 0x8000 { return Err(Error::Invalid(code.to_string())); }
+        Ok(Code::Genie { addr, value, compare })
+    }
+
+    fn shark(code: &str) -> Result<Self, Error> {
+        let raw = u32::from_str_radix(code, 16).map_err(|_| Error::Invalid(code.to_string()))?;
+        let [bank, value, lo, hi] = raw.to_be_bytes();
+        if bank & 0xF0 == 0x80 { return Err(Error::Unsupported(code.to_string())); }
+        Ok(Code::Shark { bank, value, addr: u16::from_le_bytes([lo, hi]) })
+    }
+}
+
+impl FromStr for Code {
+    type Err = Error;
+
+    /// `ABC-DEF` / `ABC-DEF-GHI` for Game Genie, `TTVVAAAA` (address little endian) for GameShark.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let code = s.trim();
+        if code.len() == 8 && !code.contains('-') { Code::shark(code) } else { Code::genie(code) }
+    }
+}
+
+/// A named list of codes, separated by `+` or spaces.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Cheat {
+    pub name: String,
+    pub code: String,
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+impl Cheat {
+    pub fn new(name: String, code: String) -> Result<Self, Error> {
+        let cheat = Self { name, code, enabled: true };
+        cheat.codes()?;
+        Ok(cheat)
+    }
+
+    pub fn codes(&self) -> Result<Vec<Code>, Error> {
+        self.code.split(|c: char| c == '+' || c.is_whitespace())
+            .filter(|x| !x.is_empty())
+            .map(Code::from_str)
+            .collect()
+    }
+}
+
+impl Display for Cheat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{} ({})", self.name, self.code))
+    }
+}
+
+/// Enabled codes of `cheats`, invalid ones are skipped.
+pub fn active(cheats: &[Cheat]) -> impl Iterator<Item=Code> + '_ {
+    cheats.iter()
+        .filter(|x| x.enabled)
+        .flat_map(|x| x.codes().unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_game_genie() {
+        assert_eq!("3E1-6CF".parse(), Ok(Code::Genie { addr: 0x016C, value: 0x3E, compare: None }));
+        assert_eq!("3e1-6cf-2ab".parse(), Ok(Code::Genie { addr: 0x016C, value: 0x3E, compare: Some(0x70) }));
+        assert_eq!("3E16CE".parse(), Ok(Code::Genie { addr: 0x116C, value: 0x3E, compare: None }));
+        assert!(matches!("000-000".parse::<Code>(), Err(Error::Invalid(_))));
+        assert!(matches!("3E1-6C".parse::<Code>(), Err(Error::Invalid(_))));
+        assert!(matches!("3G1-6CF".parse::<Code>(), Err(Error::Invalid(_))));
+    }
+
+    #[test]
+    fn decodes_game_shark() {
+        assert_eq!("01FF34C2".parse(), Ok(Code::Shark { bank: 0x01, addr: 0xC234, value: 0xFF }));
+        assert_eq!(" 9263A0D1 ".parse(), Ok(Code::Shark { bank: 0x92, addr: 0xD1A0, value: 0x63 }));
+        assert_eq!("81FF34C2".parse::<Code>(), Err(Error::Unsupported("81FF34C2".to_string())));
+        assert!(matches!("01FF34CX".parse::<Code>(), Err(Error::Invalid(_))));
+    }
+
+    #[test]
+    fn splits_cheats() {
+        let cheat = Cheat::new("lives".to_string(), "01FF34C2+3E1-6CF  0163A0D1".to_string()).unwrap();
+        assert_eq!(cheat.codes().unwrap().len(), 3);
+        assert!(Cheat::new("broken".to_string(), "01FF34C2+nope".to_string()).is_err());
+
+        let off = Cheat { enabled: false, ..cheat.clone() };
+        let broken = Cheat { code: "nope".to_string(), ..cheat.clone() };
+        assert_eq!(active(&[cheat, off, broken]).count(), 3);
+    }
+}
@@ -1,8 +1,9 @@
 use serde::{Deserialize, Serialize};
 
-use super::breakpoints::Breakpoints;
+use super::breakpoints::{Banks, Breakpoints};
 use super::cpu::{self, Reg, Value};
 use super::mem::{IOBus, MBCController};
+use super::symbols::Symbols;
 
 pub trait Emulator: ReadAccess + Schedule {}
 
@@ -21,6 +22,7 @@ pub trait Schedule {
     fn breakpoints(&mut self) -> &mut Breakpoints;
     fn play(&mut self);
     fn reset(&mut self);
+    fn running(&self) -> bool;
 
     fn speed(&self) -> i32;
     fn speedup(&mut self);
@@ -32,6 +34,22 @@ pub trait ReadAccess {
     fn get_range(&self, st: u16, len: u16) -> Vec<u8>;
     fn bus(&self) -> Box<&dyn Bus>;
     fn mbc(&self) -> Box<&dyn MBCController>;
+    /// Labels of the cartridge symbol file.
+    fn symbols(&self) -> &Symbols;
+    /// Banks currently mapped.
+    fn banks(&self) -> Banks;
+    /// Number of ROM banks of the cartridge.
+    fn rom_banks(&self) -> usize;
+    /// Content of any ROM bank, mapped or not.
+    fn read_bank(&self, bank: usize) -> Vec<u8>;
+}
+
+/// Edits of the emulated state from a debugger.
+pub trait WriteAccess {
+    /// False when the value does not have the size of the register.
+    fn set_cpu_register(&mut self, reg: Reg, value: Value) -> bool;
+    /// Writes through the cpu bus: writes to the cartridge switch banks.
+    fn write(&mut self, addr: u16, value: u8);
 }
 
 pub trait State {
@@ -11,7 +11,7 @@ mod devices;
 pub const CGB_MODE: u8 = 0x80;
 pub const DMG_MODE: u8 = 0x04;
 
-/// 7	LCD and PPU enable	0=Off, 1=On
+// 7	LCD and PPU enable	0=Off, 1=On
 // 6	Window tile map area	0=9800-9BFF, 1=9C00-9FFF
 // 5	Window enable	0=Off, 1=On
 // 4	BG and Window tile data area	0=8800-97FF, 1=8000-8FFF
@@ -326,7 +326,7 @@ impl IO {
                 if reg & 0x70 != 0 {
                     format!("Channel 1 Sweep\nPace {} Hz\n{}\nSlope (divider): {}", ((reg & 0x70) >> 4) * 128, if reg & 0x8 != 0 { "Subtraction" } else { "Addition" }, 1 << (reg & 0x7))
                 } else {
-                    format!("Channel 1 Sweep\nDisabled (bits 4-6 disabled)")
+                    "Channel 1 Sweep\nDisabled (bits 4-6 disabled)".to_string()
                 }
             ),
             IO::NR11 => Some(format!("Channel 1 Duty & Length\nWave duty {} %\nLength: {} Hz", 12.5 * (1 + ((reg & 0xC0) >> 6)) as f32, 256 * (reg as usize & 0x3F))),
@@ -349,7 +349,7 @@ impl IO {
             IO::JOYP => Some(format!("Joypad Query/Status\nQuery {}\nRight / A {}\nLeft / B {}\nUp / Select {}\nDown / Start {}", ["None", "Direction", "Action", "All"][(reg & 0x30) as usize >> 4], reg & 0x1 == 0, reg & 0x2 == 0, reg & 0x4 == 0, reg & 0x8 == 0)),
             IO::IE => Some(format!("Enable interrupts:\nVBlank (@0x0040): {}\nLCD Stat (@0x0048): {}\nTimer (@0x0050): {}\nSerial (@0x0058): {}\nJoypad (@0x0060): {}", reg & 0x1 != 0, reg & 0x2 != 0, reg & 0x4 != 0, reg & 0x8 != 0, reg & 0x10 != 0)),
             IO::IF => Some(format!("Interrupt flag:\nVBlank (@0x0040): {}\nLCD Stat (@0x0048): {}\nTimer (@0x0050): {}\nSerial (@0x0058): {}\nJoypad (@0x0060): {}", reg & 0x1 != 0, reg & 0x2 != 0, reg & 0x4 != 0, reg & 0x8 != 0, reg & 0x10 != 0)),
-            IO::SB => Some(format!("Serial byte: holds a byte that will (or is being) replaced by another coming from a connected gameboy (bit by bit every cycle)")),
+            IO::SB => Some("Serial byte: holds a byte that will (or is being) replaced by another coming from a connected gameboy (bit by bit every cycle)".to_string()),
             _ => None
         }
     }
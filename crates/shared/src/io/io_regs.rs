@@ -7,6 +7,9 @@ pub struct IORegs {
     palette: Palette,
     cgb: IOReg,
     range: Vec<IOReg>,
+    /// LY always reads 0x90, as expected by the gameboy-doctor traces
+    #[serde(default, skip)]
+    stub_ly: bool,
 }
 
 impl IORegs {
@@ -16,12 +19,13 @@ impl IORegs {
         Self {
             palette,
             cgb: IOReg::rdonly().with_value(cgb as u8),
-            range: (0..128).into_iter().map(|i| {
+            range: (0..128).map(|i| {
                 let (access, value) = IO::try_from(0xFF00 + i)
                     .map(|x| (x.access(), x.default(cgb))).unwrap_or(Default::default());
                 IOReg::with_access(access)
                     .with_value(value)
             }).collect(),
+            stub_ly: false,
         }
     }
 
@@ -89,6 +93,9 @@ impl IORegs {
         self.range[io as u16 as usize - crate::mem::IO as usize].writable()
     }
 
+    /// Makes LY read 0x90 (first line of the vblank), to compare traces with gameboy-doctor.
+    pub fn stub_ly(&mut self, stub: bool) { self.stub_ly = stub; }
+
     pub fn palette(&self) -> Palette { self.palette }
     pub fn set_palette(&mut self, palette: Palette) {
         self.palette = palette;
@@ -96,14 +103,15 @@ impl IORegs {
 }
 
 impl Mem for IORegs {
-    fn read(&self, addr: u16, _absolute: u16) -> u8 {
-        self.range.get(addr as usize).map(|x| x.read()).expect(format!("read outside of IOReg range {addr:#06X}").as_str())
+    fn read(&self, addr: u16, absolute: u16) -> u8 {
+        if self.stub_ly && absolute == IO::LY as u16 { return 0x90; }
+        self.range.get(addr as usize).map(|x| x.read()).unwrap_or_else(|| panic!("read outside of IOReg range {addr:#06X}"))
     }
 
     fn write(&mut self, addr: u16, value: u8, absolute: u16) {
         self.range.get_mut(addr as usize)
             .map(|x| x.write(0, value, absolute)).
-            expect(format!("write outside of IOReg range {addr:#06X}").as_str());
+            unwrap_or_else(|| panic!("write outside of IOReg range {addr:#06X}"));
     }
 
     fn get_range(&self, st: u16, len: u16) -> Vec<u8> {
@@ -112,3 +120,19 @@ impl Mem for IORegs {
         self.range[st..end].iter().map(|x| x.value()).collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stubs_ly() {
+        let mut io = IORegs::init(false, Palette::default());
+        io.io_mut(IO::LY).direct_write(0x12);
+        let ly = IO::LY as u16;
+        assert_eq!(io.read(ly - crate::mem::IO, ly), 0x12);
+        io.stub_ly(true);
+        assert_eq!(io.read(ly - crate::mem::IO, ly), 0x90);
+        assert_eq!(io.io(IO::LY).value(), 0x12);
+    }
+}
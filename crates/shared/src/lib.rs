@@ -1,13 +1,17 @@
+#[cfg(feature = "ui")]
 pub use egui;
 pub use serde;
+#[cfg(feature = "ui")]
 pub use winit;
 
 use crate::input::KeyCat;
 
+#[cfg(feature = "ui")]
 pub mod events {
     pub use super::winit::event::{ElementState, WindowEvent};
 }
 
+#[cfg(feature = "ui")]
 pub mod widgets;
 pub mod utils;
 pub mod mem;
@@ -15,6 +19,8 @@ pub mod io;
 
 pub mod rom;
 pub mod breakpoints;
+pub mod cheats;
+pub mod symbols;
 
 pub mod input;
 pub mod audio_settings;
@@ -33,8 +39,11 @@ pub enum Handle {
     Game,
     Sprites,
     Settings,
+    /// second console, linked to the first one
+    Peer,
 }
 
+#[cfg(feature = "ui")]
 pub type Event<'a> = winit::event::Event<'a, Events>;
 
 #[derive(Debug)]
@@ -56,6 +65,7 @@ pub enum Target {
     GBC,
 }
 
+#[cfg(feature = "ui")]
 pub trait Ui {
     type Ext;
 
@@ -64,6 +74,7 @@ pub trait Ui {
     fn handle(&mut self, _event: &winit::event::Event<Events>, _ctx: &mut egui::Context, _ext: &mut <Self as Ui>::Ext) {}
 }
 
+#[cfg(feature = "ui")]
 impl Ui for () {
     type Ext = ();
 }
@@ -4,16 +4,15 @@ use log::warn;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Serialize, Deserialize)]
+#[derive(Default)]
 pub enum Console {
     GBC,
+    #[default]
     DMG,
     All,
     Other(u8),
 }
 
-impl Default for Console {
-    fn default() -> Self { Console::DMG }
-}
 
 impl Console {
     pub fn cgb_mode(&self, on_gbc: bool) -> bool {
@@ -33,15 +32,14 @@ impl From<u8> for Console {
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Default)]
 pub enum Gameboy {
+    #[default]
     DMG,
     Super,
     Other,
 }
 
-impl Default for Gameboy {
-    fn default() -> Self { Self::DMG }
-}
 
 impl From<u8> for Gameboy {
     fn from(value: u8) -> Self {
@@ -98,7 +96,9 @@ impl Capabilities {
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[repr(u8)]
+#[derive(Default)]
 pub enum Cartridge {
+    #[default]
     Rom,
     RomMbc1,
     RomRamMbc1,
@@ -121,26 +121,31 @@ pub enum Cartridge {
     RomMbc5Mr,
     RomMbc5MrSram,
     RomMbc5MrSramBatt,
+    RomMbc7SensorMrRamBatt,
+    PocketCamera,
+    RomHuc3RamBatt,
+    RomHuc1RamBatt,
     UNSUPPORTED,
 }
 
-impl Default for Cartridge {
-    fn default() -> Self { Cartridge::Rom }
-}
 
 #[derive(Serialize, Deserialize)]
+#[derive(Default)]
 pub enum Mbc {
+    #[default]
     MBC0,
     MBC1,
     MBC2,
     MBC3,
     MBC5,
+    MBC7,
+    HUC1,
+    HUC3,
+    MMM01,
+    CAMERA,
     Unknown,
 }
 
-impl Default for Mbc {
-    fn default() -> Self { Mbc::MBC0 }
-}
 
 impl Cartridge {
     pub fn capabilities(&self) -> Capabilities {
@@ -168,6 +173,10 @@ impl Cartridge {
             Cartridge::RomMbc5Mr => Capabilities(MR),
             Cartridge::RomMbc5MrSram => Capabilities(MR | SRAM),
             Cartridge::RomMbc5MrSramBatt => Capabilities(MR | SRAM | BATT),
+            Cartridge::RomMbc7SensorMrRamBatt => Capabilities(MR | RAM | BATT),
+            Cartridge::PocketCamera => Capabilities(RAM | BATT),
+            Cartridge::RomHuc3RamBatt => Capabilities(RAM | TMR | BATT),
+            Cartridge::RomHuc1RamBatt => Capabilities(RAM | BATT),
             Cartridge::UNSUPPORTED => Capabilities(ROM)
         }
     }
@@ -182,9 +191,9 @@ impl Cartridge {
             Cartridge::RomMbc2Batt => Mbc::MBC2,
             Cartridge::RomRam => Mbc::MBC0,
             Cartridge::RomRamBatt => Mbc::MBC0,
-            Cartridge::RomMmm01 => Mbc::Unknown,
-            Cartridge::RomMmm01Sram => Mbc::Unknown,
-            Cartridge::RomMmm01SramBatt => Mbc::Unknown,
+            Cartridge::RomMmm01 => Mbc::MMM01,
+            Cartridge::RomMmm01Sram => Mbc::MMM01,
+            Cartridge::RomMmm01SramBatt => Mbc::MMM01,
             Cartridge::RomMbc3TmrBatt => Mbc::MBC3,
             Cartridge::RomRamMbc3TmrBatt => Mbc::MBC3,
             Cartridge::RomMbc3 => Mbc::MBC3,
@@ -196,6 +205,10 @@ impl Cartridge {
             Cartridge::RomMbc5Mr => Mbc::MBC5,
             Cartridge::RomMbc5MrSram => Mbc::MBC5,
             Cartridge::RomMbc5MrSramBatt => Mbc::MBC5,
+            Cartridge::RomMbc7SensorMrRamBatt => Mbc::MBC7,
+            Cartridge::PocketCamera => Mbc::CAMERA,
+            Cartridge::RomHuc3RamBatt => Mbc::HUC3,
+            Cartridge::RomHuc1RamBatt => Mbc::HUC1,
             Cartridge::UNSUPPORTED => Mbc::Unknown
         }
     }
@@ -226,6 +239,10 @@ impl From<u8> for Cartridge {
             0x1C => Cartridge::RomMbc5Mr,
             0x1D => Cartridge::RomMbc5MrSram,
             0x1E => Cartridge::RomMbc5MrSramBatt,
+            0x22 => Cartridge::RomMbc7SensorMrRamBatt,
+            0xFC => Cartridge::PocketCamera,
+            0xFE => Cartridge::RomHuc3RamBatt,
+            0xFF => Cartridge::RomHuc1RamBatt,
             _ => Cartridge::UNSUPPORTED
         }
     }
@@ -257,7 +274,7 @@ impl RomSize {
     pub fn mask(&self) -> usize {
         match self.0 {
             n @ 0..=8 => 2usize.pow((n + 1) as u32) - 1,
-            0x52 | 0x53 | 0x54 => 0x7F,
+            0x52..=0x54 => 0x7F,
             n => {
                 warn!("invalid rom size {n}");
                 0
@@ -266,7 +283,7 @@ impl RomSize {
     }
 
     pub fn size(&self) -> usize {
-        self.banks() as usize * RomSize::BANK_SIZE as usize
+        self.banks() * RomSize::BANK_SIZE as usize
     }
 }
 
@@ -304,7 +321,7 @@ impl RamSize {
     }
 
     pub fn size(&self) -> usize {
-        self.banks() as usize * RamSize::BANK_SIZE as usize
+        self.banks() * RamSize::BANK_SIZE as usize
     }
 }
 
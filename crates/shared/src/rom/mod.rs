@@ -5,7 +5,7 @@ use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 
-pub use header::{Capabilities, Header, Mbc};
+pub use header::{Capabilities, Cartridge, Gameboy, Header, Mbc};
 
 mod header;
 
@@ -20,7 +20,7 @@ pub struct Rom {
 
 impl PartialOrd for Rom {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.filename.partial_cmp(&other.filename)
+        Some(self.cmp(other))
     }
 }
 
@@ -38,13 +38,26 @@ impl Rom {
         let mut v = Vec::new();
         let mut file = File::open(path)?;
         file.read_to_end(&mut v)?;
+        Self::from_bytes(f, location, v)
+    }
+
+    /// Rom already in memory, `location` and `filename` only locate its save and cover.
+    pub fn from_bytes(filename: String, location: PathBuf, v: Vec<u8>) -> Result<Self> {
         if v.len() < 0x150 {
             return Err(std::io::Error::new(ErrorKind::InvalidData, "Invalid rom"));
         }
+        let mut header = Header::new(&v[0..=0x14F]);
+        // MMM01 multicarts boot from their last 32KiB, which holds the menu and the real header
+        if let Some(st) = v.len().checked_sub(0x8000) {
+            let menu = Header::new(&v[st..=st + 0x14F]);
+            if matches!(menu.cartridge, Cartridge::RomMmm01 | Cartridge::RomMmm01Sram | Cartridge::RomMmm01SramBatt) {
+                header = menu;
+            }
+        }
         Ok(Self {
-            filename: f,
+            filename,
             location,
-            header: Header::new(&v[0..=0x14F]),
+            header,
             content: v,
             cover: None,
         })
@@ -54,7 +67,37 @@ impl Rom {
         self.content.clone()
     }
 
+    /// Number of 16KiB banks, the last one may be incomplete.
+    pub fn banks(&self) -> usize {
+        (self.content.len() + 0x3FFF) / 0x4000
+    }
+
+    /// Content of a 16KiB bank, the bank number wraps around like the cartridge address lines do.
+    pub fn bank(&self, bank: usize) -> &[u8] {
+        let st = (bank % self.banks()) * 0x4000;
+        &self.content[st..(st + 0x4000).min(self.content.len())]
+    }
+
     pub fn find_roms<P: AsRef<std::path::Path>>(_path: P) -> Vec<Self> {
         vec![]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn banks() {
+        let mut raw = vec![0; 2 * 0x4000 + 0x100];
+        raw.chunks_mut(0x4000).enumerate().for_each(|(n, bank)| bank[0] = n as u8);
+        let rom = Rom::from_bytes("test".to_string(), Default::default(), raw).unwrap();
+        assert_eq!(rom.banks(), 3);
+        assert_eq!(rom.bank(1)[0], 1);
+        assert_eq!(rom.bank(1).len(), 0x4000);
+        // the last bank is incomplete
+        assert_eq!(rom.bank(2).len(), 0x100);
+        // bank numbers wrap around
+        assert_eq!(rom.bank(4)[0], 1);
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::rom::Rom;
+
+/// Labels of a RGBDS or no$gmb symbol file: `bank:address label` lines, `;` comments.
+#[derive(Default, Clone, Debug)]
+pub struct Symbols {
+    labels: BTreeMap<(u16, u16), String>,
+    names: HashMap<String, (u16, u16)>,
+}
+
+impl Symbols {
+    /// Reads `<rom>.sym` next to the cartridge, empty when there is none.
+    pub fn load(rom: &Rom) -> Self {
+        let path = rom.location.join(format!("{}.sym", rom.filename));
+        match std::fs::read_to_string(&path) {
+            Ok(text) => {
+                let symbols = Self::parse(&text);
+                log::info!("{} symbols loaded from {path:?}", symbols.labels.len());
+                symbols
+            }
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let mut symbols = Self::default();
+        for line in text.lines() {
+            let line = line.split(';').next().unwrap_or_default().trim();
+            let Some((at, name)) = line.split_once(char::is_whitespace) else { continue; };
+            let Some((bank, addr)) = at.split_once(':') else { continue; };
+            let (Ok(bank), Ok(addr)) = (u16::from_str_radix(bank, 16), u16::from_str_radix(addr, 16)) else { continue; };
+            let name = name.trim().to_string();
+            symbols.names.entry(name.clone()).or_insert((bank, addr));
+            symbols.labels.entry((bank, addr)).or_insert(name);
+        }
+        symbols
+    }
+
+    pub fn is_empty(&self) -> bool { self.labels.is_empty() }
+
+    /// Label at `addr` in `bank`, or in any bank when it isn't known.
+    pub fn label(&self, addr: u16, bank: Option<u16>) -> Option<&str> {
+        match bank {
+            Some(bank) => self.labels.get(&(bank, addr)),
+            None => self.labels.iter().find(|((_, x), _)| *x == addr).map(|(_, name)| name),
+        }.map(String::as_str)
+    }
+
+    /// Bank and address of a label.
+    pub fn address(&self, name: &str) -> Option<(u16, u16)> {
+        self.names.get(name).copied()
+    }
+
+    /// `addr` followed by its label, if any.
+    pub fn format(&self, addr: u16, bank: Option<u16>) -> String {
+        match self.label(addr, bank) {
+            Some(label) => format!("{label} ({addr:#06X})"),
+            None => format!("{addr:#06X}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE: &str = "; File generated by rgblink
+00:0150 Main
+00:0150 Start
+01:4000 Bank1Start ; comment
+01:4000 Duplicate
+02:4000 Bank2Start
+garbage line
+0G:0000 Invalid
+;01:4100 Commented
+";
+
+    #[test]
+    fn parses_symbol_files() {
+        let symbols = Symbols::parse(FILE);
+        assert!(!symbols.is_empty());
+        assert_eq!(symbols.label(0x0150, Some(0)), Some("Main"));
+        assert_eq!(symbols.address("Start"), Some((0, 0x0150)));
+        assert_eq!(symbols.label(0x4000, Some(1)), Some("Bank1Start"));
+        assert_eq!(symbols.label(0x4000, Some(2)), Some("Bank2Start"));
+        assert_eq!(symbols.label(0x4000, Some(3)), None);
+        assert_eq!(symbols.label(0x4000, None), Some("Bank1Start"));
+        assert_eq!(symbols.address("Invalid"), None);
+        assert_eq!(symbols.address("Commented"), None);
+        assert!(Symbols::parse("; nothing\n\n").is_empty());
+    }
+
+    #[test]
+    fn formats_addresses() {
+        let symbols = Symbols::parse(FILE);
+        assert_eq!(symbols.format(0x4000, Some(2)), "Bank2Start (0x4000)");
+        assert_eq!(symbols.format(0x4100, Some(2)), "0x4100");
+    }
+}
@@ -0,0 +1,66 @@
+use std::fmt::{Debug, Formatter};
+use std::path::Path;
+use std::sync::Arc;
+
+use image::imageops::FilterType;
+
+/// Stand-in for the Pocket Camera sensor: grayscale frames taken from a still image,
+/// or from every image of a directory, one per capture.
+#[derive(Clone)]
+pub struct Sensor {
+    frames: Arc<Vec<Vec<u8>>>,
+    next: usize,
+}
+
+impl Debug for Sensor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Sensor")
+            .field("frames", &self.frames.len())
+            .field("next", &self.next)
+            .finish()
+    }
+}
+
+impl Default for Sensor {
+    /// Horizontal gradient, for captures with no image loaded.
+    fn default() -> Self {
+        let frame = (0..Sensor::HEIGHT).flat_map(|_| (0..Sensor::WIDTH).map(|x| (x * 255 / (Sensor::WIDTH - 1)) as u8)).collect();
+        Self { frames: Arc::new(vec![frame]), next: 0 }
+    }
+}
+
+impl Sensor {
+    pub const WIDTH: usize = 128;
+    pub const HEIGHT: usize = 112;
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, image::ImageError> {
+        let path = path.as_ref();
+        let frames = if path.is_dir() {
+            let mut paths = std::fs::read_dir(path)?
+                .filter_map(|x| x.ok().map(|x| x.path()))
+                .filter(|x| x.is_file())
+                .collect::<Vec<_>>();
+            paths.sort();
+            paths.iter().filter_map(|x| Sensor::frame(x).map_err(|e| log::warn!("skipped {x:?}: {e:?}")).ok()).collect()
+        } else { vec![Sensor::frame(path)?] };
+        if frames.is_empty() {
+            return Err(std::io::Error::new(std::io::ErrorKind::NotFound, "no image found").into());
+        }
+        log::info!("camera loaded {} frame(s) from {path:?}", frames.len());
+        Ok(Self { frames: Arc::new(frames), next: 0 })
+    }
+
+    fn frame(path: &Path) -> Result<Vec<u8>, image::ImageError> {
+        let image = image::io::Reader::open(path)?.with_guessed_format()?.decode()?;
+        Ok(image.resize_to_fill(Sensor::WIDTH as u32, Sensor::HEIGHT as u32, FilterType::Triangle)
+            .to_luma8()
+            .into_raw())
+    }
+
+    /// Next frame, `WIDTH * HEIGHT` luminance values.
+    pub fn capture(&mut self) -> &[u8] {
+        let frame = self.next % self.frames.len();
+        self.next = frame + 1;
+        &self.frames[frame]
+    }
+}
@@ -19,6 +19,8 @@ impl Clock {
     }
 
     pub fn value(&self) -> u8 { self.inner }
+
+    pub fn set_value(&mut self, value: u8) { self.inner = value % self.round; }
 }
 
 pub struct Chrono {
@@ -27,6 +29,12 @@ pub struct Chrono {
     on: bool
 }
 
+impl Default for Chrono {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Chrono {
     pub fn new() -> Self {
         Self {
@@ -36,7 +44,7 @@ impl Chrono {
         }
     }
 
-    pub fn paused(&self) -> bool { self.on == false }
+    pub fn paused(&self) -> bool { !self.on }
 
     pub fn start(&mut self) {
         self.st = Instant::now();
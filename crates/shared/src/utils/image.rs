@@ -1,10 +1,15 @@
 use std::fmt::{Debug, Formatter};
+#[cfg(feature = "ui")]
 use std::io::Read;
+#[cfg(feature = "ui")]
 use std::path::Path;
 
+#[cfg(feature = "ui")]
 use egui::{ColorImage, Context, TextureHandle, TextureOptions};
+#[cfg(feature = "ui")]
 use egui_extras::image::{FitTo, load_svg_bytes_with_size};
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "ui")]
 use winit::window::Icon;
 
 pub type Image<const W: usize, const H: usize> = [[[u32; 3]; W]; H];
@@ -42,6 +47,7 @@ impl Debug for RawData {
     }
 }
 
+#[cfg(feature = "ui")]
 impl RawData {
     pub fn icon(&self) -> Option<Icon> {
         Icon::from_rgba(self.data.clone(), self.w as u32, self.h as u32).ok()
@@ -52,12 +58,14 @@ impl RawData {
     }
 }
 
+#[cfg(feature = "ui")]
 pub trait ImageLoader {
     fn load_image<S: Into<String>, P: AsRef<std::path::Path>>(&self, name: S, path: P) -> Option<(TextureHandle, RawData)>;
     fn load_svg<const W: u32, const H: u32>(&mut self, name: impl Into<String>, path: impl AsRef<Path>) -> TextureHandle;
     fn load_svg_bytes<const W: u32, const H: u32>(&mut self, name: impl Into<String>, bytes: &[u8]) -> TextureHandle;
 }
 
+#[cfg(feature = "ui")]
 impl ImageLoader for Context {
     fn load_image<S: Into<String>, P: AsRef<Path>>(&self, name: S, path: P) -> Option<(TextureHandle, RawData)> {
         load_image_from_path(path.as_ref()).ok()
@@ -71,11 +79,12 @@ impl ImageLoader for Context {
 
     fn load_svg_bytes<const W: u32, const H: u32>(&mut self, name: impl Into<String>, bytes: &[u8]) -> TextureHandle {
         let name = name.into();
-        let tex = load_svg_bytes_with_size(bytes, FitTo::Size(W, H)).expect(format!("could not load {}", name).as_str());
+        let tex = load_svg_bytes_with_size(bytes, FitTo::Size(W, H)).unwrap_or_else(|_| panic!("could not load {}", name));
         self.load_texture(name, tex, TextureOptions::LINEAR)
     }
 }
 
+#[cfg(feature = "ui")]
 pub fn load_image_from_path(path: &Path) -> Result<(RawData, egui::ColorImage), image::ImageError> {
     let image = image::io::Reader::open(path)?.with_guessed_format()?.decode()?;
     let [w, h] = [image.width() as usize, image.height() as usize];
@@ -89,6 +98,7 @@ pub fn load_image_from_path(path: &Path) -> Result<(RawData, egui::ColorImage),
         )))
 }
 
+#[cfg(feature = "ui")]
 pub fn load_svg_from_path<const W: u32, const H: u32>(path: &Path) -> Result<egui::ColorImage, String> {
     let buf = {
         let mut buf = vec![];
@@ -1,16 +1,19 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 
+#[cfg(feature = "ui")]
 use egui::Color32;
 use serde::{Deserialize, Serialize};
 
 pub mod image;
 pub mod clock;
 pub mod rtc;
+pub mod camera;
 pub mod convert;
 pub mod palette;
 pub mod serde_arrays;
 
+#[cfg(feature = "ui")]
 pub const DARK_BLACK: Color32 = Color32::from_rgb(0x23, 0x27, 0x2A);
 
 pub trait ToBox {
@@ -4,7 +4,9 @@ const GRAY_SCALE: [[u8; 3]; 4] = [[0xBF; 3], [0x7F; 3], [0x3F; 3], [0; 3]];
 const ORIGINAL: [[u8; 3]; 4] = [[224, 248, 208], [136, 192, 112], [52, 104, 86], [8, 24, 32]];
 
 #[derive(Debug, Deserialize, Serialize, Copy, Clone)]
+#[derive(Default)]
 pub enum Palette {
+    #[default]
     GrayScale,
     Original,
     Custom([[u8; 3]; 4]),
@@ -19,9 +21,6 @@ impl PartialEq for Palette {
     }
 }
 
-impl Default for Palette {
-    fn default() -> Self { Self::GrayScale }
-}
 
 impl Palette {
     pub fn color(&self, index: u8) -> [u8; 3] {
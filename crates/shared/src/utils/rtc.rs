@@ -2,6 +2,7 @@ use std::time::SystemTime;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Default)]
 pub struct Rtc {
     quartz: u16,
     s: u8,
@@ -14,25 +15,12 @@ pub struct Rtc {
     lh: u8,
     ldl: u8,
     ldh: u8,
+    /// Seconds the host clock moved since the save was written, caught up on the first tick
+    /// unless [`Rtc::ignore_host`] drops them.
+    #[serde(default, skip)]
+    lag: u64,
 }
 
-impl Default for Rtc {
-    fn default() -> Self {
-        Self {
-            quartz: 0,
-            s: 0,
-            m: 0,
-            h: 0,
-            dl: 0,
-            dh: 0,
-            ls: 0,
-            lm: 0,
-            lh: 0,
-            ldl: 0,
-            ldh: 0,
-        }
-    }
-}
 
 impl Rtc {
     const MAX_SECONDS: u64 = 44_236_800;
@@ -66,6 +54,9 @@ impl Rtc {
         self.latch();
     }
 
+    /// Only counts emulated time from now on, so that input movies replay identically.
+    pub fn ignore_host(&mut self) { self.lag = 0; }
+
     pub fn latch(&mut self) {
         self.ls = self.s & 0x3F;
         self.lm = self.m & 0x3F;
@@ -75,6 +66,7 @@ impl Rtc {
     }
 
     pub fn tick(&mut self, seconds: bool) {
+        if self.lag != 0 { self.catch_up(); }
         if self.dh & 0x40 != 0 { return; };
         if !seconds {
             if self.quartz == 32767 { self.quartz = 0; } else {
@@ -102,10 +94,21 @@ impl Rtc {
         self.dl = dl;
     }
 
+    fn catch_up(&mut self) {
+        let mut elapsed = std::mem::take(&mut self.lag);
+        if elapsed / Rtc::MAX_SECONDS > 0 { self.dh |= 0x80; }
+        elapsed %= Rtc::MAX_SECONDS;
+        if self.dh & 0x40 == 0 {
+            for _ in 0..elapsed { self.tick(true); }
+            log::info!("had {elapsed} seconds to catch up ! {self:#02X?}");
+        }
+    }
+
     pub fn serialize(&self) -> Vec<u8> {
         let epoch = SystemTime::now().duration_since(std::time::UNIX_EPOCH)
             .map(|x| x.as_secs())
-            .unwrap_or(0);
+            .unwrap_or(0)
+            .saturating_sub(self.lag);
         let mut ser = vec![self.s, self.m, self.h, self.dl, self.dh, self.ls, self.lm, self.lh, self.ldl, self.ldh];
         ser.extend(epoch.to_le_bytes());
         log::info!("serialized to {:0x?}", ser);
@@ -118,16 +121,146 @@ impl Rtc {
         let [ls, lm, lh, ldl, ldh] = raw[5..10] else { unreachable!() };
         let [e0, e1, e2, e3, e4, e5, e6, e7] = raw[10..18] else { unreachable!() };
         let epoch = u64::from_le_bytes([e0, e1, e2, e3, e4, e5, e6, e7]);
-        let mut elapsed = SystemTime::now().duration_since(std::time::UNIX_EPOCH)
+        let lag = SystemTime::now().duration_since(std::time::UNIX_EPOCH)
             .map(|x| x.as_secs()).unwrap_or(0).saturating_sub(epoch);
-        let mut rtc = Self { quartz: 0, s, m, h, dl, dh, ls, lm, lh, ldl, ldh };
+        let rtc = Self { quartz: 0, s, m, h, dl, dh, ls, lm, lh, ldl, ldh, lag };
         log::info!("deserialized rtc {rtc:#02X?}");
-        if elapsed / Rtc::MAX_SECONDS > 0 { rtc.dh |= 0x80; }
-        elapsed %= Rtc::MAX_SECONDS;
-        if dh & 0x40 == 0 {
-            for _ in 0..elapsed { rtc.tick(true); }
-            log::info!("had {elapsed} seconds to catch up ! {rtc:#02X?}");
-        }
         Some(rtc)
     }
 }
+
+/// HuC3 clock, counting minutes since midnight and days on 12 bits.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MinuteClock {
+    quartz: u16,
+    s: u8,
+    minutes: u16,
+    days: u16,
+    /// Same as [`Rtc`], seconds the host clock moved since the save was written.
+    #[serde(default, skip)]
+    lag: u64,
+}
+
+impl MinuteClock {
+    const MINUTES: u16 = 24 * 60;
+    const DAYS: u16 = 0xFFF;
+
+    pub fn minutes(&self) -> (u16, u16) { (self.minutes, self.days) }
+
+    pub fn set(&mut self, minutes: u16, days: u16) {
+        self.quartz = 0;
+        self.s = 0;
+        self.minutes = minutes % Self::MINUTES;
+        self.days = days & Self::DAYS;
+    }
+
+    pub fn ignore_host(&mut self) { self.lag = 0; }
+
+    pub fn tick(&mut self) {
+        if self.lag != 0 { self.catch_up(); }
+        if self.quartz == 32767 { self.quartz = 0; } else {
+            self.quartz += 1;
+            return;
+        }
+        self.seconds(1);
+    }
+
+    fn seconds(&mut self, elapsed: u64) {
+        let seconds = self.s as u64 + elapsed;
+        let minutes = self.minutes as u64 + seconds / 60;
+        let days = self.days as u64 + minutes / Self::MINUTES as u64;
+        self.s = (seconds % 60) as u8;
+        self.minutes = (minutes % Self::MINUTES as u64) as u16;
+        self.days = days as u16 & Self::DAYS;
+    }
+
+    fn catch_up(&mut self) {
+        let elapsed = std::mem::take(&mut self.lag);
+        self.seconds(elapsed);
+        log::info!("had {elapsed} seconds to catch up ! {self:?}");
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let epoch = SystemTime::now().duration_since(std::time::UNIX_EPOCH)
+            .map(|x| x.as_secs())
+            .unwrap_or(0)
+            .saturating_sub(self.lag);
+        let mut ser = vec![self.s];
+        ser.extend(self.minutes.to_le_bytes());
+        ser.extend(self.days.to_le_bytes());
+        ser.extend(epoch.to_le_bytes());
+        ser
+    }
+
+    /// Also reads the [`Rtc`] previously saved by HuC3 cartridges.
+    pub fn deserialize(raw: Vec<u8>) -> Option<Self> {
+        if raw.len() == 18 { return Rtc::deserialize(raw).map(Self::from); }
+        if raw.len() != 13 { return None; }
+        let [s, m0, m1, d0, d1] = raw[0..5] else { unreachable!() };
+        let [e0, e1, e2, e3, e4, e5, e6, e7] = raw[5..13] else { unreachable!() };
+        let epoch = u64::from_le_bytes([e0, e1, e2, e3, e4, e5, e6, e7]);
+        let lag = SystemTime::now().duration_since(std::time::UNIX_EPOCH)
+            .map(|x| x.as_secs()).unwrap_or(0).saturating_sub(epoch);
+        let minutes = u16::from_le_bytes([m0, m1]) % Self::MINUTES;
+        let days = u16::from_le_bytes([d0, d1]) & Self::DAYS;
+        Some(Self { quartz: 0, s: s % 60, minutes, days, lag })
+    }
+}
+
+impl From<Rtc> for MinuteClock {
+    fn from(rtc: Rtc) -> Self {
+        let days = rtc.dl as u16 | (rtc.dh as u16 & 1) << 8;
+        Self { quartz: rtc.quartz, s: rtc.s, minutes: rtc.h as u16 * 60 + rtc.m as u16, days, lag: rtc.lag }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Clock saved an hour ago at 12:00:00.
+    fn saved() -> Vec<u8> {
+        let mut raw = vec![0, 0, 12, 0, 0, 0, 0, 12, 0, 0];
+        let epoch = SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs() - 3600;
+        raw.extend(epoch.to_le_bytes());
+        raw
+    }
+
+    #[test]
+    fn catches_up_with_the_host() {
+        let mut rtc = Rtc::deserialize(saved()).unwrap();
+        rtc.tick(false);
+        rtc.latch();
+        assert_eq!(rtc.read(0xA), 13);
+    }
+
+    #[test]
+    fn emulated_time() {
+        let mut rtc = Rtc::deserialize(saved()).unwrap();
+        rtc.ignore_host();
+        rtc.tick(false);
+        rtc.latch();
+        assert_eq!(rtc.read(0xA), 12);
+    }
+
+    #[test]
+    fn counts_days_on_12_bits() {
+        let mut clock = MinuteClock::default();
+        clock.set(24 * 60 - 1, 0x1FF);
+        clock.seconds(60);
+        assert_eq!(clock.minutes(), (0, 0x200));
+        clock.set(24 * 60 - 1, 0xFFF);
+        clock.seconds(60);
+        assert_eq!(clock.minutes(), (0, 0));
+    }
+
+    #[test]
+    fn reads_the_older_huc3_saves() {
+        let mut clock = MinuteClock::deserialize(saved()).unwrap();
+        clock.ignore_host();
+        assert_eq!(clock.minutes(), (12 * 60, 0));
+        let mut clock = MinuteClock::deserialize(clock.serialize()).unwrap();
+        clock.ignore_host();
+        assert_eq!(clock.minutes(), (12 * 60, 0));
+    }
+}
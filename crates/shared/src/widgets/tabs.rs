@@ -25,7 +25,7 @@ impl<'ui, E: PartialEq + Eq + Hash + Copy + Clone + Tab> Tabs<'ui, E> {
                         ui.spacing_mut().button_padding.y = 8.;
                         if ui.selectable_label(name == current, name.name())
                             .clicked() {
-                            *current = name.clone();
+                            *current = *name;
                         }
                     }
                 });